@@ -0,0 +1,237 @@
+//! Bidi property data ("BiDi"), as in `ubidi.icu`.
+
+use std::convert::TryFrom;
+use std::io::prelude::*;
+use error::IcuError;
+use trie::Trie2;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots
+const IX_INDEX_TOP: usize = 0;
+const IX_LENGTH: usize = 1;
+const IX_TRIE_SIZE: usize = 2;
+const IX_MIRROR_LENGTH: usize = 3;
+const IX_JG_START: usize = 4;
+const IX_JG_LIMIT: usize = 5;
+const IX_JG_START2: usize = 6;
+const IX_JG_LIMIT2: usize = 7;
+const IX_MAX_VALUES: usize = 15;
+const IX_TOP: usize = 16;
+
+/// The bidi class is in the low bits of each trie value.
+const CLASS_MASK: u32 = 0x1f;
+
+/// The indexes and trie of a bidi property data file.
+///
+/// The data starts with an `indexes[]` array whose first entry is its own
+/// length, followed by the trie, the mirroring pairs as 32-bit words, and
+/// two arrays of joining groups, one byte per code point, for the code
+/// points from each array's start up to its limit.
+#[derive(Clone, Debug)]
+pub struct BiDiData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: Vec<u32>,
+    trie: Trie2,
+}
+
+impl<R> BiDiData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<BiDiData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::BiDi)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let count = EndianReader::read_u32(&mut reader)?;
+        if (count as usize) < IX_TOP {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        if base + 4 * u64::from(count) > len {
+            return Err(IcuError::Malformed("indexes run past the end of the data"));
+        }
+        let mut indexes = Vec::with_capacity(count as usize);
+        indexes.push(count);
+        for _ in 1..count {
+            indexes.push(EndianReader::read_u32(&mut reader)?);
+        }
+
+        let jg_length = indexes[IX_JG_LIMIT].checked_sub(indexes[IX_JG_START]);
+        let jg2_length = indexes[IX_JG_LIMIT2].checked_sub(indexes[IX_JG_START2]);
+        let (jg_length, jg2_length) = match (jg_length, jg2_length) {
+            (Some(jg), Some(jg2)) => (u64::from(jg), u64::from(jg2)),
+            _ => return Err(IcuError::Malformed("joining group limit before its start")),
+        };
+        let sections = 4 * u64::from(count)
+            + u64::from(indexes[IX_TRIE_SIZE])
+            + 4 * u64::from(indexes[IX_MIRROR_LENGTH])
+            + jg_length
+            + jg2_length;
+        if sections > u64::from(indexes[IX_LENGTH]) {
+            return Err(IcuError::Malformed("data overruns its length"));
+        }
+        if base + u64::from(indexes[IX_LENGTH]) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        let trie = Trie2::try_init(&mut reader)?;
+        if trie.serialized_length() > indexes[IX_TRIE_SIZE] {
+            return Err(IcuError::Malformed("trie overruns its space"));
+        }
+
+        Ok(BiDiData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            trie,
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The trie of bidi properties; its values hold the bidi class in
+    /// their low five bits, and the mirroring and joining properties above.
+    pub fn trie(&self) -> &Trie2 {
+        &self.trie
+    }
+
+    /// The bidi class of `c`, as ICU's `UCharDirection` numbers it: 0 for
+    /// L, 1 for R, 2 for EN and so on.
+    pub fn bidi_class(&self, c: char) -> u8 {
+        (self.trie.get(c) & CLASS_MASK) as u8
+    }
+
+    /// The byte offset of the trie from the start of the data, just past
+    /// `indexes[]`.
+    pub fn trie_offset(&self) -> u32 {
+        4 * self.indexes[IX_INDEX_TOP]
+    }
+
+    /// The number of bytes set aside for the trie.
+    pub fn trie_size(&self) -> u32 {
+        self.indexes[IX_TRIE_SIZE]
+    }
+
+    /// The number of 32-bit mirroring pairs.
+    pub fn mirror_length(&self) -> u32 {
+        self.indexes[IX_MIRROR_LENGTH]
+    }
+
+    /// The code points the first joining group array covers, from its
+    /// start up to its limit.
+    pub fn jg_range(&self) -> (u32, u32) {
+        (self.indexes[IX_JG_START], self.indexes[IX_JG_LIMIT])
+    }
+
+    /// The code points the second joining group array covers.
+    pub fn jg2_range(&self) -> (u32, u32) {
+        (self.indexes[IX_JG_START2], self.indexes[IX_JG_LIMIT2])
+    }
+
+    /// The maximum values of the bidi class and joining properties, packed
+    /// as in the trie values.
+    pub fn max_values(&self) -> u32 {
+        self.indexes[IX_MAX_VALUES]
+    }
+
+    /// The size of the data in bytes, not counting the header.
+    pub fn data_size(&self) -> u32 {
+        self.indexes[IX_LENGTH]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bidi::BiDiData;
+    use error::IcuError;
+    use std::io::Cursor;
+    use trie::fixtures::{be_u32s, bmp_trie, bmp_trie_length};
+    use version::Version;
+    use {write_header, DataFormat, Order};
+
+    const TRIE_SIZE: u32 = bmp_trie_length(false);
+
+    /// A 16-bit trie giving the digits the bidi class EN (2) and '(' ON (10)
+    /// with its mirrored flag.
+    fn trie() -> Vec<u8> {
+        bmp_trie(false, &[(0x30..0x3a, 2), (0x28..0x29, 0x1000 | 10)])
+    }
+
+    /// `indexes`, the trie, two mirroring pairs and a four-byte joining
+    /// group array.
+    fn ubidi(indexes: &[u32]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::BiDi;
+        write_header(&mut bytes, format, [2, 2, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend(be_u32s(indexes));
+        bytes.extend(trie());
+        bytes.extend_from_slice(&[0, 0, 0, 0x28, 0, 0, 0, 0x29, 1, 2, 3, 4]);
+        Cursor::new(bytes)
+    }
+
+    fn indexes() -> Vec<u32> {
+        let length = 64 + TRIE_SIZE + 8 + 4;
+        vec![16, length, TRIE_SIZE, 2, 0x620, 0x624, 0x10ac0, 0x10ac0, 0, 0, 0, 0, 0, 0, 0, 0x3f]
+    }
+
+    #[test]
+    fn try_init_reads_indexes_and_trie() {
+        let b = BiDiData::try_init(ubidi(&indexes())).expect("Failed to read BiDi");
+        assert_eq!(b.version(), Some(Version::Unicode10_0((10, 0, 0, 0))));
+        assert_eq!(b.bidi_class('a'), 0);
+        assert_eq!(b.bidi_class('7'), 2);
+        assert_eq!(b.bidi_class('('), 10);
+        assert_eq!(b.trie().get('('), 0x100a);
+        assert_eq!(b.trie_offset(), 64);
+        assert_eq!(b.trie_size(), TRIE_SIZE);
+        assert_eq!(b.mirror_length(), 2);
+        assert_eq!(b.jg_range(), (0x620, 0x624));
+        assert_eq!(b.jg2_range(), (0x10ac0, 0x10ac0));
+        assert_eq!(b.max_values(), 0x3f);
+        assert_eq!(b.data_size(), 64 + TRIE_SIZE + 12);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_indexes() {
+        let mut short = indexes();
+        short.truncate(15);
+        short[0] = 15;
+        match BiDiData::try_init(ubidi(&short)) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bad = indexes();
+        bad[5] = 0x61f; // joining group limit before its start
+        assert!(BiDiData::try_init(ubidi(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[3] += 1; // mirrors past the data's length
+        assert!(BiDiData::try_init(ubidi(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[1] += 4; // past the end of the file
+        assert!(BiDiData::try_init(ubidi(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[2] -= 2; // too little space for the trie
+        assert!(BiDiData::try_init(ubidi(&bad)).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! Break iteration rules ("Brk "), as in `word.brk` and `line.brk`, the
+//! compiled state tables of ICU's rule-based break iterators.
+
+use std::convert::TryFrom;
+use std::io::{SeekFrom, prelude::*};
+use error::IcuError;
+use trie::Trie2;
+use util::utf16_to_string;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+/// The first word of an `RBBIDataHeader`.
+const MAGIC: u32 = 0xb1a0;
+
+// RBBIDataHeader words
+const IX_MAGIC: usize = 0;
+const IX_LENGTH: usize = 2;
+const IX_CAT_COUNT: usize = 3;
+// byte offsets from the start of the data, each followed by its section's
+// length in bytes
+const IX_FTABLE: usize = 4;
+const IX_RTABLE: usize = 6;
+const IX_SFTABLE: usize = 8;
+const IX_SRTABLE: usize = 10;
+const IX_TRIE: usize = 12;
+const IX_RULE_SOURCE: usize = 14;
+const IX_STATUS_TABLE: usize = 16;
+/// The header's words, with six reserved ones at the end.
+const IX_COUNT: usize = 24;
+
+/// The bytes before a state table's rows: its state count, row length,
+/// flags and a reserved word.
+const STATE_TABLE_HEADER_SIZE: u32 = 16;
+
+/// Trie values with this bit set are for characters a dictionary breaks.
+const DICTIONARY_FLAG: u32 = 0x4000;
+
+/// Which of a rule set's four state tables to look at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateTableKind {
+    Forward,
+    Reverse,
+    SafeForward,
+    SafeReverse,
+}
+
+impl StateTableKind {
+    /// Where the table sits in `BreakData`'s state tables.
+    fn index(self) -> usize {
+        match self {
+            StateTableKind::Forward => 0,
+            StateTableKind::Reverse => 1,
+            StateTableKind::SafeForward => 2,
+            StateTableKind::SafeReverse => 3,
+        }
+    }
+
+    /// The header slot of the table's offset, which its length follows.
+    fn slot(self) -> usize {
+        match self {
+            StateTableKind::Forward => IX_FTABLE,
+            StateTableKind::Reverse => IX_RTABLE,
+            StateTableKind::SafeForward => IX_SFTABLE,
+            StateTableKind::SafeReverse => IX_SRTABLE,
+        }
+    }
+}
+
+/// Where one of the state tables is, and the counts from its header.
+///
+/// Each of the `num_states` rows is `row_len` bytes of 16-bit values: the
+/// accepting, lookahead and tag fields, a reserved one, and then the next
+/// state for each character category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateTable {
+    /// The byte offset of the table from the start of the data.
+    pub offset: u32,
+    /// The number of bytes set aside for the table.
+    pub length: u32,
+    pub num_states: u32,
+    pub row_len: u32,
+    /// `RBBI_LOOKAHEAD_HARD_BREAK` (1) and `RBBI_BOF_REQUIRED` (2).
+    pub flags: u32,
+}
+
+/// The header, category trie and rule source of a compiled break rule set.
+///
+/// The data starts with an `RBBIDataHeader`: a magic number, the format
+/// version again, the data's length and the number of character
+/// categories, then the byte offset and length of each section, counted
+/// from the start of the data. The sections are the forward, reverse,
+/// safe forward and safe reverse state tables, the trie mapping characters
+/// to categories, the rules as UTF-16 and the rule status values. Running
+/// the state tables is left to the caller.
+#[derive(Clone, Debug)]
+pub struct BreakData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    header: [u32; IX_COUNT],
+    state_tables: [Option<StateTable>; 4],
+    trie: Trie2,
+    rule_source: String,
+    statuses: Vec<i32>,
+}
+
+impl<R> BreakData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<BreakData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::BreakIteration)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4 * IX_COUNT as u64)?;
+        let mut header = [0; IX_COUNT];
+        for word in header.iter_mut() {
+            *word = EndianReader::read_u32(&mut reader)?;
+        }
+        // the format version is copied into the header as bytes
+        reader.seek(SeekFrom::Start(base + 4))?;
+        let mut format_version = [0; 4];
+        reader.read_exact(&mut format_version)?;
+        if header[IX_MAGIC] != MAGIC || format_version[0] != 4 {
+            return Err(IcuError::Malformed("not an RBBIDataHeader"));
+        }
+        let data_length = header[IX_LENGTH];
+        if data_length < 4 * IX_COUNT as u32 {
+            return Err(IcuError::Malformed("data is shorter than its header"));
+        }
+        if base + u64::from(data_length) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+        for slot in (IX_FTABLE..=IX_STATUS_TABLE).step_by(2) {
+            let end = u64::from(header[slot]) + u64::from(header[slot + 1]);
+            if end > u64::from(data_length) {
+                return Err(IcuError::Malformed("section runs past the end of the data"));
+            }
+        }
+
+        let mut state_tables = [None; 4];
+        let kinds = [
+            StateTableKind::Forward,
+            StateTableKind::Reverse,
+            StateTableKind::SafeForward,
+            StateTableKind::SafeReverse,
+        ];
+        for &kind in &kinds {
+            let (offset, length) = (header[kind.slot()], header[kind.slot() + 1]);
+            if length == 0 {
+                continue;
+            }
+            if length < STATE_TABLE_HEADER_SIZE {
+                return Err(IcuError::Malformed("state table is shorter than its header"));
+            }
+            reader.seek(SeekFrom::Start(base + u64::from(offset)))?;
+            let num_states = EndianReader::read_u32(&mut reader)?;
+            let row_len = EndianReader::read_u32(&mut reader)?;
+            let flags = EndianReader::read_u32(&mut reader)?;
+            let rows = u64::from(num_states) * u64::from(row_len);
+            if u64::from(STATE_TABLE_HEADER_SIZE) + rows > u64::from(length) {
+                return Err(IcuError::Malformed("state table rows overrun the table"));
+            }
+            state_tables[kind.index()] = Some(StateTable {
+                offset,
+                length,
+                num_states,
+                row_len,
+                flags,
+            });
+        }
+
+        reader.seek(SeekFrom::Start(base + u64::from(header[IX_TRIE])))?;
+        let trie = Trie2::try_init(&mut reader)?;
+        if trie.is_32_bit() {
+            return Err(IcuError::Malformed("category trie isn't 16-bit"));
+        }
+        if trie.serialized_length() > header[IX_TRIE + 1] {
+            return Err(IcuError::Malformed("trie overruns its space"));
+        }
+
+        // the rules are NUL-terminated, then padded
+        reader.seek(SeekFrom::Start(base + u64::from(header[IX_RULE_SOURCE])))?;
+        let mut units = Vec::new();
+        for _ in 0..header[IX_RULE_SOURCE + 1] / 2 {
+            match EndianReader::read_u16(&mut reader)? {
+                0 => break,
+                unit => units.push(unit),
+            }
+        }
+        let rule_source = utf16_to_string(&units)?;
+
+        reader.seek(SeekFrom::Start(base + u64::from(header[IX_STATUS_TABLE])))?;
+        let mut statuses = Vec::with_capacity(header[IX_STATUS_TABLE + 1] as usize / 4);
+        for _ in 0..header[IX_STATUS_TABLE + 1] / 4 {
+            statuses.push(EndianReader::read_u32(&mut reader)? as i32);
+        }
+
+        Ok(BreakData {
+            reader,
+            header_size,
+            data_version,
+            header,
+            state_tables,
+            trie,
+            rule_source,
+            statuses,
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The size of the data in bytes, from the start of the
+    /// `RBBIDataHeader`.
+    pub fn data_size(&self) -> u32 {
+        self.header[IX_LENGTH]
+    }
+
+    /// The number of character categories, which is the number of next
+    /// states in each state table row.
+    pub fn category_count(&self) -> u32 {
+        self.header[IX_CAT_COUNT]
+    }
+
+    /// The state table `kind`, unless the rules leave it out.
+    pub fn state_table(&self, kind: StateTableKind) -> Option<StateTable> {
+        self.state_tables[kind.index()]
+    }
+
+    /// The trie mapping characters to their categories, with
+    /// `DICTIONARY_FLAG` (0x4000) set for characters a dictionary breaks.
+    pub fn trie(&self) -> &Trie2 {
+        &self.trie
+    }
+
+    /// The character category of `c`.
+    pub fn category(&self, c: char) -> u16 {
+        (self.trie.get(c) & !DICTIONARY_FLAG) as u16
+    }
+
+    /// Whether runs of `c` are broken with a dictionary rather than the
+    /// state tables.
+    pub fn is_dictionary_char(&self, c: char) -> bool {
+        self.trie.get(c) & DICTIONARY_FLAG != 0
+    }
+
+    /// The source of the rules, as they were compiled, comments and all.
+    pub fn rule_source(&self) -> &str {
+        &self.rule_source
+    }
+
+    /// The rule status values, which the state tables' tag fields index
+    /// groups of: a count followed by that many values.
+    pub fn rule_statuses(&self) -> &[i32] {
+        &self.statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use brk::{BreakData, StateTable, StateTableKind};
+    use error::IcuError;
+    use std::io::Cursor;
+    use trie::fixtures::{be_u16s, be_u32s, bmp_trie, bmp_trie_length};
+    use version::Version;
+    use {open_any, write_header, DataFormat, Order};
+
+    const TRIE_SIZE: u32 = bmp_trie_length(false);
+    const RULES: &str = "$L = [a-z];\n$L+;";
+
+    /// A 16-bit trie giving the letters category 3 and the digits category 4
+    /// with the dictionary flag.
+    fn trie() -> Vec<u8> {
+        bmp_trie(false, &[(0x61..0x7b, 3), (0x30..0x3a, 0x4000 | 4)])
+    }
+
+    /// The `RBBIDataHeader` of `brk`: five categories, forward and reverse
+    /// tables of two 18-byte rows each at 96 and 152, no safe tables, then
+    /// the trie at 208, the rules at 4896 and four status values at 4936.
+    fn header() -> [u32; 24] {
+        let mut header = [0; 24];
+        header[..4].copy_from_slice(&[0xb1a0, 0x0400_0000, 4952, 5]);
+        header[4..18].copy_from_slice(&[
+            96, 56, 152, 56, 208, 0, 208, 0, 208, TRIE_SIZE, 4896, 40, 4936, 16,
+        ]);
+        header
+    }
+
+    /// Break rules with the data header `header`, in the sections `header()`
+    /// lays out.
+    fn brk(header: &[u32]) -> Cursor<Vec<u8>> {
+        let mut words = header.to_vec();
+        for flags in &[0, 1] {
+            // a state table: two states of 18-byte rows, and padding
+            words.extend_from_slice(&[2, 18, *flags, 0]);
+            words.extend_from_slice(&[0; 10]);
+        }
+        let mut bytes = Vec::new();
+        let format = DataFormat::BreakIteration;
+        write_header(&mut bytes, format, [4, 0, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend(be_u32s(&words));
+        bytes.extend(trie());
+        let rules: Vec<u16> = RULES.encode_utf16().chain(vec![0; 40 / 2 - RULES.len()]).collect();
+        bytes.extend(be_u16s(&rules));
+        for status in &[1, 0, 1, 200] {
+            bytes.extend_from_slice(&[0, 0, 0, *status as u8]);
+        }
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn try_init_reads_the_header_and_sections() {
+        let b = BreakData::try_init(brk(&header())).expect("Failed to read break rules");
+        assert_eq!(b.version(), Some(Version::Unicode10_0((10, 0, 0, 0))));
+        assert_eq!(b.data_size(), 4952);
+        assert_eq!(b.category_count(), 5);
+        assert_eq!(
+            b.state_table(StateTableKind::Forward),
+            Some(StateTable {
+                offset: 96,
+                length: 56,
+                num_states: 2,
+                row_len: 18,
+                flags: 0,
+            })
+        );
+        let reverse = b.state_table(StateTableKind::Reverse).unwrap();
+        assert_eq!((reverse.offset, reverse.flags), (152, 1));
+        assert_eq!(b.state_table(StateTableKind::SafeForward), None);
+        assert_eq!(b.state_table(StateTableKind::SafeReverse), None);
+
+        assert_eq!(b.category('q'), 3);
+        assert_eq!(b.category('7'), 4);
+        assert_eq!(b.category('Q'), 0);
+        assert!(b.is_dictionary_char('7') && !b.is_dictionary_char('q'));
+        assert_eq!(b.trie().get('7'), 0x4004);
+        assert_eq!(b.rule_source(), RULES);
+        assert_eq!(b.rule_statuses(), [1, 0, 1, 200]);
+
+        let any = open_any(brk(&header())).unwrap();
+        assert_eq!(any.format(), DataFormat::BreakIteration);
+        assert!(any.as_break_iteration().is_some());
+    }
+
+    #[test]
+    fn try_init_rejects_bad_headers() {
+        let mut bad = header();
+        bad[0] = 0xb1a1; // not the magic number
+        match BreakData::try_init(brk(&bad)) {
+            Err(IcuError::Malformed(_)) => {}
+            other => panic!("expected Malformed, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bad = header();
+        bad[1] = 0x0300_0000; // a format 3 RBBIDataHeader
+        assert!(BreakData::try_init(brk(&bad)).is_err());
+
+        let mut bad = header();
+        bad[2] += 4; // past the end of the file
+        assert!(BreakData::try_init(brk(&bad)).is_err());
+
+        let mut bad = header();
+        bad[17] += 4; // status values past the end of the data
+        assert!(BreakData::try_init(brk(&bad)).is_err());
+
+        let mut bad = header();
+        bad[5] = 48; // too short for two rows
+        assert!(BreakData::try_init(brk(&bad)).is_err());
+
+        let mut bad = header();
+        bad[13] -= 2; // too little space for the trie
+        assert!(BreakData::try_init(brk(&bad)).is_err());
+    }
+}
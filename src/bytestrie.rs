@@ -0,0 +1,549 @@
+//! ICU's `BytesTrie` and `UCharsTrie`, the compact string tries that map
+//! byte and UTF-16 strings to integer values in dictionaries and other
+//! data.
+
+/// How far a string matched in a trie.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrieResult {
+    /// The string isn't in the trie, nor the start of any string that is.
+    NoMatch,
+    /// The string is the start of strings in the trie, but isn't one.
+    NoValue,
+    /// The string is in the trie, and no longer string starts with it.
+    FinalValue,
+    /// The string is in the trie, and longer strings start with it.
+    IntermediateValue,
+}
+
+impl TrieResult {
+    /// Whether the string so far is in the trie.
+    pub fn has_value(self) -> bool {
+        self == TrieResult::FinalValue || self == TrieResult::IntermediateValue
+    }
+
+    /// Whether the string so far is in the trie or starts strings that are.
+    pub fn matches(self) -> bool {
+        self != TrieResult::NoMatch
+    }
+}
+
+/// A walk through an ICU `BytesTrie`, a byte at a time.
+///
+/// Node lead bytes below 0x10 are branches, 0x10..0x20 linear matches of
+/// one to sixteen bytes and the rest values, final if their low bit is
+/// set, with the value's top bits in the rest of the lead. A branch node
+/// binary searches on a split byte down to a list of at most five bytes,
+/// each followed by its final value or the delta to the rest of its
+/// subtrie, except the last, whose subtrie follows directly.
+#[derive(Clone, Debug)]
+pub struct BytesTrie<'a> {
+    bytes: &'a [u8],
+    /// Where the next node or match byte is, or none once a match failed.
+    pos: Option<usize>,
+    /// The bytes left in the current linear match, less one.
+    remaining_match_length: i32,
+}
+
+impl<'a> BytesTrie<'a> {
+    const MIN_LINEAR_MATCH: u8 = 0x10;
+    const MIN_VALUE_LEAD: u8 = 0x20;
+    const MIN_ONE_BYTE_VALUE_LEAD: u8 = 0x10;
+    const MIN_TWO_BYTE_VALUE_LEAD: u8 = 0x51;
+    const MIN_THREE_BYTE_VALUE_LEAD: u8 = 0x6c;
+    const FOUR_BYTE_VALUE_LEAD: u8 = 0x7e;
+    const MIN_TWO_BYTE_DELTA_LEAD: u8 = 0xc0;
+    const MIN_THREE_BYTE_DELTA_LEAD: u8 = 0xf0;
+    const FOUR_BYTE_DELTA_LEAD: u8 = 0xfe;
+    const MAX_BRANCH_LINEAR_SUB_NODE_LENGTH: usize = 5;
+
+    /// Starts a walk at the root of the serialized trie `bytes`.
+    pub fn new(bytes: &'a [u8]) -> BytesTrie<'a> {
+        BytesTrie {
+            bytes,
+            pos: Some(0),
+            remaining_match_length: -1,
+        }
+    }
+
+    /// Matches one more byte. Once a byte fails to match, so do all the
+    /// ones after it.
+    pub fn next(&mut self, b: u8) -> TrieResult {
+        let result = match self.pos {
+            Some(pos) => self.next_at(pos, b),
+            None => None,
+        };
+        result.unwrap_or_else(|| {
+            self.pos = None;
+            TrieResult::NoMatch
+        })
+    }
+
+    /// The value of the string matched so far, if it's in the trie.
+    pub fn value(&self) -> Option<i32> {
+        let pos = self.pos?;
+        let lead = *self.bytes.get(pos)?;
+        if self.remaining_match_length >= 0 || lead < Self::MIN_VALUE_LEAD {
+            return None;
+        }
+        self.read_value(pos + 1, lead >> 1).map(|(value, _)| value)
+    }
+
+    fn next_at(&mut self, mut pos: usize, b: u8) -> Option<TrieResult> {
+        if self.remaining_match_length >= 0 {
+            return self.match_linear(pos, b, self.remaining_match_length);
+        }
+        loop {
+            let node = *self.bytes.get(pos)?;
+            pos += 1;
+            if node < Self::MIN_LINEAR_MATCH {
+                return self.branch_next(pos, node, b);
+            } else if node < Self::MIN_VALUE_LEAD {
+                return self.match_linear(pos, b, i32::from(node - Self::MIN_LINEAR_MATCH));
+            } else if node & 1 != 0 {
+                // a final value: nothing more matches
+                return None;
+            } else {
+                pos = Self::skip_value(pos, node);
+            }
+        }
+    }
+
+    /// Matches `b` against the next byte of a linear match with `length`
+    /// bytes left after it.
+    fn match_linear(&mut self, pos: usize, b: u8, length: i32) -> Option<TrieResult> {
+        if *self.bytes.get(pos)? != b {
+            return None;
+        }
+        self.remaining_match_length = length - 1;
+        self.pos = Some(pos + 1);
+        if length == 0 {
+            Some(self.result_at(pos + 1))
+        } else {
+            Some(TrieResult::NoValue)
+        }
+    }
+
+    fn branch_next(&mut self, mut pos: usize, node: u8, b: u8) -> Option<TrieResult> {
+        let mut length = usize::from(node);
+        if length == 0 {
+            length = usize::from(*self.bytes.get(pos)?);
+            pos += 1;
+        }
+        length += 1;
+        // a binary search down to a short linear list
+        while length > Self::MAX_BRANCH_LINEAR_SUB_NODE_LENGTH {
+            let split = *self.bytes.get(pos)?;
+            pos += 1;
+            if b < split {
+                length >>= 1;
+                pos = self.jump_by_delta(pos)?;
+            } else {
+                length -= length >> 1;
+                pos = self.skip_delta(pos)?;
+            }
+        }
+        while length > 1 {
+            let key = *self.bytes.get(pos)?;
+            pos += 1;
+            if key == b {
+                let node = *self.bytes.get(pos)?;
+                if node & 1 != 0 {
+                    self.pos = Some(pos);
+                    return Some(TrieResult::FinalValue);
+                }
+                // a non-final value is the delta to the rest of the trie
+                let (delta, next) = self.read_value(pos + 1, node >> 1)?;
+                let pos = next.checked_add(delta as usize)?;
+                self.pos = Some(pos);
+                return Some(self.result_at(pos));
+            }
+            length -= 1;
+            pos = Self::skip_value(pos + 1, *self.bytes.get(pos)?);
+        }
+        if *self.bytes.get(pos)? == b {
+            self.pos = Some(pos + 1);
+            Some(self.result_at(pos + 1))
+        } else {
+            None
+        }
+    }
+
+    /// The result of a match ending before the node at `pos`.
+    fn result_at(&self, pos: usize) -> TrieResult {
+        match self.bytes.get(pos) {
+            Some(&node) if node >= Self::MIN_VALUE_LEAD && node & 1 != 0 => TrieResult::FinalValue,
+            Some(&node) if node >= Self::MIN_VALUE_LEAD => TrieResult::IntermediateValue,
+            _ => TrieResult::NoValue,
+        }
+    }
+
+    /// Reads the value whose lead, shifted right by one, is `lead`, from
+    /// the bytes after the lead at `pos`, and the position after it.
+    fn read_value(&self, pos: usize, lead: u8) -> Option<(i32, usize)> {
+        let byte = |i: usize| self.bytes.get(pos + i).map(|&b| i32::from(b));
+        Some(if lead < Self::MIN_TWO_BYTE_VALUE_LEAD {
+            (i32::from(lead) - i32::from(Self::MIN_ONE_BYTE_VALUE_LEAD), pos)
+        } else if lead < Self::MIN_THREE_BYTE_VALUE_LEAD {
+            (i32::from(lead - Self::MIN_TWO_BYTE_VALUE_LEAD) << 8 | byte(0)?, pos + 1)
+        } else if lead < Self::FOUR_BYTE_VALUE_LEAD {
+            let high = i32::from(lead - Self::MIN_THREE_BYTE_VALUE_LEAD) << 16;
+            (high | byte(0)? << 8 | byte(1)?, pos + 2)
+        } else if lead == Self::FOUR_BYTE_VALUE_LEAD {
+            (byte(0)? << 16 | byte(1)? << 8 | byte(2)?, pos + 3)
+        } else {
+            (byte(0)? << 24 | byte(1)? << 16 | byte(2)? << 8 | byte(3)?, pos + 4)
+        })
+    }
+
+    /// The position after the value with the lead byte `lead`, which is
+    /// just before `pos`.
+    fn skip_value(pos: usize, lead: u8) -> usize {
+        if lead < Self::MIN_TWO_BYTE_VALUE_LEAD << 1 {
+            pos
+        } else if lead < Self::MIN_THREE_BYTE_VALUE_LEAD << 1 {
+            pos + 1
+        } else if lead < Self::FOUR_BYTE_VALUE_LEAD << 1 {
+            pos + 2
+        } else {
+            pos + 3 + usize::from((lead >> 1) & 1)
+        }
+    }
+
+    fn jump_by_delta(&self, pos: usize) -> Option<usize> {
+        let byte = |i: usize| self.bytes.get(pos + i).map(|&b| usize::from(b));
+        let lead = *self.bytes.get(pos)?;
+        let (delta, next) = if lead < Self::MIN_TWO_BYTE_DELTA_LEAD {
+            (usize::from(lead), pos + 1)
+        } else if lead < Self::MIN_THREE_BYTE_DELTA_LEAD {
+            (usize::from(lead - Self::MIN_TWO_BYTE_DELTA_LEAD) << 8 | byte(1)?, pos + 2)
+        } else if lead < Self::FOUR_BYTE_DELTA_LEAD {
+            let high = usize::from(lead - Self::MIN_THREE_BYTE_DELTA_LEAD) << 16;
+            (high | byte(1)? << 8 | byte(2)?, pos + 3)
+        } else if lead == Self::FOUR_BYTE_DELTA_LEAD {
+            (byte(1)? << 16 | byte(2)? << 8 | byte(3)?, pos + 4)
+        } else {
+            (byte(1)? << 24 | byte(2)? << 16 | byte(3)? << 8 | byte(4)?, pos + 5)
+        };
+        next.checked_add(delta)
+    }
+
+    fn skip_delta(&self, pos: usize) -> Option<usize> {
+        let lead = *self.bytes.get(pos)?;
+        Some(if lead < Self::MIN_TWO_BYTE_DELTA_LEAD {
+            pos + 1
+        } else if lead < Self::MIN_THREE_BYTE_DELTA_LEAD {
+            pos + 2
+        } else if lead < Self::FOUR_BYTE_DELTA_LEAD {
+            pos + 3
+        } else {
+            pos + 4 + usize::from(lead & 1)
+        })
+    }
+}
+
+/// A walk through an ICU `UCharsTrie`, a UTF-16 unit at a time.
+///
+/// Node lead units below 0x30 are branches and 0x30..0x40 linear matches
+/// of one to sixteen units. Above that, bits 6..14 of a match node's lead
+/// hold an intermediate value, if any, and a lead with bit 15 set is a
+/// final value. Branches work like a `BytesTrie`'s.
+#[derive(Clone, Debug)]
+pub struct UCharsTrie<'a> {
+    units: &'a [u16],
+    pos: Option<usize>,
+    remaining_match_length: i32,
+}
+
+impl<'a> UCharsTrie<'a> {
+    const MIN_LINEAR_MATCH: u16 = 0x30;
+    const MIN_VALUE_LEAD: u16 = 0x40;
+    const NODE_TYPE_MASK: u16 = 0x3f;
+    const VALUE_IS_FINAL: u16 = 0x8000;
+    const MIN_TWO_UNIT_VALUE_LEAD: u16 = 0x4000;
+    const THREE_UNIT_VALUE_LEAD: u16 = 0x7fff;
+    const MIN_TWO_UNIT_NODE_VALUE_LEAD: u16 = 0x4040;
+    const THREE_UNIT_NODE_VALUE_LEAD: u16 = 0x7fc0;
+    const MIN_TWO_UNIT_DELTA_LEAD: u16 = 0xfc00;
+    const THREE_UNIT_DELTA_LEAD: u16 = 0xffff;
+    const MAX_BRANCH_LINEAR_SUB_NODE_LENGTH: usize = 5;
+
+    /// Starts a walk at the root of the serialized trie `units`.
+    pub fn new(units: &'a [u16]) -> UCharsTrie<'a> {
+        UCharsTrie {
+            units,
+            pos: Some(0),
+            remaining_match_length: -1,
+        }
+    }
+
+    /// Matches one more unit, like `BytesTrie::next`.
+    pub fn next(&mut self, unit: u16) -> TrieResult {
+        let result = match self.pos {
+            Some(pos) => self.next_at(pos, unit),
+            None => None,
+        };
+        result.unwrap_or_else(|| {
+            self.pos = None;
+            TrieResult::NoMatch
+        })
+    }
+
+    /// The value of the string matched so far, if it's in the trie.
+    pub fn value(&self) -> Option<i32> {
+        let pos = self.pos?;
+        let lead = *self.units.get(pos)?;
+        if self.remaining_match_length >= 0 || lead < Self::MIN_VALUE_LEAD {
+            return None;
+        }
+        let value = if lead & Self::VALUE_IS_FINAL != 0 {
+            self.read_value(pos + 1, lead & !Self::VALUE_IS_FINAL)
+        } else {
+            self.read_node_value(pos + 1, lead)
+        };
+        value.map(|(value, _)| value)
+    }
+
+    fn next_at(&mut self, mut pos: usize, unit: u16) -> Option<TrieResult> {
+        if self.remaining_match_length >= 0 {
+            return self.match_linear(pos, unit, self.remaining_match_length);
+        }
+        let mut node = *self.units.get(pos)?;
+        pos += 1;
+        loop {
+            if node < Self::MIN_LINEAR_MATCH {
+                return self.branch_next(pos, node, unit);
+            } else if node < Self::MIN_VALUE_LEAD {
+                return self.match_linear(pos, unit, i32::from(node - Self::MIN_LINEAR_MATCH));
+            } else if node & Self::VALUE_IS_FINAL != 0 {
+                return None;
+            } else {
+                // skip the intermediate value to the node sharing its lead
+                pos = Self::skip_node_value(pos, node);
+                node &= Self::NODE_TYPE_MASK;
+            }
+        }
+    }
+
+    fn match_linear(&mut self, pos: usize, unit: u16, length: i32) -> Option<TrieResult> {
+        if *self.units.get(pos)? != unit {
+            return None;
+        }
+        self.remaining_match_length = length - 1;
+        self.pos = Some(pos + 1);
+        if length == 0 {
+            Some(self.result_at(pos + 1))
+        } else {
+            Some(TrieResult::NoValue)
+        }
+    }
+
+    fn branch_next(&mut self, mut pos: usize, node: u16, unit: u16) -> Option<TrieResult> {
+        let mut length = usize::from(node);
+        if length == 0 {
+            length = usize::from(*self.units.get(pos)?);
+            pos += 1;
+        }
+        length += 1;
+        while length > Self::MAX_BRANCH_LINEAR_SUB_NODE_LENGTH {
+            let split = *self.units.get(pos)?;
+            pos += 1;
+            if unit < split {
+                length >>= 1;
+                pos = self.jump_by_delta(pos)?;
+            } else {
+                length -= length >> 1;
+                pos = self.skip_delta(pos)?;
+            }
+        }
+        while length > 1 {
+            let key = *self.units.get(pos)?;
+            pos += 1;
+            let node = *self.units.get(pos)?;
+            if key == unit {
+                if node & Self::VALUE_IS_FINAL != 0 {
+                    self.pos = Some(pos);
+                    return Some(TrieResult::FinalValue);
+                }
+                let (delta, next) = self.read_value(pos + 1, node)?;
+                let pos = next.checked_add(delta as usize)?;
+                self.pos = Some(pos);
+                return Some(self.result_at(pos));
+            }
+            length -= 1;
+            pos = Self::skip_value(pos + 1, node & !Self::VALUE_IS_FINAL);
+        }
+        if *self.units.get(pos)? == unit {
+            self.pos = Some(pos + 1);
+            Some(self.result_at(pos + 1))
+        } else {
+            None
+        }
+    }
+
+    fn result_at(&self, pos: usize) -> TrieResult {
+        match self.units.get(pos) {
+            Some(&node) if node >= Self::MIN_VALUE_LEAD && node & Self::VALUE_IS_FINAL != 0 => {
+                TrieResult::FinalValue
+            }
+            Some(&node) if node >= Self::MIN_VALUE_LEAD => TrieResult::IntermediateValue,
+            _ => TrieResult::NoValue,
+        }
+    }
+
+    /// Reads the value with the lead unit `lead`, less its final bit, from
+    /// the units after the lead at `pos`, and the position after it.
+    fn read_value(&self, pos: usize, lead: u16) -> Option<(i32, usize)> {
+        let unit = |i: usize| self.units.get(pos + i).map(|&u| i32::from(u));
+        Some(if lead < Self::MIN_TWO_UNIT_VALUE_LEAD {
+            (i32::from(lead), pos)
+        } else if lead < Self::THREE_UNIT_VALUE_LEAD {
+            (i32::from(lead - Self::MIN_TWO_UNIT_VALUE_LEAD) << 16 | unit(0)?, pos + 1)
+        } else {
+            (unit(0)? << 16 | unit(1)?, pos + 2)
+        })
+    }
+
+    fn skip_value(pos: usize, lead: u16) -> usize {
+        if lead < Self::MIN_TWO_UNIT_VALUE_LEAD {
+            pos
+        } else if lead < Self::THREE_UNIT_VALUE_LEAD {
+            pos + 1
+        } else {
+            pos + 2
+        }
+    }
+
+    /// Reads the intermediate value in the match node lead `lead`, and the
+    /// units after it at `pos`, and the position after it.
+    fn read_node_value(&self, pos: usize, lead: u16) -> Option<(i32, usize)> {
+        let unit = |i: usize| self.units.get(pos + i).map(|&u| i32::from(u));
+        Some(if lead < Self::MIN_TWO_UNIT_NODE_VALUE_LEAD {
+            (i32::from(lead >> 6) - 1, pos)
+        } else if lead < Self::THREE_UNIT_NODE_VALUE_LEAD {
+            let high = lead & Self::THREE_UNIT_NODE_VALUE_LEAD;
+            (i32::from(high - Self::MIN_TWO_UNIT_NODE_VALUE_LEAD) << 10 | unit(0)?, pos + 1)
+        } else {
+            (unit(0)? << 16 | unit(1)?, pos + 2)
+        })
+    }
+
+    fn skip_node_value(pos: usize, lead: u16) -> usize {
+        if lead < Self::MIN_TWO_UNIT_NODE_VALUE_LEAD {
+            pos
+        } else if lead < Self::THREE_UNIT_NODE_VALUE_LEAD {
+            pos + 1
+        } else {
+            pos + 2
+        }
+    }
+
+    fn jump_by_delta(&self, pos: usize) -> Option<usize> {
+        let unit = |i: usize| self.units.get(pos + i).map(|&u| usize::from(u));
+        let lead = *self.units.get(pos)?;
+        let (delta, next) = if lead < Self::MIN_TWO_UNIT_DELTA_LEAD {
+            (usize::from(lead), pos + 1)
+        } else if lead == Self::THREE_UNIT_DELTA_LEAD {
+            (unit(1)? << 16 | unit(2)?, pos + 3)
+        } else {
+            (usize::from(lead - Self::MIN_TWO_UNIT_DELTA_LEAD) << 16 | unit(1)?, pos + 2)
+        };
+        next.checked_add(delta)
+    }
+
+    fn skip_delta(&self, pos: usize) -> Option<usize> {
+        let lead = *self.units.get(pos)?;
+        Some(if lead < Self::MIN_TWO_UNIT_DELTA_LEAD {
+            pos + 1
+        } else if lead == Self::THREE_UNIT_DELTA_LEAD {
+            pos + 3
+        } else {
+            pos + 2
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bytestrie::{BytesTrie, TrieResult, UCharsTrie};
+
+    /// A byte trie of "a".."g" and "gh", with values of each encoded
+    /// length: a branch on seven bytes, split at "d" into a list of "d"
+    /// through "g" and, 20 bytes on, a list of "a" through "c".
+    const BYTES: [u8; 30] = [
+        0x06, b'd', 0x14,
+        b'd', 0xdb, 0x23, 0x45, // 0x12345
+        b'e', 0xfd, 0x12, 0x34, 0x56, // 0x123456
+        b'f', 0xff, 0xff, 0xff, 0xff, 0xff, // -1
+        b'g', 0x2e, 0x10, b'h', 0x31, // 7, then "h" to 8
+        b'a', 0x23, // 1
+        b'b', 0xa1, // 0x40
+        b'c', 0xc7, 0x34, // 0x1234
+    ];
+
+    fn get(key: &[u8]) -> (TrieResult, Option<i32>) {
+        let mut trie = BytesTrie::new(&BYTES);
+        let mut result = TrieResult::NoValue;
+        for &b in key {
+            result = trie.next(b);
+        }
+        (result, trie.value())
+    }
+
+    #[test]
+    fn bytes_trie_reads_values_of_every_length() {
+        let expected = [
+            ("a", 1),
+            ("b", 0x40),
+            ("c", 0x1234),
+            ("d", 0x12345),
+            ("e", 0x12_3456),
+            ("f", -1),
+            ("gh", 8),
+        ];
+        for &(key, value) in &expected {
+            assert_eq!(get(key.as_bytes()), (TrieResult::FinalValue, Some(value)), "{}", key);
+        }
+        assert_eq!(get(b"g"), (TrieResult::IntermediateValue, Some(7)));
+    }
+
+    #[test]
+    fn bytes_trie_stops_at_mismatches() {
+        assert_eq!(get(b"z"), (TrieResult::NoMatch, None));
+        assert_eq!(get(b"ab"), (TrieResult::NoMatch, None));
+        assert_eq!(get(b"gi"), (TrieResult::NoMatch, None));
+        assert_eq!(get(b"ghh"), (TrieResult::NoMatch, None));
+
+        let mut trie = BytesTrie::new(&BYTES);
+        assert!(!trie.next(b'0').matches());
+        assert_eq!(trie.next(b'a'), TrieResult::NoMatch);
+    }
+
+    #[test]
+    fn bytes_trie_survives_truncated_data() {
+        for len in 0..BYTES.len() {
+            let mut trie = BytesTrie::new(&BYTES[..len]);
+            trie.next(b'g');
+            trie.next(b'h');
+            trie.value();
+        }
+    }
+
+    #[test]
+    fn uchars_trie_reads_node_values() {
+        // 中, 中国 and 人: a branch whose first entry jumps to a linear
+        // match holding an intermediate value in its lead
+        let units = [0x0001, 0x4e2d, 0x0002, 0x4eba, 0x8001, 0x00b0, 0x56fd, 0x8003];
+        let mut trie = UCharsTrie::new(&units);
+        assert_eq!(trie.next(0x4e2d), TrieResult::IntermediateValue);
+        assert_eq!(trie.value(), Some(1));
+        assert_eq!(trie.next(0x56fd), TrieResult::FinalValue);
+        assert_eq!(trie.value(), Some(3));
+
+        let mut trie = UCharsTrie::new(&units);
+        assert_eq!(trie.next(0x4eba), TrieResult::FinalValue);
+        assert_eq!(trie.value(), Some(1));
+        assert_eq!(trie.next(0x4eba), TrieResult::NoMatch);
+        assert_eq!(trie.value(), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! Case mapping data ("cAsE"), as in `ucase.icu`.
+
+use std::convert::TryFrom;
+use std::io::prelude::*;
+use error::IcuError;
+use trie::Trie2;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots
+const IX_INDEX_TOP: usize = 0;
+const IX_LENGTH: usize = 1;
+const IX_TRIE_SIZE: usize = 2;
+const IX_EXC_LENGTH: usize = 3;
+const IX_UNFOLD_LENGTH: usize = 4;
+const IX_MAX_FULL_LENGTH: usize = 15;
+const IX_TOP: usize = 16;
+
+/// The indexes and trie of a case mapping data file.
+///
+/// The data starts with an `indexes[]` array whose first entry is its own
+/// length, followed by the trie, the 16-bit exceptions that trie values
+/// with the exception bit point into, and the 16-bit unfold table for
+/// reverse case folding.
+#[derive(Clone, Debug)]
+pub struct CaseData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: Vec<u32>,
+    trie: Trie2,
+}
+
+impl<R> CaseData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<CaseData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::Case)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let count = EndianReader::read_u32(&mut reader)?;
+        if (count as usize) < IX_TOP {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        if base + 4 * u64::from(count) > len {
+            return Err(IcuError::Malformed("indexes run past the end of the data"));
+        }
+        let mut indexes = Vec::with_capacity(count as usize);
+        indexes.push(count);
+        for _ in 1..count {
+            indexes.push(EndianReader::read_u32(&mut reader)?);
+        }
+
+        let sections = 4 * u64::from(count)
+            + u64::from(indexes[IX_TRIE_SIZE])
+            + 2 * u64::from(indexes[IX_EXC_LENGTH])
+            + 2 * u64::from(indexes[IX_UNFOLD_LENGTH]);
+        if sections > u64::from(indexes[IX_LENGTH]) {
+            return Err(IcuError::Malformed("data overruns its length"));
+        }
+        if base + u64::from(indexes[IX_LENGTH]) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        let trie = Trie2::try_init(&mut reader)?;
+        if trie.serialized_length() > indexes[IX_TRIE_SIZE] {
+            return Err(IcuError::Malformed("trie overruns its space"));
+        }
+
+        Ok(CaseData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            trie,
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The trie of case properties.
+    pub fn trie(&self) -> &Trie2 {
+        &self.trie
+    }
+
+    /// The 16-bit case properties of `cp`: its case type in the low two
+    /// bits, and either a delta to its simple case mapping in the top bits
+    /// or, with the exception bit set, an index into the exceptions.
+    pub fn get_case_props(&self, cp: char) -> u16 {
+        self.trie.get(cp) as u16
+    }
+
+    /// The number of bytes set aside for the trie.
+    pub fn trie_size(&self) -> u32 {
+        self.indexes[IX_TRIE_SIZE]
+    }
+
+    /// The byte offset of the exceptions from the start of the data.
+    pub fn exceptions_offset(&self) -> u32 {
+        4 * self.indexes[IX_INDEX_TOP] + self.indexes[IX_TRIE_SIZE]
+    }
+
+    /// The number of 16-bit units of exceptions.
+    pub fn exceptions_length(&self) -> u32 {
+        self.indexes[IX_EXC_LENGTH]
+    }
+
+    /// The byte offset of the unfold table from the start of the data.
+    pub fn unfold_offset(&self) -> u32 {
+        self.exceptions_offset() + 2 * self.indexes[IX_EXC_LENGTH]
+    }
+
+    /// The number of 16-bit units in the unfold table.
+    pub fn unfold_length(&self) -> u32 {
+        self.indexes[IX_UNFOLD_LENGTH]
+    }
+
+    /// The lengths of the longest full case mappings, packed four bits
+    /// each: lowercase, folding, uppercase and titlecase from the bottom.
+    pub fn max_full_length(&self) -> u32 {
+        self.indexes[IX_MAX_FULL_LENGTH]
+    }
+
+    /// The size of the data in bytes, not counting the header.
+    pub fn data_size(&self) -> u32 {
+        self.indexes[IX_LENGTH]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use case::CaseData;
+    use error::IcuError;
+    use std::io::Cursor;
+    use trie::fixtures::{be_u32s, bmp_trie, bmp_trie_length};
+    use version::Version;
+    use {write_header, DataFormat, Order};
+
+    const TRIE_SIZE: u32 = bmp_trie_length(false);
+
+    /// A 16-bit trie giving A-Z the props of an uppercase letter lowercasing
+    /// 32 code points up, and a-z the reverse.
+    fn trie() -> Vec<u8> {
+        let lower = ((-32i16 as u16) << 7) | 1;
+        bmp_trie(false, &[(0x41..0x5b, (32 << 7) | 2), (0x61..0x7b, u32::from(lower))])
+    }
+
+    /// `indexes`, the trie, three units of exceptions and five of unfold
+    /// table.
+    fn ucase(indexes: &[u32]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Case;
+        write_header(&mut bytes, format, [3, 0, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend(be_u32s(indexes));
+        bytes.extend(trie());
+        bytes.extend_from_slice(&[0; 16]);
+        Cursor::new(bytes)
+    }
+
+    fn indexes() -> Vec<u32> {
+        let length = 64 + TRIE_SIZE + 16;
+        vec![16, length, TRIE_SIZE, 3, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0333]
+    }
+
+    #[test]
+    fn try_init_reads_indexes_and_trie() {
+        let c = CaseData::try_init(ucase(&indexes())).expect("Failed to read cAsE");
+        assert_eq!(c.version(), Some(Version::Unicode10_0((10, 0, 0, 0))));
+        assert_eq!(c.get_case_props('A'), (32 << 7) | 2);
+        assert_eq!(c.get_case_props('z') & 3, 1);
+        assert_eq!((c.get_case_props('z') as i16) >> 7, -32);
+        assert_eq!(c.get_case_props('0'), 0);
+        assert_eq!(c.get_case_props('\u{1f600}'), 0);
+        assert_eq!(c.trie_size(), TRIE_SIZE);
+        assert_eq!(c.exceptions_offset(), 64 + TRIE_SIZE);
+        assert_eq!(c.exceptions_length(), 3);
+        assert_eq!(c.unfold_offset(), 64 + TRIE_SIZE + 6);
+        assert_eq!(c.unfold_length(), 5);
+        assert_eq!(c.max_full_length(), 0x0333);
+        assert_eq!(c.data_size(), 64 + TRIE_SIZE + 16);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_indexes() {
+        let mut short = indexes();
+        short.truncate(15);
+        short[0] = 15;
+        match CaseData::try_init(ucase(&short)) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bad = indexes();
+        bad[4] += 1; // unfold table past the data's length
+        assert!(CaseData::try_init(ucase(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[1] += 4; // past the end of the file
+        assert!(CaseData::try_init(ucase(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[2] -= 2; // too little space for the trie
+        assert!(CaseData::try_init(ucase(&bad)).is_err());
+    }
+
+    #[test]
+    fn try_init_rejects_other_formats() {
+        let mut bytes = ucase(&indexes()).into_inner();
+        bytes[12..16].copy_from_slice(b"cASE");
+        assert!(CaseData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
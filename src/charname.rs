@@ -0,0 +1,530 @@
+//! Unicode character names ("unam"), as in `unames.icu`.
+
+use std::io::{self, SeekFrom, prelude::*};
+use error::IcuError;
+use version::PiecewiseVersion;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+/// Code points whose names share a group differ only in their low bits.
+const GROUP_SHIFT: u32 = 5;
+const LINES_PER_GROUP: usize = 1 << GROUP_SHIFT;
+
+/// A `tokens` entry for a byte that stands for itself.
+const NOT_A_TOKEN: u16 = 0xffff;
+/// A `tokens` entry for the first byte of a two-byte token.
+const LEAD_BYTE: u16 = 0xfffe;
+
+// algorithmic range types
+const RANGE_HEX: u8 = 0;
+const RANGE_FACTORIZED: u8 = 1;
+/// The size of an algorithmic range before its type-specific data.
+const RANGE_HEADER_SIZE: u16 = 12;
+
+/// The character names of a Unicode names data file.
+///
+/// The data starts with the offsets of the token strings, the groups, the
+/// group strings and the algorithmic ranges, followed by the token table.
+/// Names are stored in groups of 32 code points, with each name a string
+/// of bytes that stand for themselves or for a token, a whole word from
+/// the token strings; a byte whose token is the lead byte marker combines
+/// with the next byte to index the token table instead. Within a name,
+/// `;` separates the modern name from the Unicode 1.0 name.
+///
+/// Code points in ranges whose names follow a pattern, like CJK ideographs
+/// and Hangul syllables, aren't stored but built from their range.
+#[derive(Clone, Debug)]
+pub struct CharNameData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    tokens: Vec<u16>,
+    token_strings: Vec<u8>,
+    /// `(code point >> GROUP_SHIFT, offset into group_strings)` of each
+    /// group, sorted.
+    groups: Vec<(u16, u32)>,
+    group_strings: Vec<u8>,
+    ranges: Vec<AlgorithmicRange>,
+}
+
+/// Code points from `start` through `end` whose names are built the same
+/// way.
+#[derive(Clone, Debug)]
+struct AlgorithmicRange {
+    start: u32,
+    end: u32,
+    names: AlgorithmicNames,
+}
+
+#[derive(Clone, Debug)]
+enum AlgorithmicNames {
+    /// The prefix followed by the code point in this many uppercase hex
+    /// digits, like "CJK UNIFIED IDEOGRAPH-4E00".
+    Hex(String, usize),
+    /// The prefix followed by a string from each factor's list, picked by
+    /// the digits of the code point's offset into the range in a mixed
+    /// radix of the lists' lengths, like "HANGUL SYLLABLE GAG".
+    Factorized(String, Vec<Vec<String>>),
+}
+
+impl<R> CharNameData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<CharNameData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::CharacterName)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 16)?;
+        let mut offsets = [0; 4];
+        for offset in offsets.iter_mut() {
+            *offset = EndianReader::read_u32(&mut reader)?;
+        }
+        let [token_string_offset, groups_offset, group_string_offset, alg_names_offset] = offsets;
+        if token_string_offset < 18
+            || groups_offset < token_string_offset
+            || group_string_offset < groups_offset
+            || alg_names_offset < group_string_offset
+            || base + u64::from(alg_names_offset) + 4 > len
+        {
+            return Err(IcuError::Malformed("bad section offsets"));
+        }
+
+        let token_count = EndianReader::read_u16(&mut reader)?;
+        if 18 + 2 * u32::from(token_count) > token_string_offset {
+            return Err(IcuError::Malformed("tokens overrun the token strings"));
+        }
+        let mut tokens = Vec::with_capacity(usize::from(token_count));
+        for _ in 0..token_count {
+            tokens.push(EndianReader::read_u16(&mut reader)?);
+        }
+        reader.seek(SeekFrom::Start(base + u64::from(token_string_offset)))?;
+        let token_strings = read_bytes(&mut reader, groups_offset - token_string_offset)?;
+
+        let group_count = EndianReader::read_u16(&mut reader)?;
+        if 2 + 6 * u32::from(group_count) > group_string_offset - groups_offset {
+            return Err(IcuError::Malformed("groups overrun the group strings"));
+        }
+        let mut groups = Vec::with_capacity(usize::from(group_count));
+        for _ in 0..group_count {
+            let msb = EndianReader::read_u16(&mut reader)?;
+            let high = u32::from(EndianReader::read_u16(&mut reader)?);
+            let low = u32::from(EndianReader::read_u16(&mut reader)?);
+            groups.push((msb, high << 16 | low));
+        }
+        reader.seek(SeekFrom::Start(base + u64::from(group_string_offset)))?;
+        let group_strings = read_bytes(&mut reader, alg_names_offset - group_string_offset)?;
+
+        let range_count = EndianReader::read_u32(&mut reader)?;
+        let mut ranges = Vec::new();
+        for _ in 0..range_count {
+            let start = EndianReader::read_u32(&mut reader)?;
+            let end = EndianReader::read_u32(&mut reader)?;
+            let mut type_and_variant = [0; 2];
+            reader.read_exact(&mut type_and_variant)?;
+            let [range_type, variant] = type_and_variant;
+            let size = EndianReader::read_u16(&mut reader)?;
+            let factors_size = match range_type {
+                RANGE_FACTORIZED => 2 * u16::from(variant),
+                _ => 0,
+            };
+            if size < RANGE_HEADER_SIZE + factors_size {
+                return Err(IcuError::Malformed("algorithmic range is too small"));
+            }
+            let rest = u32::from(size - RANGE_HEADER_SIZE - factors_size);
+            if start > end {
+                return Err(IcuError::Malformed("algorithmic range ends before it starts"));
+            }
+
+            let names = match range_type {
+                RANGE_HEX => {
+                    let prefix = c_strings(&read_bytes(&mut reader, rest)?).next();
+                    AlgorithmicNames::Hex(prefix.unwrap_or_default(), usize::from(variant))
+                }
+                RANGE_FACTORIZED => {
+                    let mut factors = Vec::with_capacity(usize::from(variant));
+                    for _ in 0..variant {
+                        factors.push(EndianReader::read_u16(&mut reader)?);
+                    }
+                    let mut strings = c_strings(&read_bytes(&mut reader, rest)?);
+                    let prefix = strings.next().unwrap_or_default();
+                    let mut lists = Vec::with_capacity(factors.len());
+                    for &factor in &factors {
+                        let list: Vec<_> = strings.by_ref().take(usize::from(factor)).collect();
+                        if list.len() != usize::from(factor) {
+                            return Err(IcuError::Malformed("missing factor strings"));
+                        }
+                        lists.push(list);
+                    }
+                    AlgorithmicNames::Factorized(prefix, lists)
+                }
+                _ => {
+                    // a kind of range this reader doesn't know names nothing
+                    reader.seek(SeekFrom::Current(i64::from(rest)))?;
+                    continue;
+                }
+            };
+            ranges.push(AlgorithmicRange { start, end, names });
+        }
+
+        Ok(CharNameData {
+            reader,
+            header_size,
+            data_version,
+            tokens,
+            token_strings,
+            groups,
+            group_strings,
+            ranges,
+        })
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The name of `c`, as in "LATIN CAPITAL LETTER A", or none for code
+    /// points without one, like controls and unassigned code points.
+    pub fn name_of(&self, c: char) -> Option<String> {
+        let c = c as u32;
+        if let Some(range) = self.ranges.iter().find(|r| r.start <= c && c <= r.end) {
+            return Some(range.name(c));
+        }
+        let group = self
+            .groups
+            .binary_search_by_key(&((c >> GROUP_SHIFT) as u16), |&(msb, _)| msb)
+            .ok()?;
+        let lines = self.group_lines(group)?;
+        let name = self.expand(lines[(c as usize) & (LINES_PER_GROUP - 1)]);
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    /// The code point named `name`, matched ignoring ASCII case.
+    ///
+    /// Names in groups are found by expanding every group's names, so this
+    /// is much slower than `name_of`.
+    pub fn code_point_for(&self, name: &str) -> Option<char> {
+        let name = name.to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        if let Some(c) = self.ranges.iter().find_map(|r| r.code_point(&name)) {
+            return char::from_u32(c);
+        }
+        for (group, &(msb, _)) in self.groups.iter().enumerate() {
+            let lines = match self.group_lines(group) {
+                Some(lines) => lines,
+                None => continue,
+            };
+            if let Some(i) = lines.iter().position(|line| self.expand(line) == name) {
+                return char::from_u32(u32::from(msb) << GROUP_SHIFT | i as u32);
+            }
+        }
+        None
+    }
+
+    /// The stored names of the 32 code points in the group at `group`.
+    ///
+    /// A group's strings start with the lengths of its names as a run of
+    /// nibbles: one nibble for a length under 12, or two for a longer one,
+    /// the first of them 12 or more and its low two bits the high bits of
+    /// the length less 12. The names follow, after the byte holding the
+    /// last nibble.
+    fn group_lines(&self, group: usize) -> Option<Vec<&[u8]>> {
+        let (_, offset) = self.groups[group];
+        let strings = self.group_strings.get(offset as usize..)?;
+        let nibble = |i: usize| {
+            let b = strings.get(i / 2)?;
+            Some(if i & 1 == 0 { b >> 4 } else { b & 0xf })
+        };
+
+        let mut lengths = Vec::with_capacity(LINES_PER_GROUP);
+        let mut i = 0;
+        while lengths.len() < LINES_PER_GROUP {
+            let first = nibble(i)?;
+            i += 1;
+            if first < 12 {
+                lengths.push(usize::from(first));
+            } else {
+                lengths.push(12 + usize::from((first & 3) << 4 | nibble(i)?));
+                i += 1;
+            }
+        }
+
+        let mut start = i.div_ceil(2);
+        let mut lines = Vec::with_capacity(LINES_PER_GROUP);
+        for length in lengths {
+            lines.push(strings.get(start..start + length)?);
+            start += length;
+        }
+        Some(lines)
+    }
+
+    /// Expands the tokens of a stored name, up to the end of its modern
+    /// name.
+    fn expand(&self, line: &[u8]) -> String {
+        let mut name = String::new();
+        let mut bytes = line.iter().cloned();
+        while let Some(b) = bytes.next() {
+            let mut token = self.tokens.get(usize::from(b)).cloned().unwrap_or(NOT_A_TOKEN);
+            if token == LEAD_BYTE {
+                let index = usize::from(b) << 8 | usize::from(bytes.next().unwrap_or(0));
+                token = self.tokens.get(index).cloned().unwrap_or(NOT_A_TOKEN);
+            }
+            if token != NOT_A_TOKEN {
+                if let Some(word) = self.token_strings.get(usize::from(token)..) {
+                    name.extend(word.iter().take_while(|&&c| c != 0).map(|&c| char::from(c)));
+                }
+            } else if b == b';' {
+                break;
+            } else {
+                name.push(char::from(b));
+            }
+        }
+        name
+    }
+}
+
+impl AlgorithmicRange {
+    /// The name of `c`, which must be in the range.
+    fn name(&self, c: u32) -> String {
+        match self.names {
+            AlgorithmicNames::Hex(ref prefix, digits) => format!("{}{:02$X}", prefix, c, digits),
+            AlgorithmicNames::Factorized(ref prefix, ref lists) => {
+                let mut offset = (c - self.start) as usize;
+                let mut parts = Vec::with_capacity(lists.len());
+                for list in lists.iter().rev() {
+                    parts.push(&list[offset % list.len()]);
+                    offset /= list.len();
+                }
+                parts.iter().rev().fold(prefix.clone(), |name, part| name + part)
+            }
+        }
+    }
+
+    /// The code point in the range named `name`, if there is one.
+    fn code_point(&self, name: &str) -> Option<u32> {
+        let c = match self.names {
+            AlgorithmicNames::Hex(ref prefix, digits) => {
+                let hex = name.strip_prefix(prefix.as_str())?;
+                if hex.len() != digits || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return None;
+                }
+                u32::from_str_radix(hex, 16).ok()?
+            }
+            AlgorithmicNames::Factorized(ref prefix, ref lists) => {
+                let rest = name.strip_prefix(prefix.as_str())?;
+                self.start + match_factors(rest, lists, 0)? as u32
+            }
+        };
+        if self.start <= c && c <= self.end {
+            Some(c)
+        } else {
+            None
+        }
+    }
+}
+
+/// The offset into a factorized range of the name whose suffix is `rest`,
+/// picking a string from each list in turn. Strings can be prefixes of
+/// others in the same list, or empty, so each match is tried in turn.
+fn match_factors(rest: &str, lists: &[Vec<String>], offset: usize) -> Option<usize> {
+    let (list, lists) = match lists.split_first() {
+        Some(split) => split,
+        None if rest.is_empty() => return Some(offset),
+        None => return None,
+    };
+    list.iter().enumerate().find_map(|(i, s)| {
+        let rest = rest.strip_prefix(s.as_str())?;
+        match_factors(rest, lists, offset * list.len() + i)
+    })
+}
+
+fn read_bytes<R>(reader: &mut OrderedReader<R>, count: u32) -> io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let mut bytes = vec![0; count as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The NUL-terminated strings packed in `bytes`.
+fn c_strings(bytes: &[u8]) -> ::std::vec::IntoIter<String> {
+    let mut strings: Vec<String> = bytes
+        .split(|&b| b == 0)
+        .map(|s| s.iter().map(|&b| char::from(b)).collect())
+        .collect();
+    // whatever follows the last NUL isn't a string
+    strings.pop();
+    strings.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use charname::CharNameData;
+    use std::io::Cursor;
+    use {write_header, DataFormat, Order};
+
+    const JAMO_L: &str = "G GG N D DD R M B BB S SS _ J JJ C K T P H";
+    const JAMO_V: &str = "A AE YA YAE EO E YEO YE O WA WAE OE YO U WEO WE WI YU EU YI I";
+    const JAMO_T: &str = "_ G GG GS N NJ NH D L LG LM LB LS LT LP LH M B BS S SS NG J C K T P H";
+
+    fn push_u16(bytes: &mut Vec<u8>, unit: u16) {
+        bytes.extend_from_slice(&[(unit >> 8) as u8, unit as u8]);
+    }
+
+    fn push_u32(bytes: &mut Vec<u8>, word: u32) {
+        push_u16(bytes, (word >> 16) as u16);
+        push_u16(bytes, word as u16);
+    }
+
+    /// A big-endian names file with one group, for U+0040..U+005F, naming
+    /// U+0040, U+0041 and U+0042, and algorithmic ranges for the CJK
+    /// Extension A ideographs and the Hangul syllables.
+    ///
+    /// Token 0 is "LATIN", byte 1 leads the two-byte token 0x100,
+    /// "CAPITAL", and token 2 is "LETTER"; every other byte stands for
+    /// itself.
+    fn unames() -> Cursor<Vec<u8>> {
+        let token_count = 0x101;
+        let mut tokens = vec![0xffff; token_count];
+        tokens[0] = 0;
+        tokens[1] = 0xfffe;
+        tokens[0x100] = 6;
+        tokens[2] = 14;
+        let token_strings = b"LATIN\0CAPITAL\0LETTER\0\0".to_vec();
+
+        let letter = |c| vec![0, b' ', 1, 0, b' ', 2, b' ', c];
+        let mut lines = vec![Vec::new(); 32];
+        lines[0] = b"COMMERCIAL AT".to_vec();
+        lines[1] = letter(b'A');
+        lines[2] = letter(b'B');
+        lines[2].extend_from_slice(b";OLD B");
+        let mut nibbles = Vec::new();
+        for line in &lines {
+            match line.len() {
+                len if len < 12 => nibbles.push(len as u8),
+                len => {
+                    let len = (len - 12) as u8;
+                    nibbles.extend_from_slice(&[0xc | len >> 4, len & 0xf]);
+                }
+            }
+        }
+        nibbles.resize((nibbles.len() + 1) & !1, 0);
+        let mut group_strings: Vec<u8> = nibbles.chunks(2).map(|n| n[0] << 4 | n[1]).collect();
+        for line in &lines {
+            group_strings.extend(line);
+        }
+        group_strings.resize((group_strings.len() + 3) & !3, 0);
+
+        let mut alg = Vec::new();
+        push_u32(&mut alg, 2);
+        let prefix = b"CJK UNIFIED IDEOGRAPH-\0\0";
+        push_u32(&mut alg, 0x3400);
+        push_u32(&mut alg, 0x4db5);
+        alg.extend_from_slice(&[0, 4]);
+        push_u16(&mut alg, 12 + prefix.len() as u16);
+        alg.extend_from_slice(prefix);
+
+        let mut strings = b"HANGUL SYLLABLE \0".to_vec();
+        for jamo in &[JAMO_L, JAMO_V, JAMO_T] {
+            for s in jamo.split(' ') {
+                strings.extend(s.bytes().filter(|&b| b != b'_'));
+                strings.push(0);
+            }
+        }
+        strings.resize((strings.len() + 3) & !3, 0);
+        push_u32(&mut alg, 0xac00);
+        push_u32(&mut alg, 0xd7a3);
+        alg.extend_from_slice(&[1, 3]);
+        push_u16(&mut alg, 12 + 6 + strings.len() as u16);
+        for &factor in &[19, 21, 28] {
+            push_u16(&mut alg, factor);
+        }
+        alg.extend(strings);
+
+        let token_string_offset = 18 + 2 * token_count as u32;
+        let groups_offset = token_string_offset + token_strings.len() as u32;
+        let group_string_offset = groups_offset + 8;
+        let alg_names_offset = group_string_offset + group_strings.len() as u32;
+
+        let mut bytes = Vec::new();
+        let format = DataFormat::CharacterName;
+        write_header(&mut bytes, format, [1, 0, 0, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        let offsets = [token_string_offset, groups_offset, group_string_offset, alg_names_offset];
+        for &offset in &offsets {
+            push_u32(&mut bytes, offset);
+        }
+        push_u16(&mut bytes, token_count as u16);
+        for &token in &tokens {
+            push_u16(&mut bytes, token);
+        }
+        bytes.extend(token_strings);
+        for &unit in &[1, 2, 0, 0] {
+            push_u16(&mut bytes, unit); // one group, at offset 0
+        }
+        bytes.extend(group_strings);
+        bytes.extend(alg);
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn name_of_expands_group_names() {
+        let n = CharNameData::try_init(unames()).expect("Failed to read names");
+        assert_eq!(n.name_of('A').as_deref(), Some("LATIN CAPITAL LETTER A"));
+        assert_eq!(n.name_of('B').as_deref(), Some("LATIN CAPITAL LETTER B"));
+        assert_eq!(n.name_of('@').as_deref(), Some("COMMERCIAL AT"));
+        assert_eq!(n.name_of('C'), None);
+        assert_eq!(n.name_of('a'), None);
+    }
+
+    #[test]
+    fn name_of_builds_algorithmic_names() {
+        let n = CharNameData::try_init(unames()).expect("Failed to read names");
+        assert_eq!(n.name_of('\u{3400}').as_deref(), Some("CJK UNIFIED IDEOGRAPH-3400"));
+        assert_eq!(n.name_of('\u{4db5}').as_deref(), Some("CJK UNIFIED IDEOGRAPH-4DB5"));
+        assert_eq!(n.name_of('\u{4db6}'), None);
+        assert_eq!(n.name_of('\u{ac00}').as_deref(), Some("HANGUL SYLLABLE GA"));
+        assert_eq!(n.name_of('\u{ac01}').as_deref(), Some("HANGUL SYLLABLE GAG"));
+        assert_eq!(n.name_of('\u{d7a3}').as_deref(), Some("HANGUL SYLLABLE HIH"));
+    }
+
+    #[test]
+    fn code_point_for_finds_names() {
+        let n = CharNameData::try_init(unames()).expect("Failed to read names");
+        assert_eq!(n.code_point_for("LATIN CAPITAL LETTER A"), Some('A'));
+        assert_eq!(n.code_point_for("latin capital letter b"), Some('B'));
+        assert_eq!(n.code_point_for("COMMERCIAL AT"), Some('@'));
+        assert_eq!(n.code_point_for("OLD B"), None);
+        assert_eq!(n.code_point_for("CJK UNIFIED IDEOGRAPH-4DB5"), Some('\u{4db5}'));
+        assert_eq!(n.code_point_for("CJK UNIFIED IDEOGRAPH-4DB6"), None);
+        assert_eq!(n.code_point_for("CJK UNIFIED IDEOGRAPH-34000"), None);
+        assert_eq!(n.code_point_for("HANGUL SYLLABLE GAG"), Some('\u{ac01}'));
+        assert_eq!(n.code_point_for("HANGUL SYLLABLE GGA"), Some('\u{ae4c}'));
+        assert_eq!(n.code_point_for("HANGUL SYLLABLE A"), Some('\u{c544}'));
+        assert_eq!(n.code_point_for("HANGUL SYLLABLE X"), None);
+        assert_eq!(n.code_point_for(""), None);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_offsets() {
+        let mut bytes = unames().into_inner();
+        bytes[32 + 7] = 0; // groups before the token strings
+        assert!(CharNameData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = unames().into_inner();
+        let len = bytes.len();
+        bytes.truncate(len - 8); // cut the Hangul syllables' strings short
+        assert!(CharNameData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
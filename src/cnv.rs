@@ -0,0 +1,181 @@
+//! Converter tables ("cnvt"), the `.cnv` files that hold the mapping
+//! tables of ICU's table-based charset converters.
+
+use std::io::prelude::*;
+use std::str;
+use error::IcuError;
+use version::PiecewiseVersion;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+/// The size of ICU's `UConverterStaticData`.
+const STATIC_DATA_SIZE: u32 = 100;
+/// The space for the NUL-terminated name in the static data.
+const MAX_CONVERTER_NAME_LENGTH: usize = 60;
+
+/// The kinds of converter a table describes, numbered as ICU's
+/// `UConverterType` numbers them. The algorithmic converters, like those
+/// for UTF-8 and ISO-2022, are built into ICU and have no tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConversionType {
+    Sbcs = 0,
+    Dbcs = 1,
+    Mbcs = 2,
+    EbcdicStateful = 9,
+}
+
+/// The static data of a converter table.
+///
+/// The data starts with a `UConverterStaticData` struct, whose first
+/// field is its own size, giving the converter's name, codepage and type
+/// and the range of bytes a character takes. The type's own tables, for
+/// now only read as far as the struct, follow it.
+#[derive(Clone, Debug)]
+pub struct ConverterData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    name: String,
+    codepage: i32,
+    conversion_type: ConversionType,
+    min_bytes_per_char: u8,
+    max_bytes_per_char: u8,
+}
+
+impl<R> ConverterData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<ConverterData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::Converter)?;
+        let base = u64::from(header_size);
+
+        reader.seek_checked(base, u64::from(STATIC_DATA_SIZE))?;
+        if EndianReader::read_u32(&mut reader)? < STATIC_DATA_SIZE {
+            return Err(IcuError::Malformed("converter static data is too small"));
+        }
+        let mut name = [0; MAX_CONVERTER_NAME_LENGTH];
+        reader.read_exact(&mut name)?;
+        let name = match name.iter().position(|&b| b == 0) {
+            Some(end) if name[..end].is_ascii() => str::from_utf8(&name[..end]).unwrap(),
+            _ => return Err(IcuError::Malformed("bad converter name")),
+        };
+        let codepage = EndianReader::read_u32(&mut reader)? as i32;
+        let mut fields = [0; 4];
+        reader.read_exact(&mut fields)?;
+        let [_platform, conversion_type, min_bytes_per_char, max_bytes_per_char] = fields;
+
+        let conversion_type = match conversion_type {
+            0 => ConversionType::Sbcs,
+            1 => ConversionType::Dbcs,
+            2 => ConversionType::Mbcs,
+            9 => ConversionType::EbcdicStateful,
+            _ => return Err(IcuError::Malformed("not a table-based conversion type")),
+        };
+        if min_bytes_per_char == 0 || min_bytes_per_char > max_bytes_per_char
+            || max_bytes_per_char > 4
+        {
+            return Err(IcuError::Malformed("bad bytes per character"));
+        }
+
+        Ok(ConverterData {
+            reader,
+            header_size,
+            data_version,
+            name: name.to_string(),
+            codepage,
+            conversion_type,
+            min_bytes_per_char,
+            max_bytes_per_char,
+        })
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The converter's name, as in `ibm-37_P100-1995`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The codepage number the converter is known by, or 0 for none.
+    pub fn codepage(&self) -> i32 {
+        self.codepage
+    }
+
+    /// The kind of converter the tables are for.
+    pub fn conversion_type(&self) -> ConversionType {
+        self.conversion_type
+    }
+
+    /// The fewest bytes a character takes in the charset.
+    pub fn min_bytes_per_char(&self) -> u8 {
+        self.min_bytes_per_char
+    }
+
+    /// The most bytes a character takes in the charset.
+    pub fn max_bytes_per_char(&self) -> u8 {
+        self.max_bytes_per_char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cnv::{ConversionType, ConverterData};
+    use std::io::Cursor;
+    use {write_header, DataFormat, Order};
+
+    /// The big-endian static data of an MBCS converter for Shift-JIS,
+    /// followed by a stand-in for its tables.
+    fn cnv() -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Converter;
+        write_header(&mut bytes, format, [6, 2, 0, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend_from_slice(&[0, 0, 0, 100]);
+        let mut name = b"ibm-943_P15A-2003".to_vec();
+        name.resize(60, 0);
+        bytes.extend(name);
+        bytes.extend_from_slice(&[0, 0, 0x03, 0xaf]); // codepage 943
+        bytes.extend_from_slice(&[0, 2, 1, 2]); // IBM, MBCS, 1 to 2 bytes
+        bytes.extend_from_slice(&[0xfc, 0xfc, 0, 0, 2, 0, 0, 0, 0x7f]); // subchar
+        bytes.resize(32 + 100 + 8, 0);
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn try_init_reads_static_data() {
+        let c = ConverterData::try_init(cnv()).expect("Failed to read converter");
+        assert_eq!(c.name(), "ibm-943_P15A-2003");
+        assert_eq!(c.codepage(), 943);
+        assert_eq!(c.conversion_type(), ConversionType::Mbcs);
+        assert_eq!(c.min_bytes_per_char(), 1);
+        assert_eq!(c.max_bytes_per_char(), 2);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_static_data() {
+        let mut bytes = cnv().into_inner();
+        bytes[35] = 96; // a struct too small
+        assert!(ConverterData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = cnv().into_inner();
+        bytes[32 + 4..32 + 64].copy_from_slice(&[b'x'; 60]); // no NUL
+        assert!(ConverterData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = cnv().into_inner();
+        bytes[32 + 69] = 4; // UTF-8, which has no tables
+        assert!(ConverterData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = cnv().into_inner();
+        bytes[32 + 70] = 3; // a minimum above the maximum
+        assert!(ConverterData::try_init(Cursor::new(bytes)).is_err());
+
+        let bytes = cnv().into_inner()[..100].to_vec();
+        assert!(ConverterData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
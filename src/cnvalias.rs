@@ -0,0 +1,381 @@
+//! Converter alias tables ("CvAl"), as in `cnvalias.icu`, which map the
+//! many names of each charset to the name of its converter.
+
+use std::io::{self, prelude::*};
+use std::cmp::Ordering;
+use std::str;
+use error::IcuError;
+use version::PiecewiseVersion;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+/// The fewest sections a table of contents can list: all but the
+/// normalized strings.
+const MIN_TOC_LENGTH: u32 = 8;
+
+// the sections, in the order the table of contents lists them
+const CONVERTER_LIST: usize = 0;
+const TAG_LIST: usize = 1;
+const ALIAS_LIST: usize = 2;
+const UNTAGGED_CONV_ARRAY: usize = 3;
+const TAGGED_ALIAS_ARRAY: usize = 4;
+const TAGGED_ALIAS_LISTS: usize = 5;
+const OPTION_TABLE: usize = 6;
+const STRING_TABLE: usize = 7;
+const NORMALIZED_STRING_TABLE: usize = 8;
+
+/// The option table's `stringNormalizationType` when the normalized
+/// strings are there to compare names against.
+const STD_NORMALIZED: u16 = 1;
+/// The bits of an untagged converter entry that index the converters.
+const CONVERTER_INDEX_MASK: u16 = 0xfff;
+
+/// The alias table of a converter alias data file.
+///
+/// After a table of contents giving the number of sections and each one's
+/// length in 16-bit units come the sections themselves: the converters'
+/// names, the names of the standards (like IANA or MIME) that define
+/// aliases, all the aliases sorted by their normalized form with the
+/// converter each maps to, and for each standard and converter the
+/// standard's aliases for it. Names are offsets, in 16-bit units, into a
+/// table of NUL-terminated strings, and into a parallel table holding
+/// them normalized for matching.
+#[derive(Clone, Debug)]
+pub struct ConverterAliasData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    converters: Vec<u16>,
+    tags: Vec<u16>,
+    aliases: Vec<u16>,
+    untagged_converters: Vec<u16>,
+    tagged_aliases: Vec<u16>,
+    tagged_alias_lists: Vec<u16>,
+    strings: Vec<u8>,
+    /// The strings normalized, or empty if names are normalized as they're
+    /// compared.
+    normalized_strings: Vec<u8>,
+}
+
+impl<R> ConverterAliasData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<ConverterAliasData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::ConverterAlias)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let count = EndianReader::read_u32(&mut reader)?;
+        if count < MIN_TOC_LENGTH {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        if base + 4 * (1 + u64::from(count)) > len {
+            return Err(IcuError::Malformed("table of contents runs past the end of the data"));
+        }
+        let mut sizes = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            sizes.push(EndianReader::read_u32(&mut reader)?);
+        }
+        let units: u64 = sizes.iter().map(|&size| u64::from(size)).sum();
+        if base + 4 * (1 + u64::from(count)) + 2 * units > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        let converters = read_units(&mut reader, sizes[CONVERTER_LIST])?;
+        let tags = read_units(&mut reader, sizes[TAG_LIST])?;
+        let aliases = read_units(&mut reader, sizes[ALIAS_LIST])?;
+        let untagged_converters = read_units(&mut reader, sizes[UNTAGGED_CONV_ARRAY])?;
+        let tagged_aliases = read_units(&mut reader, sizes[TAGGED_ALIAS_ARRAY])?;
+        let tagged_alias_lists = read_units(&mut reader, sizes[TAGGED_ALIAS_LISTS])?;
+        let options = read_units(&mut reader, sizes[OPTION_TABLE])?;
+        let mut strings = vec![0; 2 * sizes[STRING_TABLE] as usize];
+        reader.read_exact(&mut strings)?;
+        let mut normalized_strings = Vec::new();
+        if options.first() == Some(&STD_NORMALIZED) && count as usize > NORMALIZED_STRING_TABLE {
+            normalized_strings = vec![0; 2 * sizes[NORMALIZED_STRING_TABLE] as usize];
+            reader.read_exact(&mut normalized_strings)?;
+        }
+
+        if aliases.len() != untagged_converters.len() {
+            return Err(IcuError::Malformed("aliases and their converters differ in number"));
+        }
+        if tagged_aliases.len() != tags.len() * converters.len() {
+            return Err(IcuError::Malformed("tagged alias array is the wrong size"));
+        }
+
+        Ok(ConverterAliasData {
+            reader,
+            header_size,
+            data_version,
+            converters,
+            tags,
+            aliases,
+            untagged_converters,
+            tagged_aliases,
+            tagged_alias_lists,
+            strings,
+            normalized_strings,
+        })
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The names of the converters, in the table's order.
+    pub fn converters(&self) -> impl Iterator<Item = &str> {
+        self.converters.iter().filter_map(move |&offset| string_at(&self.strings, offset))
+    }
+
+    /// The name of the converter for the charset named `alias`.
+    ///
+    /// Names match as ICU matches them: ignoring case, punctuation and
+    /// zeros leading a number, so `"latin1"`, `"Latin-1"` and `"LATIN_01"`
+    /// are all the same alias.
+    pub fn canonical_name(&self, alias: &str) -> Option<&str> {
+        let wanted = normalize(alias);
+        let (mut low, mut high) = (0, self.aliases.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let ordering = match self.normalized_alias(self.aliases[mid]) {
+                Some(name) => name.as_slice().cmp(wanted.as_slice()),
+                None => return None,
+            };
+            match ordering {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => {
+                    let converter = self.untagged_converters[mid] & CONVERTER_INDEX_MASK;
+                    let offset = *self.converters.get(usize::from(converter))?;
+                    return string_at(&self.strings, offset);
+                }
+            }
+        }
+        None
+    }
+
+    /// All the aliases of the converter named `canonical`, including its
+    /// own name, or none if there's no such converter.
+    ///
+    /// These are the aliases of the last standard, which ICU's data
+    /// builder always makes the one listing every alias.
+    pub fn aliases<'a>(&'a self, canonical: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let list = self.all_aliases(canonical).unwrap_or(&[]);
+        list.iter().filter_map(move |&offset| string_at(&self.strings, offset))
+    }
+
+    /// The string offsets of the aliases the last standard lists for the
+    /// converter `canonical`.
+    fn all_aliases(&self, canonical: &str) -> Option<&[u16]> {
+        let converter = self
+            .converters
+            .iter()
+            .position(|&offset| string_at(&self.strings, offset) == Some(canonical))?;
+        let tag = self.tags.len().checked_sub(1)?;
+        let list = usize::from(self.tagged_aliases[tag * self.converters.len() + converter]);
+        if list == 0 {
+            return None;
+        }
+        let count = usize::from(*self.tagged_alias_lists.get(list)?);
+        self.tagged_alias_lists.get(list + 1..list + 1 + count)
+    }
+
+    /// The normalized form of the string at `offset`.
+    fn normalized_alias(&self, offset: u16) -> Option<Vec<u8>> {
+        if self.normalized_strings.is_empty() {
+            string_at(&self.strings, offset).map(normalize)
+        } else {
+            string_at(&self.normalized_strings, offset).map(|s| s.as_bytes().to_vec())
+        }
+    }
+}
+
+/// Reads `count` 16-bit units.
+fn read_units<R>(reader: &mut OrderedReader<R>, count: u32) -> io::Result<Vec<u16>>
+where
+    R: Read + Seek,
+{
+    let mut units = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        units.push(EndianReader::read_u16(reader)?);
+    }
+    Ok(units)
+}
+
+/// The NUL-terminated string at `offset`, in 16-bit units, in `strings`.
+fn string_at(strings: &[u8], offset: u16) -> Option<&str> {
+    let bytes = strings.get(2 * usize::from(offset)..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Reduces a charset name to the form ICU compares names in: ASCII
+/// letters lowercased, digits kept except zeros that lead a number, and
+/// everything else dropped.
+fn normalize(name: &str) -> Vec<u8> {
+    let bytes = name.as_bytes();
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut after_digit = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'0' => {
+                let next_is_digit = bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+                if !after_digit && next_is_digit {
+                    continue;
+                }
+                normalized.push(b);
+            }
+            b'1'..=b'9' => {
+                after_digit = true;
+                normalized.push(b);
+            }
+            b if b.is_ascii_alphabetic() => {
+                after_digit = false;
+                normalized.push(b.to_ascii_lowercase());
+            }
+            _ => after_digit = false,
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use cnvalias::{normalize, ConverterAliasData};
+    use error::IcuError;
+    use std::io::Cursor;
+    use {write_header, DataFormat, Order};
+
+    /// A string table being built, with the offset in 16-bit units of each
+    /// string added.
+    struct Strings {
+        plain: Vec<u8>,
+        normalized: Vec<u8>,
+    }
+
+    impl Strings {
+        fn add(&mut self, s: &str) -> u16 {
+            let offset = (self.plain.len() / 2) as u16;
+            self.plain.extend_from_slice(s.as_bytes());
+            self.normalized.extend(normalize(s));
+            let padded = (s.len() + 2) & !1;
+            self.plain.resize(2 * offset as usize + padded, 0);
+            self.normalized.resize(2 * offset as usize + padded, 0);
+            offset
+        }
+    }
+
+    /// Two converters, ISO-8859-1 and UTF-8, with aliases from the IANA
+    /// standard and the "ALL" standard listing every alias.
+    fn cnvalias(normalized: bool) -> Cursor<Vec<u8>> {
+        let mut s = Strings {
+            plain: vec![0, 0],
+            normalized: vec![0, 0],
+        };
+        let latin1 = s.add("ISO-8859-1");
+        let utf8 = s.add("UTF-8");
+        let (iana, all) = (s.add("IANA"), s.add("ALL"));
+        let (l1, latin1_alias, cs_utf8) = (s.add("l1"), s.add("latin1"), s.add("csUTF8"));
+
+        let converters = [latin1, utf8];
+        let tags = [iana, all];
+        // sorted by normalized name: csutf8, iso88591, l1, latin1, utf8
+        let aliases = [cs_utf8, latin1, l1, latin1_alias, utf8];
+        let untagged = [1, 0, 0, 0, 1];
+        // IANA: ISO-8859-1 at 1, UTF-8 at 4; ALL: ISO-8859-1 at 7, UTF-8 at 11
+        let tagged = [1, 4, 7, 11];
+        let lists = [
+            0, 2, latin1, l1, 2, utf8, cs_utf8, 3, latin1, l1, latin1_alias, 2, utf8, cs_utf8,
+        ];
+        let options = [if normalized { 1 } else { 0 }, 0];
+
+        let mut units: Vec<u16> = Vec::new();
+        let mut sizes = Vec::new();
+        for section in &[&converters[..], &tags, &aliases, &untagged, &tagged, &lists, &options] {
+            sizes.push(section.len() as u32);
+            units.extend_from_slice(section);
+        }
+        sizes.push(s.plain.len() as u32 / 2);
+        sizes.push(s.normalized.len() as u32 / 2);
+
+        let mut bytes = Vec::new();
+        let format = DataFormat::ConverterAlias;
+        write_header(&mut bytes, format, [3, 0, 1, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        for word in Some(sizes.len() as u32).iter().chain(&sizes) {
+            bytes.extend_from_slice(&[
+                (word >> 24) as u8,
+                (word >> 16) as u8,
+                (word >> 8) as u8,
+                *word as u8,
+            ]);
+        }
+        for unit in units {
+            bytes.extend_from_slice(&[(unit >> 8) as u8, unit as u8]);
+        }
+        bytes.extend(s.plain);
+        bytes.extend(s.normalized);
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn canonical_name_resolves_aliases() {
+        for &normalized in &[true, false] {
+            let a = ConverterAliasData::try_init(cnvalias(normalized)).expect("Failed to read");
+            assert_eq!(a.converters().collect::<Vec<_>>(), vec!["ISO-8859-1", "UTF-8"]);
+            assert_eq!(a.canonical_name("latin1"), Some("ISO-8859-1"));
+            assert_eq!(a.canonical_name("Latin-1"), Some("ISO-8859-1"));
+            assert_eq!(a.canonical_name("LATIN_01"), Some("ISO-8859-1"));
+            assert_eq!(a.canonical_name("iso8859_1"), Some("ISO-8859-1"));
+            assert_eq!(a.canonical_name("utf8"), Some("UTF-8"));
+            assert_eq!(a.canonical_name("csUTF8"), Some("UTF-8"));
+            assert_eq!(a.canonical_name("utf16"), None);
+            assert_eq!(a.canonical_name(""), None);
+        }
+    }
+
+    #[test]
+    fn aliases_lists_every_alias() {
+        let a = ConverterAliasData::try_init(cnvalias(true)).expect("Failed to read");
+        let latin1: Vec<_> = a.aliases("ISO-8859-1").collect();
+        assert_eq!(latin1, vec!["ISO-8859-1", "l1", "latin1"]);
+        assert_eq!(a.aliases("UTF-8").collect::<Vec<_>>(), vec!["UTF-8", "csUTF8"]);
+        assert_eq!(a.aliases("latin1").count(), 0);
+    }
+
+    #[test]
+    fn normalize_matches_icu() {
+        assert_eq!(normalize("ISO-8859-1"), b"iso88591");
+        assert_eq!(normalize("LATIN_01"), b"latin1");
+        assert_eq!(normalize("x-0"), b"x0");
+        assert_eq!(normalize("ibm-00850"), b"ibm850");
+        assert_eq!(normalize("cp1000"), b"cp1000");
+        assert_eq!(normalize("UTF8 \u{e9}"), b"utf8");
+    }
+
+    #[test]
+    fn try_init_rejects_bad_tables() {
+        let mut bytes = cnvalias(true).into_inner();
+        bytes[32..36].copy_from_slice(&[0, 0, 0, 7]);
+        match ConverterAliasData::try_init(Cursor::new(bytes)) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bytes = cnvalias(true).into_inner();
+        let len = bytes.len();
+        bytes.truncate(len - 2);
+        assert!(ConverterAliasData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = cnvalias(true).into_inner();
+        bytes[19] = 1; // format 3.0.1.x only
+        assert!(ConverterAliasData::try_init(Cursor::new(bytes.clone())).is_ok());
+        bytes[18] = 2;
+        assert!(ConverterAliasData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Collation data ("UCol"), as in `ucadata.icu` and the collation
+//! tailorings in `coll/*.res`.
+
+use std::convert::TryFrom;
+use std::io::{SeekFrom, prelude::*};
+use error::IcuError;
+use trie::Trie2;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots
+const IX_INDEXES_LENGTH: usize = 0;
+const IX_OPTIONS: usize = 1;
+const IX_JAMO_CE32S_START: usize = 4;
+// byte offsets from the start of the data, each where its section starts
+// and the one before ends
+const IX_REORDER_CODES_OFFSET: usize = 5;
+const IX_TRIE_OFFSET: usize = 7;
+const IX_CES_OFFSET: usize = 9;
+const IX_CE32S_OFFSET: usize = 11;
+const IX_TOTAL_SIZE: usize = 19;
+
+/// The indexes and code point trie of a collation data file.
+///
+/// The data starts with an `indexes[]` array whose first entry is its own
+/// length. Past the options and the start of the Jamo CE32s come byte
+/// offsets of the data's sections, counted from the start of the data,
+/// after the ICU header. A section ends where the next one starts, so empty
+/// sections share their offset with the next. Tailorings may have fewer
+/// indexes than the root collation, leaving off trailing sections.
+#[derive(Clone, Debug)]
+pub struct CollationData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: Vec<u32>,
+    trie: Option<Trie2>,
+}
+
+impl<R> CollationData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<CollationData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::Collation)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let count = EndianReader::read_u32(&mut reader)?;
+        if count as usize <= IX_OPTIONS {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        if base + 4 * u64::from(count) > len {
+            return Err(IcuError::Malformed("indexes run past the end of the data"));
+        }
+        let mut indexes = Vec::with_capacity(count as usize);
+        indexes.push(count);
+        for _ in 1..count {
+            indexes.push(EndianReader::read_u32(&mut reader)?);
+        }
+
+        let last = indexes.len().min(IX_TOTAL_SIZE + 1);
+        if IX_REORDER_CODES_OFFSET < last {
+            let mut offsets = vec![4 * count];
+            offsets.extend_from_slice(&indexes[IX_REORDER_CODES_OFFSET..last]);
+            if offsets.windows(2).any(|w| w[0] > w[1]) {
+                return Err(IcuError::Malformed("data offsets out of order"));
+            }
+            if base + u64::from(offsets[offsets.len() - 1]) > len {
+                return Err(IcuError::Malformed("data runs past the end of the file"));
+            }
+        }
+
+        let mut data = CollationData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            trie: None,
+        };
+        let (trie_offset, trie_size) = data.section(IX_TRIE_OFFSET);
+        if trie_size > 0 {
+            data.reader.seek(SeekFrom::Start(base + u64::from(trie_offset)))?;
+            let trie = Trie2::try_init(&mut data.reader)?;
+            if trie.serialized_length() > trie_size {
+                return Err(IcuError::Malformed("trie overruns its space"));
+            }
+            data.trie = Some(trie);
+        }
+        Ok(data)
+    }
+
+    /// The version of the UCA data, which follows the Unicode version.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The number of entries in `indexes[]`.
+    pub fn indexes_length(&self) -> u32 {
+        self.indexes[IX_INDEXES_LENGTH]
+    }
+
+    /// The collation settings: strength, alternate handling, case
+    /// handling and the like, packed as in ICU's `CollationSettings`.
+    pub fn options(&self) -> u32 {
+        self.indexes[IX_OPTIONS]
+    }
+
+    /// Where the CE32s for the conjoining Jamo start in the CE32s, if the
+    /// data has them.
+    pub fn jamo_ce32s_start(&self) -> Option<u32> {
+        self.indexes
+            .get(IX_JAMO_CE32S_START)
+            .cloned()
+            .filter(|&start| (start as i32) >= 0)
+    }
+
+    /// The trie mapping code points to CE32s, if the data has one;
+    /// tailorings that only change settings don't.
+    pub fn trie(&self) -> Option<&Trie2> {
+        self.trie.as_ref()
+    }
+
+    /// The number of bytes set aside for the trie.
+    pub fn trie_size(&self) -> u32 {
+        self.section(IX_TRIE_OFFSET).1
+    }
+
+    /// The byte offset of the 64-bit CEs, from the start of the data.
+    pub fn ces_offset(&self) -> u32 {
+        self.section(IX_CES_OFFSET).0
+    }
+
+    /// The number of bytes of 64-bit CEs.
+    pub fn ces_size(&self) -> u32 {
+        self.section(IX_CES_OFFSET).1
+    }
+
+    /// The byte offset of the CE32s, from the start of the data.
+    pub fn ce32s_offset(&self) -> u32 {
+        self.section(IX_CE32S_OFFSET).0
+    }
+
+    /// The number of bytes of CE32s.
+    pub fn ce32s_size(&self) -> u32 {
+        self.section(IX_CE32S_OFFSET).1
+    }
+
+    /// The size of the data in bytes, not counting the header.
+    pub fn data_size(&self) -> u32 {
+        let last = self.indexes.len().min(IX_TOTAL_SIZE + 1);
+        if last > IX_REORDER_CODES_OFFSET {
+            self.indexes[last - 1]
+        } else {
+            4 * self.indexes_length()
+        }
+    }
+
+    /// The byte offset and size of the section starting at the offset in
+    /// `indexes[index]`. Sections past the end of `indexes[]` are empty.
+    fn section(&self, index: usize) -> (u32, u32) {
+        match (self.indexes.get(index), self.indexes.get(index + 1)) {
+            (Some(&start), Some(&end)) => (start, end - start),
+            (Some(&start), None) => (start, 0),
+            _ => (self.data_size(), 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use collation::CollationData;
+    use error::IcuError;
+    use std::io::Cursor;
+    use trie::fixtures::{be_u32s, bmp_trie, bmp_trie_length};
+    use version::Version;
+    use {write_header, DataFormat, Order};
+
+    const TRIE_SIZE: u32 = bmp_trie_length(true);
+
+    /// A 32-bit trie giving 'a' the CE32 0x1234_5605.
+    fn trie() -> Vec<u8> {
+        bmp_trie(true, &[(0x61..0x62, 0x1234_5605)])
+    }
+
+    /// A collation data file with `indexes`, the trie, and then zeros up to
+    /// the total size.
+    fn ucol(indexes: &[u32]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Collation;
+        write_header(&mut bytes, format, [5, 0, 0, 0], (9, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend(be_u32s(indexes));
+        bytes.extend(trie());
+        let total = 32 + 80 + TRIE_SIZE as usize + 24;
+        bytes.resize(total, 0);
+        Cursor::new(bytes)
+    }
+
+    /// Indexes for a trie at 80, then two CEs and two CE32s.
+    fn indexes() -> Vec<u32> {
+        let trie_end = 80 + TRIE_SIZE;
+        let end = trie_end + 24;
+        vec![
+            20, 0x0000_0017, 0, 0, 0xffff_ffff, // length, options, reserved, no Jamo
+            80, 80, 80, // reorder codes, reorder table, trie
+            trie_end, trie_end, trie_end + 16, trie_end + 16, // CEs at 9, CE32s at 11
+            end, end, end, end, end, end, end, end, // root elements through total size
+        ]
+    }
+
+    #[test]
+    fn try_init_reads_indexes_and_trie() {
+        let c = CollationData::try_init(ucol(&indexes())).expect("Failed to read UCol");
+        assert_eq!(c.version(), Some(Version::Unicode9_0((9, 0, 0, 0))));
+        assert_eq!(c.indexes_length(), 20);
+        assert_eq!(c.options(), 0x17);
+        assert_eq!(c.jamo_ce32s_start(), None);
+        assert_eq!(c.trie_size(), TRIE_SIZE);
+        assert_eq!((c.ces_offset(), c.ces_size()), (80 + TRIE_SIZE, 16));
+        assert_eq!((c.ce32s_offset(), c.ce32s_size()), (80 + TRIE_SIZE + 16, 8));
+        assert_eq!(c.data_size(), 80 + TRIE_SIZE + 24);
+        let trie = c.trie().expect("no trie");
+        assert!(trie.is_32_bit());
+        assert_eq!(trie.get('a'), 0x1234_5605);
+        assert_eq!(trie.get('b'), 0);
+    }
+
+    #[test]
+    fn try_init_reads_tailorings_without_a_trie() {
+        let mut bytes = ucol(&[3, 0x0000_0017, 0]).into_inner();
+        bytes.truncate(32 + 12);
+        let c = CollationData::try_init(Cursor::new(bytes)).expect("Failed to read UCol");
+        assert!(c.trie().is_none());
+        assert_eq!(c.trie_size(), 0);
+        assert_eq!(c.ces_size(), 0);
+        assert_eq!(c.data_size(), 12);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_indexes() {
+        match CollationData::try_init(ucol(&[1])) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bad = indexes();
+        bad[9] = 64; // CEs before the trie
+        assert!(CollationData::try_init(ucol(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[19] += 4; // past the end of the file
+        assert!(CollationData::try_init(ucol(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[8] -= 4; // too little space for the trie
+        assert!(CollationData::try_init(ucol(&bad)).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! ICU common data archives ("CmnD"), the `icudt*.dat` files that package
+//! many data items into one.
+
+use std::io::{self, Error, ErrorKind, SeekFrom, prelude::*};
+use std::str;
+use error::IcuError;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader, ResourceBundleReader};
+use SliceReader;
+
+/// Reads the table of contents of a common data archive and opens its items.
+///
+/// After the header, an archive holds a count of its items, then a
+/// `(name offset, data offset)` pair for each, then the NUL-terminated item
+/// names and the items themselves. Both offsets count bytes from the start
+/// of the count, so the names form a string pool after the table of
+/// contents. This offset-based table of contents is the only layout ICU
+/// writes to files; its pointer-based counterpart only exists in memory.
+#[derive(Clone, Debug)]
+pub struct DatPackageReader<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    /// `(name, byte offset, byte length)` of each item.
+    entries: Vec<(String, u32, u32)>,
+}
+
+impl<R> DatPackageReader<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<DatPackageReader<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, _) = read_header(&mut reader, DataFormat::Dat)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let count = EndianReader::read_u32(&mut reader)?;
+        if count == 0 || base + 4 + 8 * u64::from(count) > len {
+            return Err(IcuError::Malformed("bad table of contents"));
+        }
+        let mut toc = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let name_offset = EndianReader::read_u32(&mut reader)?;
+            let data_offset = EndianReader::read_u32(&mut reader)?;
+            toc.push((name_offset, base + u64::from(data_offset)));
+        }
+
+        let mut entries = Vec::with_capacity(toc.len());
+        for (i, &(name_offset, start)) in toc.iter().enumerate() {
+            // an item ends where the next one starts, the last at the end
+            let end = toc.get(i + 1).map_or(len, |&(_, next)| next);
+            if start > end || end > len {
+                return Err(IcuError::Malformed("bad item offset"));
+            }
+            reader.seek(SeekFrom::Start(base + u64::from(name_offset)))?;
+            let name = read_name(&mut reader)?;
+            entries.push((name, start as u32, (end - start) as u32));
+        }
+
+        Ok(DatPackageReader { reader, entries })
+    }
+
+    /// The name and byte offset of each item, in the archive's order, which
+    /// ICU sorts by name.
+    ///
+    /// Names include the package prefix, as in `icudt60l/en.res`.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.entries
+            .iter()
+            .map(|&(ref name, offset, _)| (name.as_str(), offset))
+    }
+
+    /// Positions a reader at the start of the item `name`, given with or
+    /// without the package prefix.
+    ///
+    /// Seeks on the returned reader are still relative to the start of the
+    /// archive.
+    pub fn open(&mut self, name: &str) -> io::Result<OrderedReader<&mut R>> {
+        let (offset, _) = self.find(name)?;
+        let order = self.reader.order;
+        let inner = &mut self.reader.reader;
+        inner.seek(SeekFrom::Start(u64::from(offset)))?;
+        OrderedReader::wrap(inner, order)
+    }
+
+    /// Opens the item `name`, as with `open`, as a resource bundle of
+    /// `format`, like `en.res` in an `icudt*.dat`.
+    ///
+    /// The bundle is read through a `SliceReader`, so it sees the item as a
+    /// file of its own: seeks are relative to the item's start, and reads
+    /// stop at its end.
+    pub fn open_bundle(
+        &mut self,
+        name: &str,
+        format: DataFormat,
+    ) -> io::Result<ResourceBundleReader<SliceReader<&mut R>>> {
+        let (offset, length) = self.find(name)?;
+        let item = SliceReader::new(&mut self.reader.reader, offset.into(), length.into())?;
+        ResourceBundleReader::try_init(item, format).map_err(io::Error::from)
+    }
+
+    /// The byte offset and length of the item `name`, given with or without
+    /// the package prefix.
+    fn find(&self, name: &str) -> io::Result<(u32, u32)> {
+        self.entries
+            .iter()
+            .find(|(entry, _, _)| {
+                entry == name || entry.split_once('/').map(|(_, rest)| rest) == Some(name)
+            })
+            .map(|&(_, offset, length)| (offset, length))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("no item {}", name)))
+    }
+}
+
+/// Reads a NUL-terminated invariant-character name.
+fn read_name<R>(reader: &mut R) -> io::Result<String>
+where
+    R: Read,
+{
+    let mut name = Vec::new();
+    let mut byte = [0];
+    loop {
+        reader.read_exact(&mut byte)?;
+        match byte[0] {
+            0 => break,
+            b if b.is_ascii() => name.push(b),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "non-ASCII item name")),
+        }
+    }
+    String::from_utf8(name).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use dat::DatPackageReader;
+    use std::io::{Cursor, Read};
+    use {write_header, DataFormat, Order};
+
+    /// A big-endian archive holding `pkg/a.res` and `pkg/b.res`.
+    fn archive() -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Dat;
+        write_header(&mut bytes, format, [1, 0, 0, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend_from_slice(&[
+            // table of contents
+            0x0, 0x0, 0x0, 0x02,
+            0x0, 0x0, 0x0, 0x14, 0x0, 0x0, 0x0, 0x28,
+            0x0, 0x0, 0x0, 0x1e, 0x0, 0x0, 0x0, 0x2c,
+        ]);
+        bytes.extend_from_slice(b"pkg/a.res\0pkg/b.res\0AAAABBBBBB");
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn entries_lists_the_table_of_contents() {
+        let r = DatPackageReader::try_init(archive()).expect("Failed to read archive");
+        let entries: Vec<_> = r.entries().collect();
+        assert_eq!(entries, vec![("pkg/a.res", 72), ("pkg/b.res", 76)]);
+    }
+
+    #[test]
+    fn open_positions_at_the_item() {
+        let mut r = DatPackageReader::try_init(archive()).expect("Failed to read archive");
+        let mut item = String::new();
+        r.open("pkg/b.res").unwrap().read_to_string(&mut item).unwrap();
+        assert_eq!(item, "BBBBBB");
+
+        let mut item = [0; 4];
+        r.open("a.res").unwrap().read_exact(&mut item).unwrap();
+        assert_eq!(&item, b"AAAA");
+
+        assert!(r.open("c.res").is_err());
+    }
+
+    /// A big-endian bundle whose root table is `{ a: "hi" }`.
+    fn en_res() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::ResourceBundle;
+        write_header(&mut bytes, format, [3, 0, 0, 0], (1, 4, 0, 0), Order::BigEndian).unwrap();
+        let words: [u32; 12] = [
+            0x2000_000a, // root: table at 10
+            5, 7, 12, 12, 1, // indexes
+            0x6100_0000, // keys: "a" at byte 24
+            2, 0x0068_0069, 0, // 7: "hi"
+            0x0001_0018, 0x0000_0007, // 10: { a: "hi" }
+        ];
+        for w in &words {
+            bytes.extend_from_slice(&[(w >> 24) as u8, (w >> 16) as u8, (w >> 8) as u8, *w as u8]);
+        }
+        bytes
+    }
+
+    /// A big-endian archive holding `en_res` as `pkg/en.res`, followed by
+    /// `pkg/zz.txt`.
+    fn bundle_archive() -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Dat;
+        write_header(&mut bytes, format, [1, 0, 0, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        let en_res = en_res();
+        let zz_offset = 0x30 + en_res.len() as u8;
+        bytes.extend_from_slice(&[
+            // table of contents
+            0x0, 0x0, 0x0, 0x02,
+            0x0, 0x0, 0x0, 0x14, 0x0, 0x0, 0x0, 0x30,
+            0x0, 0x0, 0x0, 0x1f, 0x0, 0x0, 0x0, zz_offset,
+        ]);
+        bytes.extend_from_slice(b"pkg/en.res\0pkg/zz.txt\0\0\0\0\0\0\0");
+        bytes.extend(en_res);
+        bytes.extend_from_slice(b"ZZZZ");
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn open_bundle_reads_bundles_in_the_archive() {
+        let mut r = DatPackageReader::try_init(bundle_archive()).expect("Failed to read archive");
+        let mut en = r.open_bundle("en.res", DataFormat::ResourceBundle).unwrap();
+        assert_eq!(en.data_size(), 48);
+        assert_eq!(en.get_string("a").unwrap(), Some("hi".to_string()));
+        assert_eq!(en.get_string("b").unwrap(), None);
+
+        assert!(r.open_bundle("pkg/zz.txt", DataFormat::ResourceBundle).is_err());
+        assert!(r.open_bundle("fr.res", DataFormat::ResourceBundle).is_err());
+    }
+
+    #[test]
+    fn try_init_rejects_other_formats() {
+        let mut bytes = archive().into_inner();
+        bytes[12..16].copy_from_slice(b"ResB");
+        assert!(DatPackageReader::try_init(Cursor::new(bytes)).is_err());
+    }
+}
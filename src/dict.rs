@@ -0,0 +1,240 @@
+//! Break iteration dictionaries ("Dict"), as in `thaidict.dict` and
+//! `cjdict.dict`, the word lists dictionary-based word breaking matches
+//! text against.
+
+use std::io::{SeekFrom, prelude::*};
+use bytestrie::{BytesTrie, TrieResult, UCharsTrie};
+use error::IcuError;
+use version::PiecewiseVersion;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots
+const IX_STRING_TRIE_OFFSET: usize = 0;
+const IX_RESERVED1_OFFSET: usize = 1;
+const IX_TRIE_TYPE: usize = 4;
+const IX_TRANSFORM: usize = 5;
+const IX_COUNT: usize = 8;
+
+// IX_TRIE_TYPE values and bits
+const TRIE_TYPE_BYTES: u32 = 0;
+const TRIE_TYPE_UCHARS: u32 = 1;
+const TRIE_TYPE_MASK: u32 = 7;
+
+// IX_TRANSFORM fields
+const TRANSFORM_TYPE_OFFSET: u32 = 0x0100_0000;
+const TRANSFORM_TYPE_MASK: u32 = 0x7f00_0000;
+const TRANSFORM_OFFSET_MASK: u32 = 0x001f_ffff;
+
+/// The words of a dictionary, in a trie of either bytes or UTF-16 units.
+#[derive(Clone, Debug)]
+enum Words {
+    /// A byte trie, with each character of a word transformed to a byte.
+    Bytes(Vec<u8>),
+    /// A UTF-16 trie, with words in UTF-16.
+    UChars(Vec<u16>),
+}
+
+/// The word list of a break iteration dictionary.
+///
+/// The data starts with eight indexes giving the byte offset of the trie
+/// and the end of it, the kind of trie and how a byte trie's words were
+/// transformed to bytes. A byte trie is for scripts whose characters all
+/// fit in a 256-character block, like Thai: each character is stored as
+/// its offset from the block's start, with ZWJ and ZWNJ at 0xff and 0xfe.
+#[derive(Clone, Debug)]
+pub struct DictionaryData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: [u32; IX_COUNT],
+    words: Words,
+}
+
+impl<R> DictionaryData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<DictionaryData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::Dictionary)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4 * IX_COUNT as u64)?;
+        let mut indexes = [0; IX_COUNT];
+        for index in indexes.iter_mut() {
+            *index = EndianReader::read_u32(&mut reader)?;
+        }
+        let start = indexes[IX_STRING_TRIE_OFFSET];
+        let end = indexes[IX_RESERVED1_OFFSET];
+        if start < 4 * IX_COUNT as u32 || end < start {
+            return Err(IcuError::Malformed("bad trie offsets"));
+        }
+        if base + u64::from(end) > len {
+            return Err(IcuError::Malformed("trie runs past the end of the file"));
+        }
+
+        reader.seek(SeekFrom::Start(base + u64::from(start)))?;
+        let words = match indexes[IX_TRIE_TYPE] & TRIE_TYPE_MASK {
+            TRIE_TYPE_BYTES => {
+                let mut bytes = vec![0; (end - start) as usize];
+                reader.read_exact(&mut bytes)?;
+                Words::Bytes(bytes)
+            }
+            TRIE_TYPE_UCHARS => {
+                let mut units = Vec::with_capacity((end - start) as usize / 2);
+                for _ in 0..(end - start) / 2 {
+                    units.push(EndianReader::read_u16(&mut reader)?);
+                }
+                Words::UChars(units)
+            }
+            _ => return Err(IcuError::Malformed("unknown trie type")),
+        };
+
+        Ok(DictionaryData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            words,
+        })
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// Whether `word` is one of the dictionary's words.
+    pub fn contains(&self, word: &str) -> bool {
+        let mut result = TrieResult::NoValue;
+        match self.words {
+            Words::Bytes(ref bytes) => {
+                let mut trie = BytesTrie::new(bytes);
+                for c in word.chars() {
+                    result = match self.transform(c) {
+                        Some(b) => trie.next(b),
+                        None => return false,
+                    };
+                }
+            }
+            Words::UChars(ref units) => {
+                let mut trie = UCharsTrie::new(units);
+                for unit in word.encode_utf16() {
+                    result = trie.next(unit);
+                }
+            }
+        }
+        !word.is_empty() && result.has_value()
+    }
+
+    /// The byte a byte trie stores `c` as, if it can hold it.
+    fn transform(&self, c: char) -> Option<u8> {
+        let transform = self.indexes[IX_TRANSFORM];
+        if transform & TRANSFORM_TYPE_MASK != TRANSFORM_TYPE_OFFSET {
+            return if (c as u32) < 0x100 { Some(c as u8) } else { None };
+        }
+        match c {
+            '\u{200d}' => Some(0xff),
+            '\u{200c}' => Some(0xfe),
+            _ => {
+                let delta = (c as u32).checked_sub(transform & TRANSFORM_OFFSET_MASK)?;
+                if delta <= 0xfd {
+                    Some(delta as u8)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dict::DictionaryData;
+    use std::io::Cursor;
+    use {write_header, DataFormat, Order};
+
+    /// A big-endian dictionary holding `trie`, of type `trie_type`, with
+    /// the Thai block's offset transform.
+    fn dict(trie_type: u32, trie: &[u8]) -> Cursor<Vec<u8>> {
+        let indexes = [32, 32 + trie.len() as u32, 0, 0, trie_type, 0x0100_0e00, 0, 0];
+        let mut bytes = Vec::new();
+        let format = DataFormat::Dictionary;
+        write_header(&mut bytes, format, [1, 0, 0, 0], (0, 0, 0, 0), Order::BigEndian).unwrap();
+        for index in &indexes {
+            bytes.extend_from_slice(&[
+                (index >> 24) as u8,
+                (index >> 16) as u8,
+                (index >> 8) as u8,
+                *index as u8,
+            ]);
+        }
+        bytes.extend_from_slice(trie);
+        Cursor::new(bytes)
+    }
+
+    /// A byte trie of the Thai words กา, การ and ข, as offsets from U+0E00:
+    /// a branch on 01 and 02, with 01 jumping two bytes ahead and 02 ending
+    /// in a final value, then a linear match of 32, an intermediate value,
+    /// a linear match of 23 and a final value.
+    fn thai() -> Cursor<Vec<u8>> {
+        dict(0, &[0x01, 0x01, 0x24, 0x02, 0x23, 0x10, 0x32, 0x22, 0x10, 0x23, 0x23])
+    }
+
+    /// A UTF-16 trie of the words 中, 中国 and 人: a branch on 4e2d and 4eba,
+    /// with 4e2d jumping two units ahead and 4eba ending in a final value,
+    /// then a linear match of 56fd whose lead holds an intermediate value,
+    /// and a final value.
+    fn chinese() -> Cursor<Vec<u8>> {
+        let units = [0x0001, 0x4e2d, 0x0002, 0x4eba, 0x8001, 0x00b0, 0x56fd, 0x8001];
+        let mut trie = Vec::new();
+        for unit in &units {
+            trie.extend_from_slice(&[(unit >> 8) as u8, *unit as u8]);
+        }
+        dict(1, &trie)
+    }
+
+    #[test]
+    fn contains_walks_byte_tries() {
+        let d = DictionaryData::try_init(thai()).expect("Failed to read dictionary");
+        assert!(d.contains("\u{e01}\u{e32}"));
+        assert!(d.contains("\u{e01}\u{e32}\u{e23}"));
+        assert!(d.contains("\u{e02}"));
+        assert!(!d.contains("\u{e01}"));
+        assert!(!d.contains("\u{e01}\u{e32}\u{e23}\u{e23}"));
+        assert!(!d.contains("\u{e03}"));
+        assert!(!d.contains("a"));
+        assert!(!d.contains(""));
+    }
+
+    #[test]
+    fn contains_walks_uchars_tries() {
+        let d = DictionaryData::try_init(chinese()).expect("Failed to read dictionary");
+        assert!(d.contains("中"));
+        assert!(d.contains("中国"));
+        assert!(d.contains("人"));
+        assert!(!d.contains("国"));
+        assert!(!d.contains("中国人"));
+        assert!(!d.contains("人中"));
+    }
+
+    #[test]
+    fn try_init_rejects_bad_dictionaries() {
+        let mut bytes = thai().into_inner();
+        bytes[16] = 2; // format 1 only
+        assert!(DictionaryData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = thai().into_inner();
+        bytes[32 + 19] = 2; // an unknown trie type
+        assert!(DictionaryData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = thai().into_inner();
+        let len = bytes.len();
+        bytes.truncate(len - 1);
+        assert!(DictionaryData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
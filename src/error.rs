@@ -0,0 +1,109 @@
+//! Errors from reading ICU data.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use DataFormat;
+
+#[derive(Debug)]
+pub enum IcuError {
+    /// The data doesn't start with the ICU magic number.
+    BadMagic,
+    /// The data header is inconsistent or isn't for the expected format.
+    HeaderAuth,
+    /// The format version in the header isn't one this crate can read.
+    UnsupportedFormatVersion {
+        format: DataFormat,
+        version: [u8; 4],
+    },
+    /// The data is in a charset family other than ASCII's, like EBCDIC (1).
+    UnsupportedCharset(u8),
+    /// The data's code units aren't the size of a UTF-16 code unit.
+    UnsupportedCharSize(u8),
+    /// A resource bundle's `indexes[]` is too short for its contents.
+    NotEnoughIndexes,
+    /// The data is structurally invalid in a format-specific way.
+    Malformed(&'static str),
+    /// Reading `need` bytes at `pos` would run past the end of the data,
+    /// which is only `len` bytes long.
+    Truncated { pos: u64, need: u64, len: u64 },
+    /// The data, only `len` bytes long, is too small to hold even the
+    /// smallest header.
+    TooSmall { len: u64 },
+    /// The header claims to be `header_size` bytes, more than the whole
+    /// data's `len`.
+    HeaderExceedsFile { header_size: u16, len: u64 },
+    /// The data ended partway through `what`, which starts at `pos`.
+    UnexpectedEof { what: &'static str, pos: u64 },
+    Io(io::Error),
+}
+
+impl fmt::Display for IcuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IcuError::BadMagic => f.write_str("ICU data file error: Not an ICU data file"),
+            IcuError::HeaderAuth => f.write_str(
+                "ICU data file error: Header authentication failed, \
+                 please check if you have a valid ICU data file",
+            ),
+            IcuError::UnsupportedFormatVersion { format, version } => write!(
+                f,
+                "{} format version {}.{}.{}.{} is not supported",
+                format, version[0], version[1], version[2], version[3]
+            ),
+            IcuError::UnsupportedCharset(1) => f.write_str("unsupported charset family EBCDIC"),
+            IcuError::UnsupportedCharset(family) => {
+                write!(f, "unsupported charset family {}", family)
+            }
+            IcuError::UnsupportedCharSize(size) => {
+                write!(f, "unsupported character size of {} bytes", size)
+            }
+            IcuError::NotEnoughIndexes => f.write_str("not enough indexes"),
+            IcuError::Malformed(what) => write!(f, "malformed data: {}", what),
+            IcuError::Truncated { pos, need, len } => write!(
+                f,
+                "data truncated: {} bytes needed at offset {}, but there are only {} bytes",
+                need, pos, len
+            ),
+            IcuError::TooSmall { len } => {
+                write!(f, "data too small for a header: {} bytes", len)
+            }
+            IcuError::HeaderExceedsFile { header_size, len } => write!(
+                f,
+                "header of {} bytes is longer than the data, which is only {} bytes",
+                header_size, len
+            ),
+            IcuError::UnexpectedEof { what, pos } => {
+                write!(f, "unexpected EOF reading {} at offset {}", what, pos)
+            }
+            IcuError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for IcuError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            IcuError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IcuError {
+    fn from(e: io::Error) -> IcuError {
+        // our own errors, passed up through an `io::Result`, come back out
+        // as themselves
+        e.downcast::<IcuError>().unwrap_or_else(IcuError::Io)
+    }
+}
+
+impl From<IcuError> for io::Error {
+    fn from(e: IcuError) -> io::Error {
+        match e {
+            IcuError::Io(e) => e,
+            e @ IcuError::UnexpectedEof { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
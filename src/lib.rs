@@ -1,41 +1,144 @@
-#![feature(try_from)]
 #![allow(dead_code)]
 
 extern crate byteorder;
+#[cfg(feature = "memmap")]
+extern crate memmap2;
+#[cfg(feature = "serde")]
+extern crate serde;
 
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::io::{self, Error, ErrorKind, SeekFrom, prelude::*};
+use std::fmt;
+#[cfg(feature = "memmap")]
+use std::fs::File;
+use std::io::{self, Cursor, Error, ErrorKind, SeekFrom, prelude::*};
+use std::ops::Range;
+#[cfg(feature = "memmap")]
+use std::path::Path;
+use std::str;
+use error::IcuError;
+use util::utf16_to_string;
 use version::Version;
 
+pub mod bidi;
+pub mod brk;
+pub mod bytestrie;
+pub mod case;
+pub mod charname;
+pub mod cnv;
+pub mod cnvalias;
+pub mod collation;
+pub mod dat;
+pub mod dict;
+pub mod error;
+pub mod nfc;
+pub mod pnames;
+pub mod stringprep;
+pub mod trie;
+pub mod uprops;
+mod util;
+pub mod value;
 pub mod version;
+pub mod visit;
+use bidi::BiDiData;
+use brk::BreakData;
+use case::CaseData;
+use charname::CharNameData;
+use cnv::ConverterData;
+use cnvalias::ConverterAliasData;
+use collation::CollationData;
+use dat::DatPackageReader;
+use dict::DictionaryData;
+use nfc::Normalizer2Data;
+use pnames::PropertyAliasData;
+use stringprep::StringPrepData;
+use uprops::UPropsData;
+use value::Value;
 use version::PiecewiseVersion;
+use visit::Visitor;
 
 const MAGIC1: u8 = 0xda;
 const MAGIC2: u8 = 0x27;
 const CHARSET_FAMILY: u8 = 0;
+const EBCDIC_CHARSET_FAMILY: u8 = 1;
 const CHAR_SIZE: u8 = 2;
+/// The size of the smallest header: the header size, the magic number and
+/// a 20-byte UDataInfo, padded to a multiple of 16 bytes.
+const MIN_HEADER_SIZE: u64 = 32;
 
-const MAGIC_NUMBER_CHECK_FAILED: &str = "ICU data file error: Not an ICU data file";
-const HEADER_CHECK_FAILED: &str = "ICU data file error: Header authentication failed, please check if you have a valid ICU data file";
-
-/// Indices for the indexes[] array, located after the header, and
+/// The slots of the indexes[] array, located after the header, and
 /// directly after the root resource.
-const RES_INDEX_LENGTH: u64 = 0;
-const RES_INDEX_KEYS_TOP: u64 = 1;
-const RES_INDEX_RESOURCES_TOP: u64 = 2;
-const RES_INDEX_BUNDLE_TOP: u64 = 3;
-const RES_INDEX_MAX_TABLE_LENGTH: u64 = 4;
-const RES_INDEX_ATTRIBUTES: u64 = 5;
-const RES_INDEX_16BIT_TOP: u64 = 6;
-const RES_INDEX_POOL_CHECKSUM: u64 = 7;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IndexSlot {
+    Length = 0,
+    KeysTop = 1,
+    ResourcesTop = 2,
+    BundleTop = 3,
+    MaxTableLength = 4,
+    Attributes = 5,
+    Bits16Top = 6,
+    PoolChecksum = 7,
+}
+
+impl IndexSlot {
+    /// The byte position of the slot in a bundle with a `header_size`-byte
+    /// header.
+    fn offset(self, header_size: u16) -> u64 {
+        u64::from(header_size) + 4 + 4 * self as u64
+    }
+
+    /// Whether a bundle with `indexes_length` indexes has the slot.
+    fn is_in(self, indexes_length: u32) -> bool {
+        indexes_length > self as u32
+    }
+
+    /// The slot's name in ICU, which errors reading it report.
+    fn name(self) -> &'static str {
+        match self {
+            IndexSlot::Length => "RES_INDEX_LENGTH",
+            IndexSlot::KeysTop => "RES_INDEX_KEYS_TOP",
+            IndexSlot::ResourcesTop => "RES_INDEX_RESOURCES_TOP",
+            IndexSlot::BundleTop => "RES_INDEX_BUNDLE_TOP",
+            IndexSlot::MaxTableLength => "RES_INDEX_MAX_TABLE_LENGTH",
+            IndexSlot::Attributes => "RES_INDEX_ATTRIBUTES",
+            IndexSlot::Bits16Top => "RES_INDEX_16BIT_TOP",
+            IndexSlot::PoolChecksum => "RES_INDEX_POOL_CHECKSUM",
+        }
+    }
+
+    /// The slot's value in `indexes`, which must be long enough to have it.
+    fn of(self, indexes: &[u32]) -> u32 {
+        indexes[self as usize]
+    }
+}
+
+// resource types, from the top nibble of a resource word
+const URES_STRING: u32 = 0;
+const URES_BINARY: u32 = 1;
+const URES_TABLE: u32 = 2;
+const URES_ALIAS: u32 = 3;
+const URES_TABLE32: u32 = 4;
+const URES_TABLE16: u32 = 5;
+const URES_STRING_V2: u32 = 6;
+const URES_INT: u32 = 7;
+const URES_ARRAY: u32 = 8;
+const URES_ARRAY16: u32 = 9;
+const URES_INT_VECTOR: u32 = 14;
 
 // resource attribute bits
 const RES_ATT_NO_FALLBACK: u32 = 1;
 const RES_ATT_IS_POOL_BUNDLE: u32 = 2;
 const RES_ATT_USES_POOL_BUNDLE: u32 = 4;
 
-#[derive(Clone, Copy, Debug)]
+/// How many aliases `follow_aliases` follows before giving up, as in ICU.
+const MAX_ALIAS_DEPTH: usize = 256;
+/// How deeply `dump` descends into nested tables and arrays.
+const MAX_DUMP_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum DataFormat {
     // "ResB"
     ResourceBundle = 0x5265_7342,
@@ -53,12 +156,12 @@ pub enum DataFormat {
     BreakIteration = 0x4272_6b20,
     // "Cfu "
     Spoof = 0x4366_7520,
-    // "SPDR"
+    // "SPRP"
     StringPrep = 0x5350_5250,
     // "BiDi"
     BiDi = 0x4269_4469,
     // "cAsE"
-    Case = 0x6341_5345,
+    Case = 0x6341_7345,
     // "unam"
     CharacterName = 0x756e_616d,
     // "CvAl"
@@ -70,6 +173,30 @@ pub enum DataFormat {
 }
 
 impl DataFormat {
+    /// The format with the four-byte id `sig`, as in a data header, if it's
+    /// one of the known ones. The reverse of `<[u8; 4]>::from`.
+    pub const fn from_signature(sig: [u8; 4]) -> Option<DataFormat> {
+        use DataFormat::*;
+        Some(match &sig {
+            b"ResB" => ResourceBundle,
+            b"UCol" => Collation,
+            b"Dict" => Dictionary,
+            b"CmnD" => Dat,
+            b"Nrm2" => Normalized2,
+            b"UPro" => CharacterProperty,
+            b"Brk " => BreakIteration,
+            b"Cfu " => Spoof,
+            b"SPRP" => StringPrep,
+            b"BiDi" => BiDi,
+            b"cAsE" => Case,
+            b"unam" => CharacterName,
+            b"CvAl" => ConverterAlias,
+            b"cnvt" => Converter,
+            b"pnam" => PropertyAlias,
+            _ => return None,
+        })
+    }
+
     fn is_acceptable_version(&self, format_version: [u8; 4]) -> bool {
         use DataFormat::*;
         match *self {
@@ -78,20 +205,18 @@ impl DataFormat {
                     || format_version[0] == 3
             }
             Collation => format_version[0] == 5,
-            Dictionary => true,
+            Dictionary => format_version[0] == 1,
             Dat => format_version[0] == 1,
             Normalized2 => format_version[0] == 3,
             CharacterProperty => format_version[0] == 7,
             BreakIteration => {
-                let ver = u32::from(format_version[0]) << 24 + u32::from(format_version[1])
-                    << 16 + u32::from(format_version[2])
-                    << 8 + u32::from(format_version[3]);
-                ver == 0x04000000
-            }
-            Spoof => {
-                format_version[0] == 2 || format_version[1] != 0 || format_version[2] != 0
-                    || format_version[3] != 0
+                let ver = (u32::from(format_version[0]) << 24)
+                    | (u32::from(format_version[1]) << 16)
+                    | (u32::from(format_version[2]) << 8)
+                    | u32::from(format_version[3]);
+                ver == 0x0400_0000
             }
+            Spoof => format_version[0] == 2,
             StringPrep => {
                 format_version[0] == 0x3 && format_version[2] == 0x5 && format_version[3] == 0x2
             }
@@ -107,12 +232,80 @@ impl DataFormat {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+const DATA_FORMATS: [DataFormat; 15] = [
+    DataFormat::ResourceBundle,
+    DataFormat::Collation,
+    DataFormat::Dictionary,
+    DataFormat::Dat,
+    DataFormat::Normalized2,
+    DataFormat::CharacterProperty,
+    DataFormat::BreakIteration,
+    DataFormat::Spoof,
+    DataFormat::StringPrep,
+    DataFormat::BiDi,
+    DataFormat::Case,
+    DataFormat::CharacterName,
+    DataFormat::ConverterAlias,
+    DataFormat::Converter,
+    DataFormat::PropertyAlias,
+];
+
+/// The four bytes of the format id, as they appear in a data header.
+impl From<DataFormat> for [u8; 4] {
+    fn from(format: DataFormat) -> [u8; 4] {
+        let id = format as u32;
+        [(id >> 24) as u8, (id >> 16) as u8, (id >> 8) as u8, id as u8]
+    }
+}
+
+impl TryFrom<[u8; 4]> for DataFormat {
+    type Error = io::Error;
+
+    fn try_from(id: [u8; 4]) -> Result<Self, Self::Error> {
+        DataFormat::from_signature(id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown data format {:?}", String::from_utf8_lossy(&id)),
+            )
+        })
+    }
+}
+
+/// Shows the four-character format id, like `ResB`.
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &b in &<[u8; 4]>::from(*self) {
+            write!(f, "{}", char::from(b))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Order {
     BigEndian,
     LittleEndian,
 }
 
+impl Order {
+    /// The byte order of the target platform.
+    pub fn native() -> Order {
+        if cfg!(target_endian = "big") {
+            Order::BigEndian
+        } else {
+            Order::LittleEndian
+        }
+    }
+
+    /// The other byte order.
+    pub fn opposite(self) -> Order {
+        match self {
+            Order::BigEndian => Order::LittleEndian,
+            Order::LittleEndian => Order::BigEndian,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct OrderedReader<R>
 where
@@ -120,14 +313,58 @@ where
 {
     reader: R,
     order: Order,
+    /// The length of the stream, found once when it's wrapped.
+    len: u64,
 }
 
 impl<R> OrderedReader<R>
 where
     R: Read + Seek,
 {
-    pub fn wrap(reader: R, order: Order) -> OrderedReader<R> {
-        OrderedReader { reader, order }
+    /// Wraps `reader`, finding the length of the stream by seeking to its
+    /// end and back.
+    pub fn wrap(mut reader: R, order: Order) -> io::Result<OrderedReader<R>> {
+        let pos = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
+        Ok(OrderedReader { reader, order, len })
+    }
+
+    /// The length of the stream in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the stream has no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The wrapped reader, at whatever position reading left it.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Seeks to `pos`, first checking that `need` bytes follow it before the
+    /// end of the stream, so that a read of them can't run off the end.
+    pub fn seek_checked(&mut self, pos: u64, need: u64) -> io::Result<()> {
+        let len = self.len;
+        if pos.checked_add(need).is_none_or(|end| end > len) {
+            return Err(IcuError::Truncated { pos, need, len }.into());
+        }
+        self.reader.seek(SeekFrom::Start(pos))?;
+        Ok(())
+    }
+
+    /// Reads `count` 32-bit integers starting at `start`, first checking
+    /// that they all lie within the stream.
+    pub fn read_u32_array(&mut self, start: u64, count: usize) -> io::Result<Vec<u32>> {
+        self.seek_checked(start, 4 * count as u64)?;
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            values.push(EndianReader::read_u32(self)?);
+        }
+        Ok(values)
     }
 }
 
@@ -158,9 +395,192 @@ where
     }
 }
 
-trait EndianReader: Read + Seek {
+/// ICU data held in memory, so that streams that can't seek, like sockets
+/// or decompressors, can still be parsed.
+#[derive(Clone, Debug)]
+pub struct BufferedSource<'a> {
+    inner: Cursor<Cow<'a, [u8]>>,
+}
+
+impl BufferedSource<'static> {
+    /// Reads all of `reader` into memory.
+    pub fn read_from<T>(mut reader: T) -> io::Result<BufferedSource<'static>>
+    where
+        T: Read,
+    {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(BufferedSource::from(data))
+    }
+}
+
+impl<'a> From<Vec<u8>> for BufferedSource<'a> {
+    fn from(data: Vec<u8>) -> BufferedSource<'a> {
+        BufferedSource {
+            inner: Cursor::new(Cow::Owned(data)),
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for BufferedSource<'a> {
+    fn from(data: &'a [u8]) -> BufferedSource<'a> {
+        BufferedSource {
+            inner: Cursor::new(Cow::Borrowed(data)),
+        }
+    }
+}
+
+impl<'a> Read for BufferedSource<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+impl<'a> Seek for BufferedSource<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos)
+    }
+}
+
+/// A reader over the `len` bytes at `start` of another reader, which it
+/// makes look like a stream of its own, as for an item of an archive.
+///
+/// Positions count from `start`, and reads stop at `start + len` even if
+/// the underlying reader goes on. Seeking past the end of the slice is an
+/// error, rather than leaving the reader somewhere it can't read from.
+#[derive(Debug)]
+pub struct SliceReader<R>
+where
+    R: Read + Seek,
+{
+    inner: R,
+    start: u64,
+    len: u64,
+    /// The position in the slice, which `inner` is kept at.
+    pos: u64,
+}
+
+impl<R> SliceReader<R>
+where
+    R: Read + Seek,
+{
+    /// Bounds `inner` to the `len` bytes at `start`, positioned at the
+    /// first of them.
+    pub fn new(mut inner: R, start: u64, len: u64) -> io::Result<SliceReader<R>> {
+        if start.checked_add(len).is_none() {
+            return Err(Error::new(ErrorKind::InvalidInput, "slice out of range"));
+        }
+        inner.seek(SeekFrom::Start(start))?;
+        Ok(SliceReader {
+            inner,
+            start,
+            len,
+            pos: 0,
+        })
+    }
+
+    /// The length of the slice in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the slice has no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The underlying reader, wherever the slice left it.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R> Read for SliceReader<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let left = self.len - self.pos;
+        let n = (buf.len() as u64).min(left) as usize;
+        let n = self.inner.read(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R> Seek for SliceReader<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(delta) => (self.len, delta),
+            SeekFrom::Current(delta) => (self.pos, delta),
+        };
+        let pos = match base.checked_add_signed(delta) {
+            Some(pos) if pos <= self.len => pos,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "seek out of the slice")),
+        };
+        self.inner.seek(SeekFrom::Start(self.start + pos))?;
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+/// ICU data in a memory-mapped file, so that processes reading the same
+/// large file, like `icudt*.dat`, share its pages instead of each holding
+/// a copy.
+///
+/// The mapping lives as long as the source. Its contents must not change
+/// while it's mapped: if another process writes to or truncates the file,
+/// reads through the mapping are undefined behavior, not just wrong.
+#[cfg(feature = "memmap")]
+#[derive(Debug)]
+pub struct MappedSource {
+    inner: Cursor<memmap2::Mmap>,
+}
+
+#[cfg(feature = "memmap")]
+impl MappedSource {
+    /// Maps the file at `path` into memory, read-only.
+    ///
+    /// The file must not be modified for as long as the source exists;
+    /// see the type's documentation.
+    pub fn open(path: &Path) -> io::Result<MappedSource> {
+        let file = File::open(path)?;
+        // safe as long as the file isn't modified while it's mapped, which
+        // the caller is told to ensure
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(MappedSource {
+            inner: Cursor::new(map),
+        })
+    }
+}
+
+#[cfg(feature = "memmap")]
+impl Read for MappedSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(feature = "memmap")]
+impl Seek for MappedSource {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Reads integers in the byte order of the data being read.
+///
+/// These methods share their names with `byteorder::ReadBytesExt`'s, so
+/// with both traits in scope, call them as `EndianReader::read_u32(&mut r)`.
+pub trait EndianReader: Read + Seek {
+    /// The byte order of the data.
     fn order(&self) -> Order;
 
+    /// Reads a 16-bit integer.
     fn read_u16(&mut self) -> Result<u16, io::Error> {
         match self.order() {
             Order::BigEndian => <Self as ReadBytesExt>::read_u16::<BigEndian>(self),
@@ -168,6 +588,7 @@ trait EndianReader: Read + Seek {
         }
     }
 
+    /// Reads a 32-bit integer.
     fn read_u32(&mut self) -> Result<u32, io::Error> {
         match self.order() {
             Order::BigEndian => <Self as ReadBytesExt>::read_u32::<BigEndian>(self),
@@ -175,35 +596,197 @@ trait EndianReader: Read + Seek {
         }
     }
 
+    /// Reads a signed 16-bit integer.
+    fn read_i16(&mut self) -> Result<i16, io::Error> {
+        self.read_u16().map(|v| v as i16)
+    }
+
+    /// Reads a signed 32-bit integer.
+    fn read_i32(&mut self) -> Result<i32, io::Error> {
+        self.read_u32().map(|v| v as i32)
+    }
+
+    /// Reads a 32-bit resource word and sign-extends its low 28 bits, the
+    /// value of a `URES_INT` resource.
+    fn read_int28(&mut self) -> Result<i32, io::Error> {
+        self.read_u32().map(int28)
+    }
+
+    /// Reads a 16-bit integer, saying it was `what` being read, and where,
+    /// if the data ends first.
+    fn read_u16_ctx(&mut self, what: &'static str) -> Result<u16, io::Error> {
+        let pos = self.stream_position()?;
+        self.read_u16().map_err(|e| eof_context(e, what, pos))
+    }
+
+    /// Reads a 32-bit integer, saying it was `what` being read, and where,
+    /// if the data ends first.
+    fn read_u32_ctx(&mut self, what: &'static str) -> Result<u32, io::Error> {
+        let pos = self.stream_position()?;
+        self.read_u32().map_err(|e| eof_context(e, what, pos))
+    }
+
+    /// Seeks to `pos` and reads a byte there.
     fn read_u8_from(&mut self, pos: SeekFrom) -> Result<u8, io::Error> {
         self.seek(pos)?;
         <Self as ReadBytesExt>::read_u8(self)
     }
 
+    /// Seeks to `pos` and reads a 16-bit integer there.
     fn read_u16_from(&mut self, pos: SeekFrom) -> Result<u16, io::Error> {
         self.seek(pos)?;
         self.read_u16()
     }
 
+    /// Seeks to `pos` and reads a 32-bit integer there.
     fn read_u32_from(&mut self, pos: SeekFrom) -> Result<u32, io::Error> {
         self.seek(pos)?;
         self.read_u32()
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A resource bundle ("ResB") read from `R`.
+///
+/// The reader holds nothing but `R` and plain values read from the header,
+/// so it's `Send` when `R` is and `Sync` when `R` is: an owned reader like
+/// `File`, `Cursor<Vec<u8>>` or `MappedSource` makes it both, and a
+/// `SliceReader<&mut R>` from a `DatPackageReader` makes it both when `R`
+/// is. Lookups take `&mut self`, so sharing one between threads still
+/// takes a `Mutex`.
+#[derive(Clone, Debug)]
 pub struct ResourceBundleReader<R>
 where
     R: Read + Seek,
 {
     reader: OrderedReader<R>,
-    data_version: Version,
-    root_resource: u32,
+    header_size: u16,
+    data_format: DataFormat,
+    format_version: [u8; 4],
+    data_version: PiecewiseVersion,
+    root_resource: BundleKey,
+    indexes: Vec<u32>,
+    max_table_length: u32,
+    /// The end of the resource data, in 32-bit units from the root resource.
+    bundle_top: u32,
+    keys: Keys,
+    units_16_base: u64,
+    units_16_length: u32,
+    /// The byte position of the first 32-bit resource, past the 16-bit
+    /// units.
+    resources_base: u64,
+    /// The end of the 32-bit resources, in 32-bit units from the root
+    /// resource.
+    resources_top: u32,
     no_fallback: bool,
     is_pool_bundle: bool,
     uses_pool_bundle: bool,
     pool_string_index_limit: u32,
     pool_string_index_16_limit: u32,
+    pool_checksum: Option<u32>,
+    pool_units: Option<Vec<u16>>,
+}
+
+/// A bundle's keys, and those it shares through its pool bundle.
+#[derive(Clone, Debug)]
+struct Keys {
+    local: Vec<u8>,
+    /// The offset of the first local key.
+    base: u32,
+    /// The end of the local keys for 16-bit key offsets.
+    local_limit: u32,
+    pool: Option<Vec<u8>>,
+    /// Whether the bundle is in the EBCDIC charset family, whose tables
+    /// sort their keys in EBCDIC byte order even though they're kept here
+    /// in ASCII.
+    ebcdic: bool,
+}
+
+impl Keys {
+    /// The NUL-terminated key at `offset`, which is into the pool bundle's
+    /// keys if the high bit is set.
+    fn get(&self, offset: u32) -> io::Result<&str> {
+        let key = self.bytes(offset)?;
+        // keys are in the invariant character set, a subset of ASCII
+        if !key.is_ascii() {
+            return Err(Error::new(ErrorKind::InvalidData, "non-ASCII key"));
+        }
+        str::from_utf8(key).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// The bytes of the key at `offset`, without its NUL, borrowed from the
+    /// keys and not yet checked to be a string.
+    fn bytes(&self, offset: u32) -> io::Result<&[u8]> {
+        let (keys, start) = if offset & 0x8000_0000 != 0 {
+            let pool = self.pool.as_ref().ok_or_else(no_pool)?;
+            (pool, Some(offset & 0x7fff_ffff))
+        } else {
+            (&self.local, offset.checked_sub(self.base))
+        };
+        let bytes = start
+            .and_then(|start| keys.get(start as usize..))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "key offset out of range"))?;
+        match bytes.iter().position(|&b| b == 0) {
+            Some(end) => Ok(&bytes[..end]),
+            None => Err(Error::new(ErrorKind::InvalidData, "unterminated key")),
+        }
+    }
+
+    /// `key` in the byte order the bundle's tables sort keys in, or `None`
+    /// if it has characters no key in an EBCDIC bundle can have.
+    fn in_table_order<'k>(&self, key: &'k str) -> Option<Cow<'k, [u8]>> {
+        if !self.ebcdic {
+            return Some(Cow::Borrowed(key.as_bytes()));
+        }
+        key.bytes().map(ebcdic_from_ascii).collect::<Option<Vec<_>>>().map(Cow::Owned)
+    }
+
+    /// Compares the key at `offset` with `key`, as given by
+    /// `in_table_order`, in the order the bundle's tables sort keys.
+    fn cmp(&self, offset: u32, key: &[u8]) -> io::Result<Ordering> {
+        let stored = self.bytes(offset)?;
+        if !self.ebcdic {
+            return Ok(stored.cmp(key));
+        }
+        // bytes that weren't invariant characters were read as 0xff
+        let ebcdic = |&b: &u8| ebcdic_from_ascii(b).unwrap_or(0xff);
+        Ok(stored.iter().map(ebcdic).cmp(key.iter().cloned()))
+    }
+
+    /// The `(offset, key)` of each local key, in the order they're stored.
+    ///
+    /// NULs padding between keys are skipped, as are the bytes padding out
+    /// the last word, which aren't NUL-terminated. Keys that aren't ASCII,
+    /// which lookups reject, are skipped too.
+    fn local(&self) -> impl Iterator<Item = (u32, &str)> {
+        let (keys, base) = (&self.local, self.base);
+        let ends = keys.iter().enumerate().filter(|&(_, &b)| b == 0).map(|(end, _)| end);
+        ends.scan(0, move |start, end| {
+            let key = (*start, &keys[*start..end]);
+            *start = end + 1;
+            Some(key)
+        })
+        .filter(|&(_, key)| !key.is_empty() && key.is_ascii())
+        .filter_map(move |(start, key)| Some((base + start as u32, str::from_utf8(key).ok()?)))
+    }
+
+    /// Maps a 16-bit key offset to a 32-bit one: those past the local keys
+    /// continue into the pool bundle's keys.
+    fn key_16(&self, key: u16) -> u32 {
+        let key = u32::from(key);
+        if key < self.local_limit {
+            key
+        } else {
+            0x8000_0000 | (key - self.local_limit)
+        }
+    }
+}
+
+fn not_a_number(s: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("string {:?} isn't a number", s))
+}
+
+fn no_pool() -> Error {
+    Error::new(ErrorKind::InvalidInput, "resource is in an unattached pool bundle")
 }
 
 #[allow(unused_variables)]
@@ -211,223 +794,4104 @@ impl<R> ResourceBundleReader<R>
 where
     R: Read + Seek,
 {
-    pub fn try_init(mut bytes: R, data_format: DataFormat) -> io::Result<ResourceBundleReader<R>> {
-        check_magic(&mut bytes)?;
-        let order = if read_endianness(&mut bytes)? == 1 {
-            Order::BigEndian
-        } else {
-            Order::LittleEndian
-        };
-        let mut reader = OrderedReader::wrap(bytes, order);
-        let (header_size, data_version) = read_header(&mut reader, data_format)?;
-        let root_resource = <OrderedReader<R> as EndianReader>::read_u32(&mut reader)?;
-        let offset = |n| {
-            header_size as u64 + 4 + n
-        };
+    pub fn try_init(
+        bytes: R,
+        data_format: DataFormat,
+    ) -> Result<ResourceBundleReader<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        // Bundles built for EBCDIC machines differ only in their keys,
+        // which are translated to ASCII once they're read; tables still sort
+        // them in EBCDIC order, which lookups follow.
+        let families = [CHARSET_FAMILY, EBCDIC_CHARSET_FAMILY];
+        let (header_size, data_version, charset_family) =
+            read_header_in(&mut reader, data_format, &families)?;
+        let root_resource = BundleKey(reader.read_u32_ctx("the root resource")?);
+        match root_resource.type_tag() {
+            URES_TABLE | URES_TABLE16 | URES_TABLE32 | URES_ALIAS => {}
+            _ => return Err(IcuError::Malformed("root resource isn't a table or alias")),
+        }
 
-        let indexes_0 = EndianReader::read_u32(&mut reader)?;
+        let indexes_0 = reader.read_u32_ctx(IndexSlot::Length.name())?;
         let indexes_length = indexes_0 & 0xff;
-        if indexes_length as u64 <= RES_INDEX_MAX_TABLE_LENGTH {
-            return Err(Error::new(ErrorKind::InvalidData, "not enough indexes"));
+        if !IndexSlot::MaxTableLength.is_in(indexes_length) {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        let len = reader.len();
+        if IndexSlot::Length.offset(header_size) + 4 * u64::from(indexes_length) > len {
+            return Err(IcuError::NotEnoughIndexes);
         }
+        let indexes =
+            reader.read_u32_array(IndexSlot::Length.offset(header_size), indexes_length as usize)?;
 
+        // Bundles with no attributes slot predate the attributes, and as in
+        // ICU's res_init they fall back and don't share a pool bundle.
         let mut no_fallback = false;
         let mut is_pool_bundle = false;
         let mut uses_pool_bundle = false;
         let mut pool_string_index_limit = 0;
         let mut pool_string_index_16_limit = 0;
 
-        reader.seek(SeekFrom::Start(offset(RES_INDEX_BUNDLE_TOP)))?;
-        let max_offset = EndianReader::read_u32(&mut reader)? - 1;
+        let bundle_top = IndexSlot::BundleTop.of(&indexes);
+        let max_table_length = IndexSlot::MaxTableLength.of(&indexes);
 
-        reader.seek(SeekFrom::Start(16))?;
-        let file_format_major_version = reader.read_u8()?;
+        reader.seek_checked(16, 4)?;
+        let mut format_version = [0; 4];
+        reader.read_exact(&mut format_version)?;
+        let file_format_major_version = format_version[0];
         if file_format_major_version >= 3 {
             pool_string_index_limit = indexes_0 >> 8;
         }
 
-        if indexes_length as u64 > RES_INDEX_ATTRIBUTES {
-            reader.seek(SeekFrom::Start(offset(RES_INDEX_ATTRIBUTES)))?;
-            let att = EndianReader::read_u32(&mut reader)?;
+        // Format 1.2 added the attributes word with only RES_ATT_NO_FALLBACK,
+        // and the pool bits and limits came with format 2. A format 1.1
+        // bundle's slot can be there and hold anything.
+        let has_attributes = format_version[..2] >= [1, 2][..];
+        let att = if IndexSlot::Attributes.is_in(indexes_length) {
+            IndexSlot::Attributes.of(&indexes)
+        } else {
+            0
+        };
+        if has_attributes {
             no_fallback = (att & RES_ATT_NO_FALLBACK) != 0;
+        }
+        if file_format_major_version >= 2 {
             is_pool_bundle = (att & RES_ATT_IS_POOL_BUNDLE) != 0;
             uses_pool_bundle = (att & RES_ATT_USES_POOL_BUNDLE) != 0;
             pool_string_index_limit |= (att & 0xf000) << 12; // bits 15..12 -> 27..24
             pool_string_index_16_limit = att >> 16;
         }
 
-        let key_bytes: Vec<u8>;
-        let mut local_key_limit = 0;
+        let mut pool_checksum = None;
+        if IndexSlot::PoolChecksum.is_in(indexes_length) {
+            pool_checksum = Some(IndexSlot::PoolChecksum.of(&indexes));
+        } else if is_pool_bundle || uses_pool_bundle {
+            return Err(IcuError::NotEnoughIndexes);
+        }
 
+        // Keys of regular bundles are addressed from the root resource word,
+        // while a pool bundle's are addressed from the start of its keys.
         let keys_bottom = 1 + indexes_length;
-        reader.seek(SeekFrom::Start(offset(RES_INDEX_KEYS_TOP)))?;
-        let keys_top = EndianReader::read_u32(&mut reader)?;
+        let keys_top = IndexSlot::KeysTop.of(&indexes);
+        if u64::from(header_size) + (u64::from(keys_top) << 2) > len {
+            return Err(IcuError::Malformed("keys run past the end of the data"));
+        }
+        let mut keys = Vec::new();
+        let mut keys_base = 0;
+        let mut local_key_limit = 0;
         if keys_top > keys_bottom {
-            if is_pool_bundle {
-                key_bytes = Vec::with_capacity((keys_top - keys_bottom) as usize);
-            } else {
-                local_key_limit = (keys_top as usize) << 2;
-                key_bytes = Vec::with_capacity(local_key_limit);
+            if !is_pool_bundle {
+                keys_base = keys_bottom << 2;
+                local_key_limit = keys_top << 2;
             }
-            
-        } else {
-            key_bytes = Vec::new();
+            keys = vec![0; ((keys_top - keys_bottom) << 2) as usize];
+            let keys_pos = u64::from(header_size) + u64::from(keys_bottom << 2);
+            reader.seek_checked(keys_pos, keys.len() as u64)?;
+            reader.read_exact(&mut keys)?;
+            if charset_family == EBCDIC_CHARSET_FAMILY {
+                for b in &mut keys {
+                    *b = ascii_from_ebcdic(*b);
+                }
+            }
+        }
+
+        // The 16-bit units, if any, lie between the keys and the 16-bit top.
+        // Format 1 has none, and strings are all 32-bit-addressed there.
+        let mut units_16_length = 0;
+        let resources_top = IndexSlot::ResourcesTop.of(&indexes);
+        if file_format_major_version >= 2 && IndexSlot::Bits16Top.is_in(indexes_length) {
+            let top_16 = IndexSlot::Bits16Top.of(&indexes);
+            if top_16 < keys_top || top_16 > resources_top {
+                return Err(IcuError::Malformed("16-bit units overlap the keys or resources"));
+            }
+            units_16_length = (top_16 - keys_top) * 2;
+        }
+        // and the 32-bit resources go from there up to the resources top
+        let units_16_base = u64::from(header_size) + u64::from(keys_top << 2);
+        let resources_base = units_16_base + 2 * u64::from(units_16_length);
+        if u64::from(header_size) + 4 * u64::from(resources_top) < resources_base {
+            return Err(IcuError::Malformed("resources top is below the keys"));
         }
 
         Ok(ResourceBundleReader {
             reader,
-            data_version: Version::try_from(data_version)?,
+            header_size,
+            data_format,
+            format_version,
+            data_version,
             root_resource,
+            indexes,
+            max_table_length,
+            bundle_top,
+            keys: Keys {
+                local: keys,
+                base: keys_base,
+                local_limit: local_key_limit,
+                pool: None,
+                ebcdic: charset_family == EBCDIC_CHARSET_FAMILY,
+            },
+            units_16_base,
+            units_16_length,
+            resources_base,
+            resources_top,
             no_fallback,
             is_pool_bundle,
             uses_pool_bundle,
             pool_string_index_limit,
             pool_string_index_16_limit,
+            pool_checksum,
+            pool_units: None,
         })
     }
 
-    pub fn version(&self) -> Version {
+    /// The Unicode version of the data, if its data version is one.
+    ///
+    /// Resource bundles always carry the data version 1.4, which isn't a
+    /// Unicode version, so this is `None` for them.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
         self.data_version
     }
 
-    pub fn root_resource(&self) -> u32 {
-        self.root_resource
+    /// The raw bytes of the header, from its size field up to the root
+    /// resource, including any comment after the `UDataInfo`.
+    pub fn header_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; usize::from(self.header_size)];
+        self.reader.seek_checked(0, bytes.len() as u64)?;
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
     }
-}
 
-pub fn read_header<R>(
-    reader: &mut OrderedReader<R>,
-    data_format: DataFormat,
-) -> io::Result<(u16, PiecewiseVersion)>
-where
-    R: Read + Seek,
-{
-    let header_size = read_header_size(reader)?;
-    validate_format_version(reader, data_format)?;
+    /// The raw bytes of the header's `UDataInfo`, which starts with its own
+    /// 16-bit size and holds the byte order, format and versions.
+    pub fn data_info(&mut self) -> io::Result<Vec<u8>> {
+        let size = self.reader.read_u16_from(SeekFrom::Start(4))?;
+        if 4 + size > self.header_size {
+            return Err(Error::new(ErrorKind::InvalidData, "UDataInfo overruns the header"));
+        }
+        let mut bytes = vec![0; usize::from(size)];
+        self.reader.seek(SeekFrom::Start(4))?;
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
 
-    let data_version = read_data_version(reader)?;
-    reader.seek(SeekFrom::Start(header_size.into()))?;
-    Ok((header_size, data_version))
-}
+    /// The major format version of the bundle: 1 for the legacy layout,
+    /// or 2 and 3 for the compact layout with 16-bit units.
+    pub fn format_major_version(&self) -> u8 {
+        self.format_version[0]
+    }
 
-fn read_data_version<R>(reader: &mut OrderedReader<R>) -> io::Result<PiecewiseVersion>
-where
-    R: Read + Seek,
-{
-    reader.seek(SeekFrom::Start(20))?;
-    let data_version = (
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-    );
-    Ok(data_version)
-}
+    /// The bundle's indexes[] array, from RES_INDEX_LENGTH on, as it's
+    /// stored after the root resource.
+    pub fn indexes(&self) -> &[u32] {
+        &self.indexes
+    }
 
-fn read_header_size<R>(reader: &mut OrderedReader<R>) -> io::Result<u16>
-where
-    R: Read + Seek,
-{
-    let header_size = reader.read_u16_from(SeekFrom::Start(0))?;
-    let data_info_size = reader.read_u16_from(SeekFrom::Start(4))?;
-    if data_info_size < 20 || header_size < (data_info_size + 4) {
-        return Err(Error::new(ErrorKind::InvalidData, "header size error"));
+    /// What the bundle's header and indexes say about it, all at once.
+    pub fn info(&self) -> BundleInfo {
+        let units_16_size = 2 * self.units_16_length;
+        let resources = self.resource_region();
+        BundleInfo {
+            format: self.data_format,
+            format_version: self.format_version,
+            data_version: self.data_version,
+            version: self.version(),
+            order: self.reader.order,
+            header_size: self.header_size,
+            data_size: self.data_size(),
+            keys_size: self.keys.local.len() as u32,
+            units_16_size,
+            resources_size: (resources.end - resources.start) as u32,
+            no_fallback: self.no_fallback,
+            is_pool_bundle: self.is_pool_bundle,
+            uses_pool_bundle: self.uses_pool_bundle,
+        }
     }
-    Ok(header_size)
-}
 
-fn check_magic<B>(bytes: &mut B) -> io::Result<()>
-where
-    B: Read + Seek,
-{
-    bytes.seek(SeekFrom::Start(2))?;
-    let magic1 = bytes.read_u8()?;
-    let magic2 = bytes.read_u8()?;
-    if magic1 != MAGIC1 || magic2 != MAGIC2 {
-        Err(Error::new(
-            ErrorKind::InvalidData,
-            MAGIC_NUMBER_CHECK_FAILED,
-        ))
-    } else {
-        Ok(())
+    pub fn root_resource(&self) -> BundleKey {
+        self.root_resource
     }
-}
 
-fn read_endianness<B>(bytes: &mut B) -> io::Result<u8>
-where
-    B: Read + Seek,
-{
-    bytes.seek(SeekFrom::Start(8))?;
-    let big_endian = bytes.read_u8()?;
-    let charset_family = bytes.read_u8()?;
-    let char_size = bytes.read_u8()?;
-    if big_endian > 1 || charset_family != CHARSET_FAMILY || char_size != CHAR_SIZE {
-        Err(Error::new(ErrorKind::InvalidData, HEADER_CHECK_FAILED))
-    } else {
-        Ok(big_endian)
+    /// The length of the bundle's longest table, for sizing buffers when
+    /// walking its tables.
+    pub fn max_table_length(&self) -> u32 {
+        self.max_table_length
     }
-}
 
-fn validate_format_version<R>(
-    reader: &mut OrderedReader<R>,
-    data_format: DataFormat,
-) -> io::Result<()>
-where
-    R: Read + Seek,
-{
-    let val = data_format as u32;
-    reader.seek(SeekFrom::Start(12))?;
-    let df = [
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-    ];
-    if df[0] != ((val >> 24) as u8) || df[1] != ((val >> 16) as u8) || df[2] != ((val >> 8) as u8)
-        || df[3] != (val as u8)
+    /// The size of the bundle's data in bytes, from the root resource word
+    /// through the last resource, not counting the header.
+    pub fn data_size(&self) -> u32 {
+        self.bundle_top.saturating_mul(4)
+    }
+
+    /// The byte positions of the 32-bit resources, from the end of the
+    /// 16-bit units, or of the keys before format 2, up to
+    /// RES_INDEX_RESOURCES_TOP.
+    pub fn resource_region(&self) -> Range<u64> {
+        let end = u64::from(self.header_size) + 4 * u64::from(self.resources_top);
+        self.resources_base..end
+    }
+
+    /// The byte position of the 32-bit resource the word `res` points to.
+    ///
+    /// This is `None` for the types that live in the word itself or in the
+    /// 16-bit units, and for offset 0, which is each type's empty value.
+    pub fn resource_position(&self, res: BundleKey) -> Option<u64> {
+        match res.type_tag() {
+            _ if res.offset() == 0 => None,
+            URES_STRING | URES_BINARY | URES_TABLE | URES_ALIAS | URES_TABLE32 | URES_ARRAY
+            | URES_INT_VECTOR => Some(self.position(res.offset())),
+            _ => None,
+        }
+    }
+
+    /// Every resource reachable from the root resource that's stored in the
+    /// bundle, with its byte position, in the order they're stored.
+    ///
+    /// Resources in the 16-bit units are included along with the 32-bit
+    /// ones; integers, empty values and strings in a pool bundle, which
+    /// take no space here, aren't. A resource reached more than once, or
+    /// from a table or array inside itself, is listed once. Aliases aren't
+    /// followed, since whatever they point to within the bundle is reached
+    /// from the root anyway, so alias cycles can't trap the walk.
+    pub fn resources_by_offset(&mut self) -> io::Result<Vec<(BundleKey, u64)>> {
+        let mut seen = HashSet::new();
+        let mut pending = vec![self.root_resource];
+        let mut found = Vec::new();
+        while let Some(res) = pending.pop() {
+            if !seen.insert(res) {
+                continue;
+            }
+            if let Some(pos) = self.stored_position(res) {
+                found.push((res, pos));
+            }
+            match res.type_tag() {
+                URES_TABLE | URES_TABLE16 | URES_TABLE32 => {
+                    for i in 0..self.table_len(res)? {
+                        pending.push(self.table_entry_at(res, i)?.1);
+                    }
+                }
+                URES_ARRAY | URES_ARRAY16 => {
+                    for i in 0..self.array_len(res)? {
+                        pending.extend(self.array_item(res, i)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        found.sort_by_key(|&(res, pos)| (pos, res));
+        Ok(found)
+    }
+
+    /// The byte position of `res`, in the 16-bit units or among the 32-bit
+    /// resources, if it's stored in this bundle at all.
+    fn stored_position(&self, res: BundleKey) -> Option<u64> {
+        let offset = res.offset();
+        match res.type_tag() {
+            _ if offset == 0 => None,
+            URES_TABLE16 | URES_ARRAY16 => Some(self.units_16_base + 2 * u64::from(offset)),
+            URES_STRING_V2 if offset >= self.pool_string_index_limit => {
+                let index = offset - self.pool_string_index_limit;
+                Some(self.units_16_base + 2 * u64::from(index))
+            }
+            _ => self.resource_position(res),
+        }
+    }
+
+    /// Whether lookups in this bundle must not fall back to parent locales.
+    pub fn no_fallback(&self) -> bool {
+        self.no_fallback
+    }
+
+    /// How lookups that miss in this bundle fall back, from its
+    /// attributes.
+    pub fn fallback_behavior(&self) -> FallbackBehavior {
+        if self.no_fallback {
+            FallbackBehavior::None
+        } else {
+            FallbackBehavior::Full
+        }
+    }
+
+    /// The locale the whole bundle is an alias for, from the `%%ALIAS`
+    /// string in its root table, as in the bundles for deprecated locale
+    /// ids.
+    pub fn locale_alias(&mut self) -> io::Result<Option<String>> {
+        self.get_string("%%ALIAS")
+    }
+
+    /// The locale the bundle falls back to instead of its truncation, from
+    /// the `%%Parent` string in its root table.
+    ///
+    /// `fallback_chain_with` builds fallback chains that go through these.
+    pub fn explicit_parent(&mut self) -> io::Result<Option<String>> {
+        self.get_string("%%Parent")
+    }
+
+    /// Whether this is a pool bundle, holding keys and strings shared by
+    /// other bundles.
+    pub fn is_pool_bundle(&self) -> bool {
+        self.is_pool_bundle
+    }
+
+    /// Whether this bundle needs a pool bundle attached with `with_pool`.
+    pub fn uses_pool_bundle(&self) -> bool {
+        self.uses_pool_bundle
+    }
+
+    /// The checksum of the pool bundle's keys, as stored in the pool bundle
+    /// itself and in the bundles that use it, if the bundle has one.
+    pub fn pool_checksum(&self) -> Option<u32> {
+        self.pool_checksum
+    }
+
+    /// The byte position the reader is at, to come back to later with
+    /// `restore_position`.
+    ///
+    /// Every lookup seeks to what it reads, so saving and restoring the
+    /// position is only needed by callers reading the file themselves, in
+    /// between lookups.
+    pub fn save_position(&mut self) -> io::Result<u64> {
+        self.reader.stream_position()
+    }
+
+    /// Goes back to a position from `save_position`.
+    pub fn restore_position(&mut self, pos: u64) -> io::Result<()> {
+        self.seek_to(pos)
+    }
+
+    /// The reader the bundle was read from, at whatever position reading
+    /// left it.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Seeks to byte `pos` of the file, which must lie within it.
+    pub fn seek_to(&mut self, pos: u64) -> io::Result<()> {
+        self.reader.seek_checked(pos, 0)
+    }
+
+    /// Attaches the pool bundle this bundle shares keys and strings with.
+    ///
+    /// The checksum of the pool's keys must match the one this bundle was
+    /// built against.
+    pub fn with_pool<P>(mut self, mut pool: ResourceBundleReader<P>) -> io::Result<Self>
+    where
+        P: Read + Seek,
+    {
+        if !self.uses_pool_bundle {
+            return Err(Error::new(ErrorKind::InvalidInput, "bundle doesn't use a pool bundle"));
+        }
+        if !pool.is_pool_bundle {
+            return Err(Error::new(ErrorKind::InvalidInput, "not a pool bundle"));
+        }
+        if pool.keys.ebcdic != self.keys.ebcdic {
+            return Err(Error::new(ErrorKind::InvalidInput, "pool bundle charset mismatch"));
+        }
+        if self.pool_checksum != Some(compute_pool_checksum(&pool.keys.local)) {
+            return Err(Error::new(ErrorKind::InvalidData, "pool bundle checksum mismatch"));
+        }
+        let length = pool.units_16_length;
+        let units = pool.read_16bit_units(0, length)?;
+        self.keys.pool = Some(pool.keys.local);
+        self.pool_units = Some(units);
+        Ok(self)
+    }
+
+    /// The NUL-terminated key starting at byte `offset` of the keys block,
+    /// as found in a table's key offsets.
+    ///
+    /// The key borrows from the keys, which the reader loads once; only the
+    /// key itself is checked to be ASCII. Offsets with the high bit set are
+    /// into the pool bundle's keys. The keys of a bundle in the EBCDIC
+    /// charset family are given in ASCII all the same.
+    pub fn key_at(&self, offset: u32) -> io::Result<&str> {
+        self.keys.get(offset)
+    }
+
+    /// The `(offset, key)` of every key in the bundle's keys, in the order
+    /// they're stored, which `genrb` sorts.
+    ///
+    /// The offsets are those `key_at` takes. The keys of an attached pool
+    /// bundle aren't included.
+    pub fn all_keys(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.keys.local()
+    }
+
+    /// The type of the resource word `res`, from its top four bits.
+    ///
+    /// The word 0, an empty string, is a `String` like any other offset.
+    pub fn resource_type(&self, res: BundleKey) -> ResourceType {
+        match res.type_tag() {
+            URES_STRING => ResourceType::String,
+            URES_BINARY => ResourceType::Binary,
+            URES_TABLE => ResourceType::Table,
+            URES_ALIAS => ResourceType::Alias,
+            URES_TABLE32 => ResourceType::Table32,
+            URES_TABLE16 => ResourceType::Table16,
+            URES_STRING_V2 => ResourceType::StringV2,
+            URES_INT => ResourceType::Int,
+            URES_ARRAY => ResourceType::Array,
+            URES_ARRAY16 => ResourceType::Array16,
+            URES_INT_VECTOR => ResourceType::IntVector,
+            _ => ResourceType::Reserved,
+        }
+    }
+
+    /// Decodes the resource word `res`.
+    ///
+    /// Tables and arrays decode to their child resource words, which can be
+    /// passed back in here to descend further.
+    pub fn resource(&mut self, res: BundleKey) -> io::Result<Resource> {
+        let offset = res.offset();
+        match res.type_tag() {
+            URES_STRING => self.string(offset).map(Resource::String),
+            URES_BINARY => self.binary(res).map(Resource::Binary),
+            URES_STRING_V2 => self.string_v2(res).map(Resource::String),
+            URES_INT => self.int(res).map(Resource::Int),
+            URES_TABLE | URES_TABLE16 | URES_TABLE32 => self.table(res).map(Resource::Table),
+            URES_ALIAS => self.alias_path(res).map(Resource::Alias),
+            URES_ARRAY | URES_ARRAY16 => self.array(res).map(Resource::Array),
+            URES_INT_VECTOR => self.int_vector(res).map(Resource::IntVector),
+            t => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported resource type {}", t),
+            )),
+        }
+    }
+
+    /// Looks up `key` in the table resource `table`, returning the child
+    /// resource word if there is one.
+    ///
+    /// Table keys are sorted by their bytes, so this is a binary search
+    /// over the table's key offsets. Each probe compares `key` with bytes
+    /// borrowed from the keys, without copying or decoding them. In an
+    /// EBCDIC bundle the bytes compared are the keys' EBCDIC ones, which
+    /// sort lowercase before uppercase before digits.
+    pub fn get(&mut self, table: BundleKey, key: &str) -> io::Result<Option<BundleKey>> {
+        let layout = self.table_layout(table)?;
+        let key = match self.keys.in_table_order(key) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let (mut low, mut high) = (0, u64::from(layout.length));
+        while low < high {
+            let mid = (low + high) / 2;
+            let key_offset = layout.key(&mut self.reader, &self.keys, mid)?;
+            match self.keys.cmp(key_offset, &key)? {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return layout.item(&mut self.reader, mid).map(Some),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Looks up a slash-delimited `path` like `"calendar/gregorian/eras"`
+    /// from the root resource, returning the resource word it names.
+    ///
+    /// Each component is a key in a table or a decimal index into an
+    /// array. `Ok(None)` means some component wasn't there.
+    pub fn get_by_path(&mut self, path: &str) -> io::Result<Option<BundleKey>> {
+        let mut current = self.root_resource;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            match self.child(current, component)? {
+                Some(child) => current = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+
+    /// Looks up the string at `path`, as with `get_by_path`, following
+    /// aliases on the way and at the end.
+    ///
+    /// Pooled strings need the pool bundle attached with `with_pool`. It's
+    /// an error for the path to name a resource other than a string, or to
+    /// reach an alias into another bundle.
+    pub fn get_string(&mut self, path: &str) -> io::Result<Option<String>> {
+        match self.resolve(path)? {
+            Some(res) => self.string_at(res).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up the integer at `path`, as with `get_string`.
+    ///
+    /// Besides integers, this takes the first value of an integer vector,
+    /// or a string of a decimal integer. It's an error for the path to name
+    /// anything else.
+    pub fn get_i32(&mut self, path: &str) -> io::Result<Option<i32>> {
+        let res = match self.resolve(path)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        match res.type_tag() {
+            URES_INT => self.int(res).map(Some),
+            URES_INT_VECTOR => self.first_int(res).map(Some),
+            _ => {
+                let s = self.string_at(res)?;
+                s.trim().parse().map(Some).map_err(|_| not_a_number(&s))
+            }
+        }
+    }
+
+    /// Looks up the number at `path`, as with `get_string`.
+    ///
+    /// ICU has no floating-point resources, so numbers that aren't integers
+    /// are stored as strings, which this parses. Integers and the first
+    /// value of an integer vector are taken as they are, as with `get_i32`.
+    pub fn get_string_as_f64(&mut self, path: &str) -> io::Result<Option<f64>> {
+        let res = match self.resolve(path)? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        match res.type_tag() {
+            URES_INT => self.int(res).map(|n| Some(f64::from(n))),
+            URES_INT_VECTOR => self.first_int(res).map(|n| Some(f64::from(n))),
+            _ => {
+                let s = self.string_at(res)?;
+                s.trim().parse().map(Some).map_err(|_| not_a_number(&s))
+            }
+        }
+    }
+
+    /// The resource at `path`, following aliases within the bundle on the
+    /// way and at the end.
+    fn resolve(&mut self, path: &str) -> io::Result<Option<BundleKey>> {
+        let mut current = self.root_resource;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            let child = match self.follow_aliases(current)? {
+                Some(resolved) => self.child(resolved, component)?,
+                None => None,
+            };
+            match child {
+                Some(child) => current = child,
+                None => return Ok(None),
+            }
+        }
+        self.follow_aliases(current)
+    }
+
+    /// Decodes `res`, which must be a string of either format.
+    fn string_at(&mut self, res: BundleKey) -> io::Result<String> {
+        match res.type_tag() {
+            URES_STRING => self.string(res.offset()),
+            URES_STRING_V2 => self.string_v2(res),
+            URES_ALIAS => Err(Error::new(ErrorKind::InvalidData, "alias into another bundle")),
+            _ => Err(Error::new(ErrorKind::InvalidData, "not a string resource")),
+        }
+    }
+
+    /// The first value of the integer vector `res`.
+    fn first_int(&mut self, res: BundleKey) -> io::Result<i32> {
+        self.int_vector(res)?
+            .first()
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "empty integer vector"))
+    }
+
+    /// The child of the table or array `res` named by the path component
+    /// `component`, a key or a decimal index.
+    fn child(&mut self, res: BundleKey, component: &str) -> io::Result<Option<BundleKey>> {
+        match res.type_tag() {
+            URES_TABLE | URES_TABLE16 | URES_TABLE32 => self.get(res, component),
+            URES_ARRAY | URES_ARRAY16 => match component.parse::<u32>() {
+                Ok(index) => self.array_item(res, index),
+                Err(_) => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Looks up `path` in each bundle of `chain` in turn, as with
+    /// `get_by_path`, returning the index of the first bundle it's in
+    /// along with the resource word it names.
+    ///
+    /// `chain` holds a bundle followed by its fallbacks, in the order
+    /// `fallback_chain` gives their locales. A bundle whose
+    /// `fallback_behavior` is `None` ends the chain: bundles after it
+    /// aren't searched.
+    pub fn get_with_fallback(
+        chain: &mut [ResourceBundleReader<R>],
+        path: &str,
+    ) -> io::Result<Option<(usize, BundleKey)>> {
+        for (i, bundle) in chain.iter_mut().enumerate() {
+            if let Some(res) = bundle.get_by_path(path)? {
+                return Ok(Some((i, res)));
+            }
+            if bundle.fallback_behavior() == FallbackBehavior::None {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the target of the `URES_ALIAS` resource `res`.
+    pub fn resolve_alias(&mut self, res: BundleKey) -> io::Result<AliasTarget> {
+        self.alias_path(res).map(|path| AliasTarget::parse(&path))
+    }
+
+    /// Follows `res` through any aliases to resources in this bundle,
+    /// returning the resource they end at.
+    ///
+    /// Only `/LOCALE/` aliases, which name a path in the bundle being
+    /// read, can be followed here; an alias into another bundle is returned
+    /// as it is, for the caller to resolve. `Ok(None)` means an alias names
+    /// a path that isn't there.
+    pub fn follow_aliases(&mut self, res: BundleKey) -> io::Result<Option<BundleKey>> {
+        let mut current = res;
+        for _ in 0..MAX_ALIAS_DEPTH {
+            if current.type_tag() != URES_ALIAS {
+                return Ok(Some(current));
+            }
+            let target = self.resolve_alias(current)?;
+            if target.package.is_some() || target.bundle.is_some() {
+                return Ok(Some(current));
+            }
+            match self.get_by_path(&target.path)? {
+                Some(next) => current = next,
+                None => return Ok(None),
+            }
+        }
+        Err(Error::new(ErrorKind::InvalidData, "too many aliases, or an alias cycle"))
+    }
+
+    /// The path of the `URES_ALIAS` resource `res`, stored like a legacy
+    /// string.
+    fn alias_path(&mut self, res: BundleKey) -> io::Result<String> {
+        if res.type_tag() != URES_ALIAS {
+            return Err(Error::new(ErrorKind::InvalidData, "not a URES_ALIAS resource"));
+        }
+        self.string(res.offset())
+    }
+
+    /// Decodes the `URES_STRING_V2` resource `res`, stored in the 16-bit
+    /// units.
+    ///
+    /// The string's length is implicit, up to a NUL, unless its first unit
+    /// is a trail surrogate: 0xdc00..0xdfee holds the length in its low ten
+    /// bits, 0xdfef..0xdffe is followed by one more unit of length, and
+    /// 0xdfff is followed by two.
+    pub fn string_v2(&mut self, res: BundleKey) -> io::Result<String> {
+        if res.type_tag() != URES_STRING_V2 {
+            return Err(Error::new(ErrorKind::InvalidData, "not a URES_STRING_V2 resource"));
+        }
+        self.check_string_v2()?;
+        let offset = res.offset();
+        if offset == 0 {
+            return Ok(String::new());
+        }
+        let (pooled, start, length) = self.string_v2_start(offset)?;
+        let mut units = Vec::new();
+        match length {
+            Some(length) => {
+                for i in start..start + length {
+                    units.push(self.read_string_unit(pooled, i)?);
+                }
+            }
+            None => {
+                let mut index = start;
+                let mut unit = self.read_string_unit(pooled, index)?;
+                while unit != 0 {
+                    units.push(unit);
+                    index += 1;
+                    unit = self.read_string_unit(pooled, index)?;
+                }
+            }
+        }
+        utf16_to_string(&units)
+    }
+
+    /// The number of UTF-16 code units in the string resource `res`, either
+    /// a `URES_STRING` or a `URES_STRING_V2`, found without decoding it.
+    ///
+    /// Stored lengths are returned as they are; only NUL-terminated
+    /// strings have their units read, to find the NUL.
+    pub fn string_len(&mut self, res: BundleKey) -> io::Result<usize> {
+        let offset = res.offset();
+        match res.type_tag() {
+            URES_STRING if offset == 0 => Ok(0),
+            URES_STRING => {
+                let pos = self.position(offset);
+                self.reader.read_u32_from(SeekFrom::Start(pos)).map(|length| length as usize)
+            }
+            URES_STRING_V2 => {
+                self.check_string_v2()?;
+                if offset == 0 {
+                    return Ok(0);
+                }
+                match self.string_v2_start(offset)? {
+                    (_, _, Some(length)) => Ok(length as usize),
+                    (pooled, start, None) => {
+                        let mut index = start;
+                        while self.read_string_unit(pooled, index)? != 0 {
+                            index += 1;
+                        }
+                        Ok((index - start) as usize)
+                    }
+                }
+            }
+            _ => Err(Error::new(ErrorKind::InvalidData, "not a string resource")),
+        }
+    }
+
+    /// Format 1 bundles predate `URES_STRING_V2`: all their strings are
+    /// legacy `URES_STRING`s, with a 32-bit length.
+    fn check_string_v2(&self) -> io::Result<()> {
+        if self.format_major_version() < 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "URES_STRING_V2 resource in a format 1 bundle",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Where the units of the `URES_STRING_V2` string at `offset` start,
+    /// whether they're in the pool bundle, and the string's length if it's
+    /// stored rather than implied by a NUL.
+    fn string_v2_start(&mut self, offset: u32) -> io::Result<(bool, u32, Option<u32>)> {
+        // strings below the limit are in the pool bundle's 16-bit units
+        let pooled = offset < self.pool_string_index_limit;
+        let index = if pooled {
+            offset
+        } else {
+            offset - self.pool_string_index_limit
+        };
+        let first = self.read_string_unit(pooled, index)?;
+        if first & 0xfc00 != 0xdc00 {
+            return Ok((pooled, index, None));
+        }
+        let (start, length) = if first < 0xdfef {
+            (index + 1, u32::from(first & 0x3ff))
+        } else if first < 0xdfff {
+            let low = self.read_string_unit(pooled, index + 1)?;
+            (index + 2, (u32::from(first - 0xdfef) << 16) | u32::from(low))
+        } else {
+            let high = self.read_string_unit(pooled, index + 1)?;
+            let low = self.read_string_unit(pooled, index + 2)?;
+            (index + 3, (u32::from(high) << 16) | u32::from(low))
+        };
+        Ok((pooled, start, Some(length)))
+    }
+
+    /// Reads the unit at `index` in the pool bundle's or this bundle's
+    /// 16-bit units.
+    fn read_string_unit(&mut self, pooled: bool, index: u32) -> io::Result<u16> {
+        if !pooled {
+            return self.read_16bit_at(index);
+        }
+        self.pool_units
+            .as_ref()
+            .ok_or_else(no_pool)?
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "16-bit index out of range"))
+    }
+
+    /// Reads the unit at `index` in the 16-bit units.
+    fn read_16bit_at(&mut self, index: u32) -> io::Result<u16> {
+        if index >= self.units_16_length {
+            return Err(Error::new(ErrorKind::InvalidData, "16-bit index out of range"));
+        }
+        let pos = self.units_16_base + 2 * u64::from(index);
+        self.reader.read_u16_from(SeekFrom::Start(pos))
+    }
+
+    /// Reads `count` units starting at `index` in the 16-bit units.
+    fn read_16bit_units(&mut self, index: u32, count: u32) -> io::Result<Vec<u16>> {
+        if u64::from(index) + u64::from(count) > u64::from(self.units_16_length) {
+            return Err(Error::new(ErrorKind::InvalidData, "16-bit index out of range"));
+        }
+        let pos = self.units_16_base + 2 * u64::from(index);
+        self.reader.seek(SeekFrom::Start(pos))?;
+        let mut units = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            units.push(EndianReader::read_u16(&mut self.reader)?);
+        }
+        Ok(units)
+    }
+
+    /// Decodes the `URES_INT` resource `res`, whose signed 28-bit value is
+    /// in the resource word itself.
+    pub fn int(&mut self, res: BundleKey) -> io::Result<i32> {
+        if res.type_tag() != URES_INT {
+            return Err(Error::new(ErrorKind::InvalidData, "not a URES_INT resource"));
+        }
+        Ok(int28(res.0))
+    }
+
+    /// Decodes the `URES_INT_VECTOR` resource `res`: a 32-bit count followed
+    /// by that many 32-bit integers.
+    pub fn int_vector(&mut self, res: BundleKey) -> io::Result<Vec<i32>> {
+        if res.type_tag() != URES_INT_VECTOR {
+            return Err(Error::new(ErrorKind::InvalidData, "not a URES_INT_VECTOR resource"));
+        }
+        if res.offset() == 0 {
+            return Ok(Vec::new());
+        }
+        let pos = self.position(res.offset());
+        let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+        let mut values = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            values.push(EndianReader::read_i32(&mut self.reader)?);
+        }
+        Ok(values)
+    }
+
+    /// Decodes the array resource `res` to its child resource words, which
+    /// can be passed to `resource` and the like to decode them in turn.
+    ///
+    /// A `URES_ARRAY` is a 32-bit count followed by that many 32-bit child
+    /// resource words; a `URES_ARRAY16` is read from the 16-bit units. Offset
+    /// 0 is the empty array.
+    pub fn array(&mut self, res: BundleKey) -> io::Result<Vec<BundleKey>> {
+        let offset = res.offset();
+        match res.type_tag() {
+            URES_ARRAY16 => return self.array16(offset),
+            URES_ARRAY => {}
+            _ => return Err(Error::new(ErrorKind::InvalidData, "not an array resource")),
+        }
+        if offset == 0 {
+            return Ok(Vec::new());
+        }
+        let pos = self.position(offset);
+        let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+        let mut items = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            items.push(BundleKey(EndianReader::read_u32(&mut self.reader)?));
+        }
+        Ok(items)
+    }
+
+    /// Reads the `URES_BINARY` resource `res`: a 32-bit length followed by
+    /// that many bytes, returned as they are in the file.
+    pub fn binary(&mut self, res: BundleKey) -> io::Result<Vec<u8>> {
+        if res.type_tag() != URES_BINARY {
+            return Err(Error::new(ErrorKind::InvalidData, "not a URES_BINARY resource"));
+        }
+        if res.offset() == 0 {
+            return Ok(Vec::new());
+        }
+        let pos = self.position(res.offset());
+        let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(u64::from(length)).read_to_end(&mut bytes)?;
+        if bytes.len() != length as usize {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "binary resource is truncated"));
+        }
+        Ok(bytes)
+    }
+
+    /// The number of entries in the table resource `table`, from its count
+    /// alone, without reading any of the entries.
+    pub fn table_len(&mut self, table: BundleKey) -> io::Result<usize> {
+        self.table_layout(table).map(|layout| layout.length as usize)
+    }
+
+    /// Iterates over the `(key, child resource word)` entries of the table
+    /// resource `table`, in key order, reading each entry as it goes.
+    pub fn table_iter(&mut self, table: BundleKey) -> io::Result<TableIter<'_, R>> {
+        let layout = self.table_layout(table)?;
+        Ok(TableIter {
+            reader: &mut self.reader,
+            keys: &self.keys,
+            layout,
+            index: 0,
+        })
+    }
+
+    /// The key offset and child resource word of entry `i` of the table
+    /// resource `table`, read without looking the key up.
+    ///
+    /// The entry is the one `table_iter` yields `i`th, with the raw key
+    /// offset `key_at` takes in place of the key. An `i` past the end of
+    /// the table is an error.
+    pub fn table_entry_at(&mut self, table: BundleKey, i: usize) -> io::Result<(u32, BundleKey)> {
+        let layout = self.table_layout(table)?;
+        if i >= layout.length as usize {
+            return Err(Error::new(ErrorKind::InvalidInput, "table index out of range"));
+        }
+        let key = layout.key(&mut self.reader, &self.keys, i as u64)?;
+        Ok((key, layout.item(&mut self.reader, i as u64)?))
+    }
+
+    /// Writes the resource `res` and everything under it to `out`, in the
+    /// indented text form of ICU's `derb` tool.
+    ///
+    /// Tables are written as `key { ... }`, arrays as `{ ... }` with their
+    /// items one per line, and other types with a tag, as in
+    /// `key:int { 5 }`. Aliases are written as their paths, not followed.
+    /// A table or array that contains itself, which only a corrupt bundle
+    /// can have, is an error.
+    pub fn dump<W>(&mut self, res: BundleKey, out: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut open = Vec::new();
+        self.dump_resource(res, None, &mut open, out)
+    }
+
+    /// Writes `res`, named `key` if it's in a table, nested inside the
+    /// tables and arrays in `open`.
+    fn dump_resource<W>(
+        &mut self,
+        res: BundleKey,
+        key: Option<&str>,
+        open: &mut Vec<BundleKey>,
+        out: &mut W,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let indent = "    ".repeat(open.len());
+        let name = key.unwrap_or("");
+        let children = match self.resource(res)? {
+            Resource::String(s) => {
+                return match key {
+                    Some(key) => writeln!(out, "{}{} {{ {} }}", indent, key, quote(&s)),
+                    None => writeln!(out, "{}{},", indent, quote(&s)),
+                };
+            }
+            Resource::Int(n) => return writeln!(out, "{}{}:int {{ {} }}", indent, name, n),
+            Resource::IntVector(values) => {
+                let values: Vec<String> = values.iter().map(i32::to_string).collect();
+                let values = values.join(", ");
+                return writeln!(out, "{}{}:intvector {{ {} }}", indent, name, values);
+            }
+            Resource::Binary(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                return writeln!(out, "{}{}:bin {{ {} }}", indent, name, hex);
+            }
+            Resource::Alias(path) => {
+                return writeln!(out, "{}{}:alias {{ {} }}", indent, name, quote(&path));
+            }
+            Resource::Table(entries) => {
+                let mut children = Vec::with_capacity(entries.len());
+                for (key_offset, child) in entries {
+                    children.push((Some(self.key_at(key_offset)?.to_string()), child));
+                }
+                children
+            }
+            Resource::Array(items) => items.into_iter().map(|item| (None, item)).collect(),
+        };
+
+        if open.contains(&res) {
+            return Err(Error::new(ErrorKind::InvalidData, "resource contains itself"));
+        }
+        if open.len() >= MAX_DUMP_DEPTH {
+            return Err(Error::new(ErrorKind::InvalidData, "resources nested too deeply"));
+        }
+        match key {
+            Some(key) => writeln!(out, "{}{} {{", indent, key)?,
+            None => writeln!(out, "{}{{", indent)?,
+        }
+        open.push(res);
+        for (key, child) in children {
+            self.dump_resource(child, key.as_deref(), open, out)?;
+        }
+        open.pop();
+        writeln!(out, "{}}}", indent)
+    }
+
+    /// Where the keys and child resources of the table resource `table`
+    /// are.
+    fn table_layout(&mut self, table: BundleKey) -> io::Result<TableLayout> {
+        let offset = table.offset();
+        let mut layout = TableLayout {
+            length: 0,
+            keys_pos: 0,
+            wide_keys: false,
+            items_pos: 0,
+            narrow_items: false,
+            pool_string_index_limit: self.pool_string_index_limit,
+            pool_string_index_16_limit: self.pool_string_index_16_limit,
+        };
+        match table.type_tag() {
+            // offset 0 is the empty table for every table type
+            URES_TABLE | URES_TABLE16 | URES_TABLE32 if offset == 0 => return Ok(layout),
+            URES_TABLE => {
+                let pos = self.position(offset);
+                let length = self.reader.read_u16_from(SeekFrom::Start(pos))?;
+                layout.length = u32::from(length);
+                layout.keys_pos = pos + 2;
+                layout.items_pos = table_items(pos, length);
+            }
+            URES_TABLE16 => {
+                let length = self.read_16bit_at(offset)?;
+                if u64::from(offset) + 1 + 2 * u64::from(length) > u64::from(self.units_16_length)
+                {
+                    return Err(Error::new(ErrorKind::InvalidData, "16-bit index out of range"));
+                }
+                layout.length = u32::from(length);
+                layout.keys_pos = self.units_16_base + 2 * (u64::from(offset) + 1);
+                layout.items_pos = layout.keys_pos + 2 * u64::from(length);
+                layout.narrow_items = true;
+            }
+            URES_TABLE32 => {
+                let pos = self.position(offset);
+                let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+                layout.length = length;
+                layout.keys_pos = pos + 4;
+                layout.wide_keys = true;
+                layout.items_pos = pos + 4 + 4 * u64::from(length);
+            }
+            _ => return Err(Error::new(ErrorKind::InvalidData, "not a table")),
+        }
+        self.check_table_length(layout.length)?;
+        Ok(layout)
+    }
+
+    /// Tables longer than the bundle's longest table are corrupt.
+    fn check_table_length(&self, length: u32) -> io::Result<()> {
+        if length > self.max_table_length {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "table is longer than the bundle's longest table",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The byte position of a 32-bit-addressed resource offset.
+    ///
+    /// The 28-bit offset of a resource word counts 32-bit units from the
+    /// root resource word, the first word after the header, so it's at
+    /// byte `header_size + 4 * offset` wherever the keys and 16-bit units
+    /// end. Those only bound the offsets that resources can have, which
+    /// put them in `resource_region`.
+    fn position(&self, offset: u32) -> u64 {
+        u64::from(self.header_size) + 4 * u64::from(offset)
+    }
+
+    /// A 32-bit length followed by that many UTF-16 units. Offset 0 is the
+    /// empty string.
+    fn string(&mut self, offset: u32) -> io::Result<String> {
+        if offset == 0 {
+            return Ok(String::new());
+        }
+        let pos = self.position(offset);
+        let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+        let mut units = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            units.push(EndianReader::read_u16(&mut self.reader)?);
+        }
+        utf16_to_string(&units)
+    }
+
+    /// Decodes the resource `res` and everything under it to an owned
+    /// `Value`, following aliases within the bundle.
+    ///
+    /// Like `dump`, this fails on tables and arrays that contain themselves,
+    /// whether directly or through an alias.
+    pub fn to_value(&mut self, res: BundleKey) -> io::Result<Value> {
+        let mut open = Vec::new();
+        self.value(res, &mut open)
+    }
+
+    /// Decodes `res` inside the containers in `open`.
+    fn value(&mut self, res: BundleKey, open: &mut Vec<BundleKey>) -> io::Result<Value> {
+        let res = self.follow_aliases(res)?.unwrap_or(res);
+        let (keys, items) = match self.resource(res)? {
+            Resource::String(s) => return Ok(Value::String(s)),
+            Resource::Int(n) => return Ok(Value::Int(n)),
+            Resource::IntVector(values) => return Ok(Value::IntVector(values)),
+            Resource::Binary(bytes) => return Ok(Value::Binary(bytes)),
+            Resource::Alias(path) => return Ok(Value::Alias(path)),
+            Resource::Table(entries) => {
+                let mut keys = Vec::with_capacity(entries.len());
+                let mut items = Vec::with_capacity(entries.len());
+                for (key_offset, child) in entries {
+                    keys.push(self.key_at(key_offset)?.to_string());
+                    items.push(child);
+                }
+                (Some(keys), items)
+            }
+            Resource::Array(items) => (None, items),
+        };
+
+        if open.contains(&res) {
+            return Err(Error::new(ErrorKind::InvalidData, "resource contains itself"));
+        }
+        if open.len() >= MAX_DUMP_DEPTH {
+            return Err(Error::new(ErrorKind::InvalidData, "resources nested too deeply"));
+        }
+        open.push(res);
+        let mut values = Vec::with_capacity(items.len());
+        for item in items {
+            values.push(self.value(item, open)?);
+        }
+        open.pop();
+        Ok(match keys {
+            Some(keys) => Value::Table(keys.into_iter().zip(values).collect()),
+            None => Value::Array(values),
+        })
+    }
+
+    /// Walks the resource `res` and everything under it depth-first,
+    /// passing each resource to `visitor` and following aliases within the
+    /// bundle as `to_value` does.
+    ///
+    /// Unlike `to_value`, nothing is kept once it's been visited: tables and
+    /// arrays are read an entry at a time, so only the path down to the
+    /// current resource is held. Like `dump`, this fails on tables and
+    /// arrays that contain themselves.
+    pub fn accept<V>(&mut self, res: BundleKey, visitor: &mut V) -> io::Result<()>
+    where
+        V: Visitor,
     {
-        return Err(Error::new(ErrorKind::InvalidInput, HEADER_CHECK_FAILED));
+        let mut open = Vec::new();
+        self.accept_resource(res, None, &mut open, visitor)
+    }
+
+    /// Visits `res`, named `key` if it's in a table, inside the containers
+    /// in `open`.
+    fn accept_resource<V>(
+        &mut self,
+        res: BundleKey,
+        key: Option<&str>,
+        open: &mut Vec<BundleKey>,
+        visitor: &mut V,
+    ) -> io::Result<()>
+    where
+        V: Visitor,
+    {
+        let res = self.follow_aliases(res)?.unwrap_or(res);
+        let is_table = match res.type_tag() {
+            URES_TABLE | URES_TABLE16 | URES_TABLE32 => true,
+            URES_ARRAY | URES_ARRAY16 => false,
+            _ => {
+                return match self.resource(res)? {
+                    Resource::String(s) => visitor.visit_string(key, &s),
+                    Resource::Int(n) => visitor.visit_int(key, n),
+                    Resource::IntVector(values) => visitor.visit_int_vector(key, &values),
+                    Resource::Binary(bytes) => visitor.visit_binary(key, &bytes),
+                    Resource::Alias(path) => visitor.visit_alias(key, &path),
+                    Resource::Table(_) | Resource::Array(_) => unreachable!(),
+                };
+            }
+        };
+
+        if open.contains(&res) {
+            return Err(Error::new(ErrorKind::InvalidData, "resource contains itself"));
+        }
+        if open.len() >= MAX_DUMP_DEPTH {
+            return Err(Error::new(ErrorKind::InvalidData, "resources nested too deeply"));
+        }
+        open.push(res);
+        if is_table {
+            let layout = self.table_layout(res)?;
+            visitor.visit_table_start(key, layout.length as usize)?;
+            for i in 0..u64::from(layout.length) {
+                let key_offset = layout.key(&mut self.reader, &self.keys, i)?;
+                let child = layout.item(&mut self.reader, i)?;
+                let child_key = self.key_at(key_offset)?.to_string();
+                self.accept_resource(child, Some(&child_key), open, visitor)?;
+            }
+            visitor.visit_table_end()?;
+        } else {
+            let length = self.array_len(res)?;
+            visitor.visit_array_start(key, length as usize)?;
+            for i in 0..length {
+                if let Some(item) = self.array_item(res, i)? {
+                    self.accept_resource(item, None, open, visitor)?;
+                }
+            }
+            visitor.visit_array_end()?;
+        }
+        open.pop();
+        Ok(())
+    }
+
+    /// The `(key offset, child resource word)` entries of the table
+    /// resource `table`.
+    fn table(&mut self, table: BundleKey) -> io::Result<Vec<(u32, BundleKey)>> {
+        let layout = self.table_layout(table)?;
+        let mut entries = Vec::with_capacity(layout.length as usize);
+        for i in 0..u64::from(layout.length) {
+            let key = layout.key(&mut self.reader, &self.keys, i)?;
+            entries.push((key, layout.item(&mut self.reader, i)?));
+        }
+        Ok(entries)
+    }
+
+    /// The number of children of the array resource `array`.
+    fn array_len(&mut self, array: BundleKey) -> io::Result<u32> {
+        let offset = array.offset();
+        if offset == 0 {
+            return Ok(0);
+        }
+        if array.type_tag() == URES_ARRAY16 {
+            return self.read_16bit_at(offset).map(u32::from);
+        }
+        let pos = self.position(offset);
+        self.reader.read_u32_from(SeekFrom::Start(pos))
+    }
+
+    /// The `index`th child of the array resource `array`, if there is one.
+    fn array_item(&mut self, array: BundleKey, index: u32) -> io::Result<Option<BundleKey>> {
+        let offset = array.offset();
+        if offset == 0 {
+            return Ok(None);
+        }
+        if array.type_tag() == URES_ARRAY16 {
+            let length = self.read_16bit_at(offset)?;
+            if index >= u32::from(length) {
+                return Ok(None);
+            }
+            let r16 = self.read_16bit_at(offset + 1 + index)?;
+            return Ok(Some(self.resource_16(r16)));
+        }
+        let pos = self.position(offset);
+        let length = self.reader.read_u32_from(SeekFrom::Start(pos))?;
+        if index >= length {
+            return Ok(None);
+        }
+        let item = pos + 4 + 4 * u64::from(index);
+        self.reader.read_u32_from(SeekFrom::Start(item)).map(|res| Some(BundleKey(res)))
+    }
+
+    /// A 16-bit count followed by that many 16-bit string indexes, in the
+    /// 16-bit units. Offset 0 is the empty array.
+    fn array16(&mut self, offset: u32) -> io::Result<Vec<BundleKey>> {
+        if offset == 0 {
+            return Ok(Vec::new());
+        }
+        let length = self.read_16bit_at(offset)?;
+        let units = self.read_16bit_units(offset + 1, u32::from(length))?;
+        Ok(units.into_iter().map(|r16| self.resource_16(r16)).collect())
+    }
+
+    /// The `URES_STRING_V2` resource word for a child of a `URES_TABLE16`
+    /// or `URES_ARRAY16`.
+    fn resource_16(&self, r16: u16) -> BundleKey {
+        resource_from_16(r16, self.pool_string_index_16_limit, self.pool_string_index_limit)
+    }
+
+}
+
+impl ResourceBundleReader<BufferedSource<'static>> {
+    /// Reads a bundle from bytes already in memory.
+    pub fn from_bytes(
+        data: Vec<u8>,
+        data_format: DataFormat,
+    ) -> Result<ResourceBundleReader<BufferedSource<'static>>, IcuError> {
+        ResourceBundleReader::try_init(BufferedSource::from(data), data_format)
+    }
+}
+
+impl<'a> ResourceBundleReader<Cursor<&'a [u8]>> {
+    /// Reads a bundle from bytes already in memory, borrowing them rather
+    /// than taking a copy.
+    pub fn from_slice(
+        data: &'a [u8],
+        data_format: DataFormat,
+    ) -> Result<ResourceBundleReader<Cursor<&'a [u8]>>, IcuError> {
+        ResourceBundleReader::try_init(Cursor::new(data), data_format)
+    }
+}
+
+/// Reads a bundle from bytes in memory, as `from_slice` does.
+impl<'a> TryFrom<(&'a [u8], DataFormat)> for ResourceBundleReader<Cursor<&'a [u8]>> {
+    type Error = IcuError;
+
+    fn try_from((data, data_format): (&'a [u8], DataFormat)) -> Result<Self, Self::Error> {
+        ResourceBundleReader::from_slice(data, data_format)
+    }
+}
+
+#[cfg(feature = "memmap")]
+impl ResourceBundleReader<MappedSource> {
+    /// Reads a bundle from a memory-mapped file, which the reader keeps
+    /// mapped until it's dropped.
+    ///
+    /// The file must not be modified while the reader exists; see
+    /// `MappedSource`.
+    pub fn from_path(
+        path: &Path,
+        data_format: DataFormat,
+    ) -> Result<ResourceBundleReader<MappedSource>, IcuError> {
+        ResourceBundleReader::try_init(MappedSource::open(path)?, data_format)
+    }
+}
+
+/// An iterator over the entries of a table resource, from
+/// `ResourceBundleReader::table_iter`.
+///
+/// Whatever the width of a table's key offsets and children, it yields
+/// the same key strings and child resource words.
+pub struct TableIter<'a, R>
+where
+    R: 'a + Read + Seek,
+{
+    reader: &'a mut OrderedReader<R>,
+    keys: &'a Keys,
+    layout: TableLayout,
+    index: u32,
+}
+
+impl<'a, R> TableIter<'a, R>
+where
+    R: Read + Seek,
+{
+    fn entry(&mut self, i: u64) -> io::Result<(&'a str, BundleKey)> {
+        let key = self.layout.key(self.reader, self.keys, i)?;
+        let value = self.layout.item(self.reader, i)?;
+        let keys: &'a Keys = self.keys;
+        Ok((keys.get(key)?, value))
+    }
+}
+
+impl<'a, R> Iterator for TableIter<'a, R>
+where
+    R: Read + Seek,
+{
+    type Item = io::Result<(&'a str, BundleKey)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.layout.length {
+            return None;
+        }
+        let i = u64::from(self.index);
+        self.index += 1;
+        Some(self.entry(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.layout.length - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Where a table's keys and children are, and how wide they are.
+///
+/// `URES_TABLE` tables have 16-bit key offsets and 32-bit children,
+/// `URES_TABLE32` tables 32-bit ones for both, and `URES_TABLE16` tables,
+/// which live in the 16-bit units, 16-bit ones for both. Their 16-bit
+/// children are always strings.
+#[derive(Clone, Copy, Debug)]
+struct TableLayout {
+    length: u32,
+    keys_pos: u64,
+    wide_keys: bool,
+    items_pos: u64,
+    narrow_items: bool,
+    pool_string_index_limit: u32,
+    pool_string_index_16_limit: u32,
+}
+
+impl TableLayout {
+    /// The key offset of entry `i`, as understood by `Keys::get`.
+    fn key<R>(&self, reader: &mut OrderedReader<R>, keys: &Keys, i: u64) -> io::Result<u32>
+    where
+        R: Read + Seek,
+    {
+        if self.wide_keys {
+            reader.read_u32_from(SeekFrom::Start(self.keys_pos + 4 * i))
+        } else {
+            let key = reader.read_u16_from(SeekFrom::Start(self.keys_pos + 2 * i))?;
+            Ok(keys.key_16(key))
+        }
+    }
+
+    /// The child resource word of entry `i`.
+    fn item<R>(&self, reader: &mut OrderedReader<R>, i: u64) -> io::Result<BundleKey>
+    where
+        R: Read + Seek,
+    {
+        if self.narrow_items {
+            let r16 = reader.read_u16_from(SeekFrom::Start(self.items_pos + 2 * i))?;
+            Ok(resource_from_16(
+                r16,
+                self.pool_string_index_16_limit,
+                self.pool_string_index_limit,
+            ))
+        } else {
+            reader.read_u32_from(SeekFrom::Start(self.items_pos + 4 * i)).map(BundleKey)
+        }
+    }
+}
+
+/// Turns a 16-bit child of a `URES_TABLE16` or `URES_ARRAY16` into a
+/// `URES_STRING_V2` resource word. Values below the 16-bit pool limit are
+/// strings in the pool bundle; the rest are local strings, which come after
+/// the pool's in the string index space.
+fn resource_from_16(r16: u16, pool_16_limit: u32, pool_limit: u32) -> BundleKey {
+    let r16 = u32::from(r16);
+    let offset = if r16 < pool_16_limit {
+        r16
+    } else {
+        r16 - pool_16_limit + pool_limit
+    };
+    BundleKey(URES_STRING_V2 << 28 | offset)
+}
+
+/// The byte position of the child resource words of the `URES_TABLE` at
+/// `pos` with `length` entries, past its keys and their padding.
+fn table_items(pos: u64, length: u16) -> u64 {
+    pos + 2 * (1 + u64::from(length) + u64::from(!length & 1))
+}
+
+/// Whether lookups that miss in a bundle go on to its parent locales.
+///
+/// More ways of falling back, like one to an explicit parent bundle, may
+/// be added, so matches need a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FallbackBehavior {
+    /// Fall back through the parent locales, as far as the root bundle.
+    Full,
+    /// Don't fall back: the bundle has the `no_fallback` attribute.
+    None,
+}
+
+/// What a bundle's header and indexes say about it, as
+/// `ResourceBundleReader::info` gives it.
+///
+/// The sizes are in bytes. The data is laid out as the root resource word
+/// and the indexes, then the keys, the 16-bit units and the 32-bit
+/// resources, which run up to RES_INDEX_RESOURCES_TOP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleInfo {
+    pub format: DataFormat,
+    pub format_version: [u8; 4],
+    pub data_version: PiecewiseVersion,
+    /// The Unicode version of the data, if its data version is one.
+    pub version: Option<Version>,
+    pub order: Order,
+    pub header_size: u16,
+    /// The size of the data after the header, as `data_size` gives it.
+    pub data_size: u32,
+    pub keys_size: u32,
+    /// The size of the 16-bit units, none before format 2.
+    pub units_16_size: u32,
+    pub resources_size: u32,
+    pub no_fallback: bool,
+    pub is_pool_bundle: bool,
+    pub uses_pool_bundle: bool,
+}
+
+/// The type of a resource word, as in ICU's internal `UResType` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceType {
+    /// A string with a 32-bit length, from bundles before format 2.
+    String,
+    Binary,
+    /// A table with 16-bit key offsets and 32-bit children.
+    Table,
+    Alias,
+    /// A table with 32-bit key offsets and children.
+    Table32,
+    /// A table in the 16-bit units, whose children are all strings.
+    Table16,
+    /// A string in the 16-bit units.
+    StringV2,
+    Int,
+    /// An array of 32-bit children.
+    Array,
+    /// An array in the 16-bit units, whose children are all strings.
+    Array16,
+    IntVector,
+    /// The types ICU hasn't assigned yet.
+    Reserved,
+}
+
+/// A resource word, which names a resource in a bundle: its type in the
+/// top four bits and, below them, where it is or, for an integer, its value.
+///
+/// Resource words and the offsets within them are both 32-bit numbers;
+/// giving words their own type keeps one from being passed for the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BundleKey(pub u32);
+
+impl BundleKey {
+    /// The resource type, from the top four bits.
+    pub fn type_tag(self) -> u32 {
+        self.0 >> 28
+    }
+
+    /// The low 28 bits: an offset, whose unit depends on the type, or an
+    /// integer's value.
+    pub fn offset(self) -> u32 {
+        self.0 & 0x0fff_ffff
+    }
+}
+
+/// A decoded resource.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resource {
+    String(String),
+    Int(i32),
+    /// `(key offset, child resource word)` pairs, in key order. The key
+    /// offsets are as understood by `ResourceBundleReader::key_at`.
+    Table(Vec<(u32, BundleKey)>),
+    /// Child resource words.
+    Array(Vec<BundleKey>),
+    IntVector(Vec<i32>),
+    Binary(Vec<u8>),
+    /// The path an alias points at, as `AliasTarget::parse` understands it.
+    Alias(String),
+}
+
+/// Where an alias resource points.
+///
+/// Alias paths take three forms: `/PACKAGE/bundle/path` names a bundle in a
+/// package, where the package `ICUDATA` is ICU's own data;
+/// `/LOCALE/path` names a path in the bundle of the locale being looked
+/// up, which may be a child of the one holding the alias; and
+/// `bundle/path` names another bundle in the same package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AliasTarget {
+    /// The package, for `/PACKAGE/bundle/path` aliases.
+    pub package: Option<String>,
+    /// The bundle, or `None` for `/LOCALE/` aliases.
+    pub bundle: Option<String>,
+    /// The slash-delimited path within the bundle, which may be empty.
+    pub path: String,
+}
+
+impl AliasTarget {
+    fn parse(alias: &str) -> AliasTarget {
+        let (package, rest) = if let Some(alias) = alias.strip_prefix('/') {
+            let mut parts = alias.splitn(2, '/');
+            let package = parts.next().unwrap_or("");
+            let rest = parts.next().unwrap_or("");
+            if package == "LOCALE" {
+                return AliasTarget {
+                    package: None,
+                    bundle: None,
+                    path: rest.to_string(),
+                };
+            }
+            (Some(package.to_string()), rest)
+        } else {
+            (None, alias)
+        };
+        let mut parts = rest.splitn(2, '/');
+        AliasTarget {
+            package,
+            bundle: parts.next().map(str::to_string),
+            path: parts.next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+/// A reader for whichever format `open_any` found.
+#[derive(Clone, Debug)]
+pub enum AnyReader<R>
+where
+    R: Read + Seek,
+{
+    ResourceBundle(ResourceBundleReader<R>),
+    Dat(DatPackageReader<R>),
+    Normalized2(Normalizer2Data<R>),
+    CharacterProperty(UPropsData<R>),
+    Collation(CollationData<R>),
+    BiDi(BiDiData<R>),
+    Case(CaseData<R>),
+    StringPrep(StringPrepData<R>),
+    ConverterAlias(ConverterAliasData<R>),
+    Converter(ConverterData<R>),
+    CharacterName(CharNameData<R>),
+    PropertyAlias(PropertyAliasData<R>),
+    Dictionary(DictionaryData<R>),
+    BreakIteration(BreakData<R>),
+}
+
+impl<R> AnyReader<R>
+where
+    R: Read + Seek,
+{
+    /// The format of the data being read.
+    pub fn format(&self) -> DataFormat {
+        match *self {
+            AnyReader::ResourceBundle(_) => DataFormat::ResourceBundle,
+            AnyReader::Dat(_) => DataFormat::Dat,
+            AnyReader::Normalized2(_) => DataFormat::Normalized2,
+            AnyReader::CharacterProperty(_) => DataFormat::CharacterProperty,
+            AnyReader::Collation(_) => DataFormat::Collation,
+            AnyReader::BiDi(_) => DataFormat::BiDi,
+            AnyReader::Case(_) => DataFormat::Case,
+            AnyReader::StringPrep(_) => DataFormat::StringPrep,
+            AnyReader::ConverterAlias(_) => DataFormat::ConverterAlias,
+            AnyReader::Converter(_) => DataFormat::Converter,
+            AnyReader::CharacterName(_) => DataFormat::CharacterName,
+            AnyReader::PropertyAlias(_) => DataFormat::PropertyAlias,
+            AnyReader::Dictionary(_) => DataFormat::Dictionary,
+            AnyReader::BreakIteration(_) => DataFormat::BreakIteration,
+        }
+    }
+
+    pub fn as_resource_bundle(&self) -> Option<&ResourceBundleReader<R>> {
+        match *self {
+            AnyReader::ResourceBundle(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// Like `as_resource_bundle`, for the lookups that need to move the
+    /// reader.
+    pub fn as_resource_bundle_mut(&mut self) -> Option<&mut ResourceBundleReader<R>> {
+        match *self {
+            AnyReader::ResourceBundle(ref mut r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_dat(&self) -> Option<&DatPackageReader<R>> {
+        match *self {
+            AnyReader::Dat(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// Like `as_dat`, for opening the package's entries.
+    pub fn as_dat_mut(&mut self) -> Option<&mut DatPackageReader<R>> {
+        match *self {
+            AnyReader::Dat(ref mut r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_normalized2(&self) -> Option<&Normalizer2Data<R>> {
+        match *self {
+            AnyReader::Normalized2(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_character_property(&self) -> Option<&UPropsData<R>> {
+        match *self {
+            AnyReader::CharacterProperty(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_collation(&self) -> Option<&CollationData<R>> {
+        match *self {
+            AnyReader::Collation(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_bidi(&self) -> Option<&BiDiData<R>> {
+        match *self {
+            AnyReader::BiDi(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_case(&self) -> Option<&CaseData<R>> {
+        match *self {
+            AnyReader::Case(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_string_prep(&self) -> Option<&StringPrepData<R>> {
+        match *self {
+            AnyReader::StringPrep(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_converter_alias(&self) -> Option<&ConverterAliasData<R>> {
+        match *self {
+            AnyReader::ConverterAlias(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_converter(&self) -> Option<&ConverterData<R>> {
+        match *self {
+            AnyReader::Converter(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_character_name(&self) -> Option<&CharNameData<R>> {
+        match *self {
+            AnyReader::CharacterName(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_property_alias(&self) -> Option<&PropertyAliasData<R>> {
+        match *self {
+            AnyReader::PropertyAlias(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_dictionary(&self) -> Option<&DictionaryData<R>> {
+        match *self {
+            AnyReader::Dictionary(ref r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn as_break_iteration(&self) -> Option<&BreakData<R>> {
+        match *self {
+            AnyReader::BreakIteration(ref r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the format id of an ICU data file from its header.
+pub fn detect_format<R>(reader: &mut R) -> io::Result<DataFormat>
+where
+    R: Read + Seek,
+{
+    check_magic(reader)?;
+    reader.seek(SeekFrom::Start(12))?;
+    let mut id = [0; 4];
+    reader.read_exact(&mut id)?;
+    DataFormat::try_from(id)
+}
+
+/// Opens an ICU data file with the reader for its format.
+///
+/// Formats without a reader yet give an `InvalidData` error.
+pub fn open_any<R>(mut reader: R) -> io::Result<AnyReader<R>>
+where
+    R: Read + Seek,
+{
+    let any = match detect_format(&mut reader)? {
+        DataFormat::ResourceBundle => AnyReader::ResourceBundle(ResourceBundleReader::try_init(
+            reader,
+            DataFormat::ResourceBundle,
+        )?),
+        DataFormat::Dat => AnyReader::Dat(DatPackageReader::try_init(reader)?),
+        DataFormat::Normalized2 => AnyReader::Normalized2(Normalizer2Data::try_init(reader)?),
+        DataFormat::CharacterProperty => {
+            AnyReader::CharacterProperty(UPropsData::try_init(reader)?)
+        }
+        DataFormat::Collation => AnyReader::Collation(CollationData::try_init(reader)?),
+        DataFormat::BiDi => AnyReader::BiDi(BiDiData::try_init(reader)?),
+        DataFormat::Case => AnyReader::Case(CaseData::try_init(reader)?),
+        DataFormat::StringPrep => AnyReader::StringPrep(StringPrepData::try_init(reader)?),
+        DataFormat::ConverterAlias => {
+            AnyReader::ConverterAlias(ConverterAliasData::try_init(reader)?)
+        }
+        DataFormat::Converter => AnyReader::Converter(ConverterData::try_init(reader)?),
+        DataFormat::CharacterName => AnyReader::CharacterName(CharNameData::try_init(reader)?),
+        DataFormat::PropertyAlias => {
+            AnyReader::PropertyAlias(PropertyAliasData::try_init(reader)?)
+        }
+        DataFormat::Dictionary => AnyReader::Dictionary(DictionaryData::try_init(reader)?),
+        DataFormat::BreakIteration => AnyReader::BreakIteration(BreakData::try_init(reader)?),
+        format => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("no reader for {} data", format),
+            ))
+        }
+    };
+    Ok(any)
+}
+
+/// Computes the checksum ICU's `genrb` stores for a pool bundle, over the
+/// bytes of its keys: a CRC-32 that starts from 0 and isn't inverted at the
+/// end, unlike the common one.
+pub fn compute_pool_checksum(keys: &[u8]) -> u32 {
+    keys.iter().fold(0, |crc, &b| {
+        let mut c = (crc ^ u32::from(b)) & 0xff;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { (c >> 1) ^ 0xedb8_8320 } else { c >> 1 };
+        }
+        (crc >> 8) ^ c
+    })
+}
+
+/// The locale ids whose bundles `locale` falls back to, starting with
+/// `locale` itself and ending with `root`: each drops the last
+/// `_`-delimited subtag of the one before, so `"sr_Latn_RS"` gives
+/// `["sr_Latn_RS", "sr_Latn", "sr", "root"]`. Empty subtags are dropped
+/// along with the one after them.
+///
+/// Callers that don't want the root bundle can pop it off the end.
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+    fallback_chain_with(locale, |_| None)
+}
+
+/// Like `fallback_chain`, but a locale whose bundle names an explicit
+/// parent, as `explicit_parent` gives it, falls back to that parent rather
+/// than to its truncation, so `"es_MX"` with the parent `"es_419"` gives
+/// `["es_MX", "es_419", "es", "root"]`.
+///
+/// The chain stops short of any locale already in it, so parents that
+/// form a cycle can't make it go on forever.
+pub fn fallback_chain_with<F>(locale: &str, mut explicit_parent: F) -> Vec<String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut chain: Vec<String> = Vec::new();
+    let mut id = locale.to_string();
+    while !id.is_empty() && id != "root" && !chain.contains(&id) {
+        let parent = match explicit_parent(&id) {
+            Some(parent) => parent,
+            None => match id.rfind('_') {
+                Some(i) => id[..i].trim_end_matches('_').to_string(),
+                None => String::new(),
+            },
+        };
+        chain.push(id);
+        id = parent;
+    }
+    chain.push("root".to_string());
+    chain
+}
+
+/// `s` in double quotes, with quotes, backslashes and control characters
+/// escaped as in ICU's resource bundle source files.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Turns an end-of-file error `e` from reading `what` at `pos` into one
+/// that says so; other errors pass through.
+fn eof_context(e: io::Error, what: &'static str, pos: u64) -> io::Error {
+    if e.kind() == ErrorKind::UnexpectedEof {
+        IcuError::UnexpectedEof { what, pos }.into()
+    } else {
+        e
+    }
+}
+
+/// Sign-extends the low 28 bits of a resource word, from bit 27.
+fn int28(word: u32) -> i32 {
+    ((word << 4) as i32) >> 4
+}
+
+/// Checks that `bytes` hold ICU data and wraps them in the data's byte order.
+fn wrap_ordered<R>(mut bytes: R) -> Result<OrderedReader<R>, IcuError>
+where
+    R: Read + Seek,
+{
+    check_magic(&mut bytes)?;
+    let order = if read_endianness(&mut bytes)? == 1 {
+        Order::BigEndian
+    } else {
+        Order::LittleEndian
+    };
+    Ok(OrderedReader::wrap(bytes, order)?)
+}
+
+pub fn read_header<R>(
+    reader: &mut OrderedReader<R>,
+    data_format: DataFormat,
+) -> Result<(u16, PiecewiseVersion), IcuError>
+where
+    R: Read + Seek,
+{
+    let (header_size, data_version, _) = read_header_in(reader, data_format, &[CHARSET_FAMILY])?;
+    Ok((header_size, data_version))
+}
+
+/// Like `read_header`, but accepts data in any of `charset_families`, and
+/// gives the family it's in as well.
+fn read_header_in<R>(
+    reader: &mut OrderedReader<R>,
+    data_format: DataFormat,
+    charset_families: &[u8],
+) -> Result<(u16, PiecewiseVersion, u8), IcuError>
+where
+    R: Read + Seek,
+{
+    let header_size = read_header_size(reader)?;
+    reader.seek_checked(0, header_size.into())?;
+    validate_format_version(reader, data_format)?;
+    let charset_family = check_charset(reader, charset_families, CHAR_SIZE)?;
+
+    let data_version = read_data_version(reader)?;
+    reader.seek_checked(header_size.into(), 0)?;
+    Ok((header_size, data_version, charset_family))
+}
+
+/// Writes a 32-byte ICU data header, returning its size.
+///
+/// The header size and the size of the data info that follows the magic
+/// number are written in `order`, like the data itself.
+pub fn write_header<W>(
+    w: &mut W,
+    format: DataFormat,
+    format_version: [u8; 4],
+    data_version: PiecewiseVersion,
+    order: Order,
+) -> io::Result<u16>
+where
+    W: Write,
+{
+    const HEADER_SIZE: u16 = 32;
+    const DATA_INFO_SIZE: u16 = 20;
+    let big_endian = match order {
+        Order::BigEndian => {
+            w.write_u16::<BigEndian>(HEADER_SIZE)?;
+            w.write_all(&[MAGIC1, MAGIC2])?;
+            w.write_u16::<BigEndian>(DATA_INFO_SIZE)?;
+            1
+        }
+        Order::LittleEndian => {
+            w.write_u16::<LittleEndian>(HEADER_SIZE)?;
+            w.write_all(&[MAGIC1, MAGIC2])?;
+            w.write_u16::<LittleEndian>(DATA_INFO_SIZE)?;
+            0
+        }
+    };
+    let (v0, v1, v2, v3) = data_version;
+    w.write_all(&[0, 0, big_endian, CHARSET_FAMILY, CHAR_SIZE, 0])?;
+    w.write_all(&<[u8; 4]>::from(format))?;
+    w.write_all(&format_version)?;
+    w.write_all(&[v0, v1, v2, v3])?;
+    // pad the header to a multiple of 16 bytes
+    w.write_all(&[0; 8])?;
+    Ok(HEADER_SIZE)
+}
+
+fn read_data_version<R>(reader: &mut OrderedReader<R>) -> io::Result<PiecewiseVersion>
+where
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(20))?;
+    let data_version = (
+        reader.read_u8()?,
+        reader.read_u8()?,
+        reader.read_u8()?,
+        reader.read_u8()?,
+    );
+    Ok(data_version)
+}
+
+fn read_header_size<R>(reader: &mut OrderedReader<R>) -> Result<u16, IcuError>
+where
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(0))?;
+    let header_size = reader.read_u16_ctx("the header size")?;
+    reader.seek(SeekFrom::Start(4))?;
+    let data_info_size = reader.read_u16_ctx("the data info size")?;
+    if data_info_size < 20 || header_size < (data_info_size + 4) {
+        return Err(IcuError::HeaderAuth);
+    }
+    let len = reader.len();
+    if u64::from(header_size) > len {
+        return Err(IcuError::HeaderExceedsFile { header_size, len });
+    }
+    Ok(header_size)
+}
+
+fn check_magic<B>(bytes: &mut B) -> Result<(), IcuError>
+where
+    B: Read + Seek,
+{
+    // no header, even one with nothing after the UDataInfo, is smaller
+    let len = bytes.seek(SeekFrom::End(0))?;
+    if len < MIN_HEADER_SIZE {
+        return Err(IcuError::TooSmall { len });
+    }
+    bytes.seek(SeekFrom::Start(2))?;
+    let magic1 = bytes.read_u8()?;
+    let magic2 = bytes.read_u8()?;
+    if magic1 != MAGIC1 || magic2 != MAGIC2 {
+        Err(IcuError::BadMagic)
+    } else {
+        Ok(())
+    }
+}
+
+fn read_endianness<B>(bytes: &mut B) -> Result<u8, IcuError>
+where
+    B: Read + Seek,
+{
+    bytes.seek(SeekFrom::Start(8))?;
+    let big_endian = bytes.read_u8()?;
+    if big_endian > 1 {
+        Err(IcuError::HeaderAuth)
+    } else {
+        Ok(big_endian)
+    }
+}
+
+/// Checks the charset family and code unit size the header gives against
+/// those the caller can read, returning the family.
+fn check_charset<B>(bytes: &mut B, charset_families: &[u8], char_size: u8) -> Result<u8, IcuError>
+where
+    B: Read + Seek,
+{
+    bytes.seek(SeekFrom::Start(9))?;
+    let family = bytes.read_u8()?;
+    if !charset_families.contains(&family) {
+        return Err(IcuError::UnsupportedCharset(family));
+    }
+    let size = bytes.read_u8()?;
+    if size != char_size {
+        return Err(IcuError::UnsupportedCharSize(size));
+    }
+    Ok(family)
+}
+
+/// The ASCII form of a byte of EBCDIC's invariant characters, as in ICU's
+/// `asciiFromEbcdic`, or 0xff, which isn't ASCII, for any other byte.
+fn ascii_from_ebcdic(b: u8) -> u8 {
+    match b {
+        0x00 => 0,
+        0x05 => b'\t',
+        0x0d => b'\r',
+        0x15 | 0x25 => b'\n',
+        0x40 => b' ',
+        0x4b => b'.',
+        0x4c => b'<',
+        0x4d => b'(',
+        0x4e => b'+',
+        0x50 => b'&',
+        0x5c => b'*',
+        0x5d => b')',
+        0x5e => b';',
+        0x60 => b'-',
+        0x61 => b'/',
+        0x6b => b',',
+        0x6c => b'%',
+        0x6d => b'_',
+        0x6e => b'>',
+        0x6f => b'?',
+        0x7a => b':',
+        0x7d => b'\'',
+        0x7e => b'=',
+        0x7f => b'"',
+        0x81..=0x89 => b'a' + (b - 0x81),
+        0x91..=0x99 => b'j' + (b - 0x91),
+        0xa2..=0xa9 => b's' + (b - 0xa2),
+        0xc1..=0xc9 => b'A' + (b - 0xc1),
+        0xd1..=0xd9 => b'J' + (b - 0xd1),
+        0xe2..=0xe9 => b'S' + (b - 0xe2),
+        0xf0..=0xf9 => b'0' + (b - 0xf0),
+        _ => 0xff,
+    }
+}
+
+/// The EBCDIC form of an ASCII invariant character, as in ICU's
+/// `ebcdicFromAscii`, or `None` for any other byte.
+fn ebcdic_from_ascii(b: u8) -> Option<u8> {
+    let e = match b {
+        0 => 0x00,
+        b'\t' => 0x05,
+        b'\r' => 0x0d,
+        b'\n' => 0x25,
+        b' ' => 0x40,
+        b'.' => 0x4b,
+        b'<' => 0x4c,
+        b'(' => 0x4d,
+        b'+' => 0x4e,
+        b'&' => 0x50,
+        b'*' => 0x5c,
+        b')' => 0x5d,
+        b';' => 0x5e,
+        b'-' => 0x60,
+        b'/' => 0x61,
+        b',' => 0x6b,
+        b'%' => 0x6c,
+        b'_' => 0x6d,
+        b'>' => 0x6e,
+        b'?' => 0x6f,
+        b':' => 0x7a,
+        b'\'' => 0x7d,
+        b'=' => 0x7e,
+        b'"' => 0x7f,
+        b'a'..=b'i' => 0x81 + (b - b'a'),
+        b'j'..=b'r' => 0x91 + (b - b'j'),
+        b's'..=b'z' => 0xa2 + (b - b's'),
+        b'A'..=b'I' => 0xc1 + (b - b'A'),
+        b'J'..=b'R' => 0xd1 + (b - b'J'),
+        b'S'..=b'Z' => 0xe2 + (b - b'S'),
+        b'0'..=b'9' => 0xf0 + (b - b'0'),
+        _ => return None,
+    };
+    Some(e)
+}
+
+fn validate_format_version<R>(
+    reader: &mut OrderedReader<R>,
+    data_format: DataFormat,
+) -> Result<(), IcuError>
+where
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(12))?;
+    let mut df = [0; 4];
+    reader.read_exact(&mut df)?;
+    if df != <[u8; 4]>::from(data_format) {
+        return Err(IcuError::HeaderAuth);
+    }
+    // format version starts at 16
+    let format_version = [
+        reader.read_u8()?,
+        reader.read_u8()?,
+        reader.read_u8()?,
+        reader.read_u8()?,
+    ];
+    if !data_format.is_acceptable_version(format_version) {
+        return Err(IcuError::UnsupportedFormatVersion {
+            format: data_format,
+            version: format_version,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use error::IcuError;
+    use {fallback_chain, fallback_chain_with};
+    use quote;
+    use {detect_format, open_any, read_header, wrap_ordered, write_header, AnyReader, Order};
+    use AliasTarget;
+    use BundleInfo;
+    use FallbackBehavior;
+    use IndexSlot;
+    use ResourceType;
+    use resource_from_16;
+    use compute_pool_checksum;
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+    use DATA_FORMATS;
+    use {EndianReader, OrderedReader};
+    use BufferedSource;
+    use SliceReader;
+    use DataFormat;
+    use Resource;
+    use BundleKey;
+    use URES_ARRAY;
+    use ResourceBundleReader;
+    use std::io;
+    use std::io::{Cursor, SeekFrom};
+    use std::io::prelude::*;
+    use std::ops::Range;
+    use value::Value;
+    use visit::Visitor;
+    #[cfg(feature = "serde")]
+    extern crate serde_json;
+
+    /// Synthesizes a big-endian bundle from the 32-bit words of its data,
+    /// starting with the root resource.
+    fn bundle(words: &[u32]) -> Cursor<Vec<u8>> {
+        bundle_with_format([3, 0, 0, 0], words)
+    }
+
+    /// Like `bundle`, with the format version `format_version`.
+    fn bundle_with_format(format_version: [u8; 4], words: &[u32]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::ResourceBundle;
+        write_header(&mut bytes, format, format_version, (1, 4, 0, 0), Order::BigEndian).unwrap();
+        for w in words {
+            bytes.extend_from_slice(&[(w >> 24) as u8, (w >> 16) as u8, (w >> 8) as u8, *w as u8]);
+        }
+        Cursor::new(bytes)
+    }
+
+    /// The little-endian form of the big-endian bundle `c`: each 32-bit word
+    /// after the header is byte-swapped, except the keys in the words
+    /// `keys`, which are bytes, and the words in `units`, which each hold
+    /// two 16-bit units.
+    fn little_endian(
+        c: Cursor<Vec<u8>>,
+        keys: Range<usize>,
+        units: &[Range<usize>],
+    ) -> Cursor<Vec<u8>> {
+        let big = c.into_inner();
+        let mut bytes = Vec::new();
+        let format = DataFormat::ResourceBundle;
+        write_header(&mut bytes, format, [3, 0, 0, 0], (1, 4, 0, 0), Order::LittleEndian).unwrap();
+        for (i, w) in big[32..].chunks(4).enumerate() {
+            if keys.contains(&i) {
+                bytes.extend_from_slice(w);
+            } else if units.iter().any(|units| units.contains(&i)) {
+                bytes.extend_from_slice(&[w[1], w[0], w[3], w[2]]);
+            } else {
+                bytes.extend_from_slice(&[w[3], w[2], w[1], w[0]]);
+            }
+        }
+        Cursor::new(bytes)
+    }
+
+    fn open(c: Cursor<Vec<u8>>) -> ResourceBundleReader<Cursor<Vec<u8>>> {
+        ResourceBundleReader::try_init(c, DataFormat::ResourceBundle)
+            .expect("Failed to read header")
+    }
+
+    /// A bundle whose root table is `{ a: [5, "hi"], b: -1 }`.
+    fn small_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_000d, // root: table at 13
+            // indexes: length, keys top, resources top, bundle top, max table length
+            5, 7, 17, 17, 2,
+            0x6100_6200, // keys: "a" at byte 24, "b" at byte 26
+            2, 0x0068_0069, 0, // 7: "hi"
+            2, 0x7000_0005, 7, // 10: [5, "hi"]
+            0x0002_0018, 0x001a_0000, 0x8000_000a, 0x7fff_ffff, // 13: { a: 10, b: -1 }
+        ])
+    }
+
+    #[test]
+    fn read_header_doesnt_fail() {
+        // header from a real resource bundle, followed by its root resource
+        // and indexes; the indexes after RES_INDEX_RESOURCES_TOP are made up
+        let mut c = Cursor::new(vec![
+            0x0, 0x20, 0xda, 0x27,
+            0x0, 0x14, 0x0, 0x0,
+            0x1, 0x0, 0x02, 0x0,
+            0x52, 0x65, 0x73, 0x42,
+            0x03, 0x0, 0x0, 0x0,
+            0x01, 0x04, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0,
+            0x20, 0x0, 0x18, 0x78,
+            0x0, 0xcb, 0x92, 0x08,
+            0x0, 0x0, 0x0, 0x09,
+            0x0, 0x0, 0x18, 0x92,
+            0x0, 0x0, 0x18, 0x92,
+            0x0, 0x0, 0x0, 0x2c,
+            0x0a, 0x8f, 0x0, 0x04,
+            0x0, 0x0, 0x0c, 0xce,
+            0x8a, 0x3c, 0xf9, 0xd2,
+        ]);
+        let r = ResourceBundleReader::try_init(&mut c, DataFormat::ResourceBundle)
+            .expect("Failed to read header");
+        assert_eq!(r.data_version(), (0x01, 0x04, 0x0, 0x0));
+        assert_eq!(r.version(), None);
+        // each index is a 32-bit word, so the slots land on the words above
+        assert_eq!(r.data_size(), 4 * 0x1892);
+        assert_eq!(r.max_table_length(), 0x2c);
+        assert!(!r.no_fallback() && !r.is_pool_bundle() && r.uses_pool_bundle());
+        assert_eq!(r.pool_checksum(), Some(0x8a3c_f9d2));
+        // the 16-bit units start at the keys top, nine words in
+        assert_eq!(r.units_16_base, 0x20 + 4 * 9);
+        assert_eq!(r.units_16_length, 2 * (0xcce - 9));
+    }
+
+    #[test]
+    fn save_and_restore_position_nest() {
+        let mut r = open(small_bundle());
+        r.seek_to(32).unwrap();
+        let outer = r.save_position().unwrap();
+        assert_eq!(outer, 32);
+
+        let root = r.root_resource();
+        let a = r.get(root, "a").unwrap().unwrap();
+        let inner = r.save_position().unwrap();
+        assert_ne!(inner, outer);
+        assert_eq!(r.resource(BundleKey(7)).unwrap(), Resource::String("hi".to_string()));
+        r.restore_position(inner).unwrap();
+        assert_eq!(r.save_position().unwrap(), inner);
+        assert_eq!(r.array(a).unwrap(), [BundleKey(0x7000_0005), BundleKey(7)]);
+
+        r.restore_position(outer).unwrap();
+        assert_eq!(r.save_position().unwrap(), 32);
+        let len = 32 + 4 * 17;
+        r.seek_to(len).unwrap();
+        assert!(r.seek_to(len + 1).is_err());
+        assert_eq!(r.save_position().unwrap(), len);
+    }
+
+    #[test]
+    fn header_bytes_and_data_info_are_raw_header_bytes() {
+        let bytes = small_bundle().into_inner();
+        let mut r = open(small_bundle());
+        let header = r.header_bytes().unwrap();
+        assert_eq!(header.len(), usize::from(r.header_size));
+        assert_eq!(header, &bytes[..32]);
+        let info = r.data_info().unwrap();
+        assert_eq!(info.len(), 20);
+        assert_eq!(info, &bytes[4..24]);
+        assert_eq!(&info[8..12], b"ResB");
+        assert_eq!(r.get_by_path("a/1").unwrap(), Some(BundleKey(7)));
+
+        // a UDataInfo that claims to run past the header
+        r.reader.reader.get_mut()[5] = 40;
+        assert!(r.data_info().is_err());
+    }
+
+    #[test]
+    fn little_endian_bundles_read_like_big_endian_ones() {
+        let pairs = vec![
+            // the keys, then "hi" and the root table's 16-bit count and keys
+            (little_endian(small_bundle(), 6..7, &[8..10, 13..15]), small_bundle()),
+            // the keys, then the 16-bit units and the root table's count and
+            // keys
+            (little_endian(table16_bundle(), 8..10, &[10..18, 18..20]), table16_bundle()),
+        ];
+        for (le, be) in pairs {
+            let (mut le, mut be) = (open(le), open(be));
+            assert_eq!(le.version(), be.version());
+            assert_eq!(le.data_version(), be.data_version());
+            assert_eq!(le.root_resource(), be.root_resource());
+            assert_eq!(le.data_size(), be.data_size());
+            let root = be.root_resource();
+            assert_eq!(le.to_value(root).unwrap(), be.to_value(root).unwrap());
+        }
+        let mut r = open(little_endian(table16_bundle(), 8..10, &[10..18, 18..20]));
+        assert_eq!(r.get_string("t/b").unwrap(), Some("ho".to_string()));
+        assert_eq!(r.get_string("r/1").unwrap(), Some("hi".to_string()));
+    }
+
+    #[test]
+    fn into_inner_gives_back_the_reader() {
+        let mut r = open(small_bundle());
+        r.seek_to(40).unwrap();
+        let c = r.into_inner();
+        assert_eq!(c.position(), 40);
+        assert_eq!(c.into_inner(), small_bundle().into_inner());
+
+        let ordered = OrderedReader::wrap(Cursor::new(vec![1, 2]), Order::BigEndian).unwrap();
+        assert_eq!(ordered.into_inner().into_inner(), [1, 2]);
+    }
+
+    #[test]
+    fn owned_readers_make_bundles_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ResourceBundleReader<Cursor<Vec<u8>>>>();
+        assert_send_sync::<ResourceBundleReader<::std::fs::File>>();
+        assert_send_sync::<ResourceBundleReader<SliceReader<&mut ::std::fs::File>>>();
+        #[cfg(feature = "memmap")]
+        assert_send_sync::<ResourceBundleReader<::MappedSource>>();
+    }
+
+    #[test]
+    fn resource_decodes_root_table() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        assert_eq!(
+            r.resource(root).unwrap(),
+            Resource::Table(vec![(0x18, BundleKey(0x8000_000a)), (0x1a, BundleKey(0x7fff_ffff))])
+        );
+        assert_eq!(
+            r.resource(BundleKey(0x8000_000a)).unwrap(),
+            Resource::Array(vec![BundleKey(0x7000_0005), BundleKey(7)])
+        );
+        assert_eq!(r.resource(BundleKey(0x7000_0005)).unwrap(), Resource::Int(5));
+        assert_eq!(r.resource(BundleKey(0x7fff_ffff)).unwrap(), Resource::Int(-1));
+        assert_eq!(r.resource(BundleKey(7)).unwrap(), Resource::String("hi".to_string()));
+    }
+
+    #[test]
+    fn resource_rejects_unsupported_types() {
+        let mut r = open(small_bundle());
+        assert!(r.resource(BundleKey(0xf000_0000)).is_err());
+    }
+
+    #[test]
+    fn key_at_reads_keys() {
+        let r = open(small_bundle());
+        assert_eq!(r.key_at(0x18).unwrap(), "a");
+        assert_eq!(r.key_at(0x1a).unwrap(), "b");
+        assert!(r.key_at(0x14).is_err());
+        assert!(r.key_at(0x1c).is_err());
+    }
+
+    #[test]
+    fn all_keys_lists_the_keys_block() {
+        let r = open(bundle(&[
+            0x2000_0000, // root: empty table
+            5, 9, 9, 9, 0,
+            // keys: "ab" at 24, "c" at 27 and, after a padding NUL, "def" at 30
+            0x6162_0063, 0x0000_6465, 0x6600_aaaa,
+        ]));
+        let keys: Vec<_> = r.all_keys().collect();
+        assert_eq!(keys, [(24, "ab"), (27, "c"), (30, "def")]);
+        for (offset, key) in keys {
+            assert_eq!(r.key_at(offset).unwrap(), key);
+        }
+
+        let r = open(pool_bundle(POOL_CHECKSUM));
+        assert_eq!(r.all_keys().collect::<Vec<_>>(), [(0, "p"), (2, "q"), (4, "r")]);
+        assert_eq!(open(bundle(&[0x2000_0000, 5, 6, 6, 6, 0])).all_keys().count(), 0);
+    }
+
+    #[test]
+    fn key_at_rejects_non_ascii_keys() {
+        let c = bundle(&[0x2000_0007, 5, 7, 7, 7, 0, 0x61e9_0000]);
+        let r = open(c);
+        assert!(r.key_at(0x18).is_err());
+        assert_eq!(r.key_at(0x1a).unwrap(), "");
+    }
+
+    #[test]
+    fn key_at_gives_ebcdic_keys_in_ascii() {
+        let mut bytes = small_bundle().into_inner();
+        bytes[9] = 1; // the EBCDIC charset family
+        bytes[56] = 0x81; // 'a'
+        bytes[58] = 0x82; // 'b'
+        let mut r = open(Cursor::new(bytes));
+        assert_eq!(r.key_at(0x18).unwrap(), "a");
+        assert_eq!(r.key_at(0x1a).unwrap(), "b");
+        assert_eq!(r.get_by_path("b").unwrap().map(|res| res.0), Some(0x7fff_ffff));
+
+        // "Zz_9" and a key holding a cent sign, which isn't invariant
+        let words = [0x2000_0008, 5, 8, 8, 8, 0, 0xe9a9_6df9, 0x004a_0000];
+        let mut bytes = bundle(&words).into_inner();
+        bytes[9] = 1;
+        let r = open(Cursor::new(bytes));
+        assert_eq!(r.key_at(0x18).unwrap(), "Zz_9");
+        assert!(r.key_at(0x1d).is_err());
+        assert_eq!(r.all_keys().collect::<Vec<_>>(), [(0x18, "Zz_9")]);
+
+        // { a: 1, B: 2, 1: 3 }, sorted in EBCDIC order as ures_swap leaves it
+        let words = [
+            0x2000_0008, // root: table at 8
+            5, 8, 13, 13, 3, // indexes
+            0x8100_c200, 0xf100_aaaa, // keys: "a" at byte 24, "B" at 26, "1" at 28
+            0x0003_0018, 0x001a_001c, 0x7000_0001, 0x7000_0002, 0x7000_0003,
+        ];
+        let mut bytes = bundle(&words).into_inner();
+        bytes[9] = 1;
+        let mut r = open(Cursor::new(bytes));
+        for &(key, value) in &[("a", 1), ("B", 2), ("1", 3)] {
+            assert_eq!(r.get_i32(key).unwrap(), Some(value));
+        }
+        assert_eq!(r.get_by_path("b").unwrap(), None);
+        assert_eq!(r.get_by_path("\u{a2}").unwrap(), None);
+    }
+
+    #[test]
+    fn get_resolves_keys_in_large_tables() {
+        // keys "k000" to "k199", five bytes each from byte 24, then a table
+        // mapping each to its number
+        let mut key_bytes = Vec::new();
+        for i in 0..200 {
+            key_bytes.extend_from_slice(format!("k{:03}\0", i).as_bytes());
+        }
+        let mut words = vec![0x2000_0100, 5, 256, 557, 557, 200];
+        for chunk in key_bytes.chunks(4) {
+            words.push(chunk.iter().fold(0, |w, &b| (w << 8) | u32::from(b)));
+        }
+        let mut units = vec![200u16];
+        units.extend((0..200).map(|i| 24 + 5 * i as u16));
+        units.push(0);
+        for pair in units.chunks(2) {
+            words.push((u32::from(pair[0]) << 16) | u32::from(pair[1]));
+        }
+        words.extend((0..200).map(|i| 0x7000_0000 | i));
+        let mut r = open(bundle(&words));
+        let root = r.root_resource();
+        for i in 0..200 {
+            let key = format!("k{:03}", i);
+            assert_eq!(r.get(root, &key).unwrap(), Some(BundleKey(0x7000_0000 | i)));
+        }
+        assert_eq!(r.get(root, "k200").unwrap(), None);
+        assert_eq!(r.get(root, "j").unwrap(), None);
+
+        // keys borrow from the reader's copy of the keys
+        let key: &str = r.key_at(24 + 5 * 7).unwrap();
+        assert_eq!(key, "k007");
+        assert_eq!(key.as_ptr(), r.key_at(24 + 5 * 7).unwrap().as_ptr());
+    }
+
+    #[test]
+    fn get_finds_table_entries_by_key() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        assert_eq!(r.get(root, "a").unwrap(), Some(BundleKey(0x8000_000a)));
+        assert_eq!(r.get(root, "b").unwrap(), Some(BundleKey(0x7fff_ffff)));
+        assert_eq!(r.get(root, "c").unwrap(), None);
+        assert_eq!(r.get(root, "").unwrap(), None);
+        assert!(r.get(BundleKey(0x8000_000a), "a").is_err());
+    }
+
+    /// A format 3 bundle with `URES_STRING_V2` strings in its 16-bit units.
+    fn string_v2_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_0014, // root: table at 20
+            7, 9, 24, 24, 2, 0, 20, // indexes, through RES_INDEX_16BIT_TOP
+            0x6100_6200, // keys: "a" at byte 32, "b" at byte 34
+            // 16-bit units from word 9:
+            // 0: "", 1: "hi", 4: "ho", 8: "x", 12: "y", 17: lone surrogate,
+            // 19: U+1F600
+            0x0000_0068, 0x0069_0000, 0xdc02_0068, 0x006f_0000, 0xdfef_0001,
+            0x0078_0000, 0xdfff_0000, 0x0001_0079, 0x0000_d800, 0x0000_d83d,
+            0xde00_0000,
+            0x0002_0020, 0x0022_0000, 0x6000_0001, 0x6000_0004, // 20: { a: "hi", b: "ho" }
+        ])
+    }
+
+    #[test]
+    fn string_v2_decodes_each_length_encoding() {
+        let mut r = open(string_v2_bundle());
+        assert_eq!(r.string_v2(BundleKey(0x6000_0000)).unwrap(), "");
+        assert_eq!(r.string_v2(BundleKey(0x6000_0001)).unwrap(), "hi");
+        assert_eq!(r.string_v2(BundleKey(0x6000_0004)).unwrap(), "ho");
+        assert_eq!(r.string_v2(BundleKey(0x6000_0008)).unwrap(), "x");
+        assert_eq!(r.string_v2(BundleKey(0x6000_000c)).unwrap(), "y");
+        assert_eq!(r.string_v2(BundleKey(0x6000_0013)).unwrap(), "\u{1f600}");
+        let root = r.root_resource();
+        let b = r.get(root, "b").unwrap().unwrap();
+        assert_eq!(r.resource(b).unwrap(), Resource::String("ho".to_string()));
+    }
+
+    /// A bundle whose root table is `{ a: "hi", b: "" }`, with its strings
+    /// stored as legacy `URES_STRING`s the way format 1 stores them all.
+    fn legacy_bundle(format_version: [u8; 4]) -> Cursor<Vec<u8>> {
+        let words = [
+            0x2000_000a, // root: table at 10
+            5, 7, 14, 14, 2,
+            0x6100_6200, // keys: "a" at byte 24, "b" at byte 26
+            2, 0x0068_0069, 0, // 7: "hi"
+            0x0002_0018, 0x001a_0000, 7, 0, // 10: { a: 7, b: 0 }
+        ];
+        bundle_with_format(format_version, &words)
+    }
+
+    #[test]
+    fn root_tables_decode_in_each_format() {
+        for &version in &[[1, 2, 0, 0], [2, 0, 0, 0], [3, 0, 0, 0]] {
+            let mut r = open(legacy_bundle(version));
+            assert_eq!(r.format_major_version(), version[0]);
+            let root = r.root_resource();
+            let entries = vec![(0x18, BundleKey(7)), (0x1a, BundleKey(0))];
+            assert_eq!(r.resource(root).unwrap(), Resource::Table(entries));
+            let a = r.get(root, "a").unwrap().unwrap();
+            assert_eq!(r.resource(a).unwrap(), Resource::String("hi".to_string()));
+        }
+        for &major in &[2, 3] {
+            let mut r = open(bundle_with_format([major, 0, 0, 0], &table16_words()));
+            assert_eq!(r.units_16_length, 16);
+            let root = r.root_resource();
+            let table = r.get(root, "t").unwrap().unwrap();
+            let a = r.get(table, "a").unwrap().unwrap();
+            assert_eq!(r.resource(a).unwrap(), Resource::String("hi".to_string()));
+        }
+    }
+
+    #[test]
+    fn format_1_bundles_have_no_16_bit_units() {
+        let mut r = open(bundle_with_format([1, 2, 0, 0], &table16_words()));
+        assert_eq!(r.units_16_length, 0);
+        assert!(r.resource(BundleKey(0x5000_0007)).is_err());
+        assert!(r.string_v2(BundleKey(0x6000_0001)).is_err());
+    }
+
+    #[test]
+    fn format_1_bundles_read_legacy_strings() {
+        let mut r = open(legacy_bundle([1, 2, 0, 0]));
+        let a = r.get_by_path("a").unwrap().unwrap();
+        assert_eq!(r.resource(a).unwrap(), Resource::String("hi".to_string()));
+        assert_eq!(r.string_len(a).unwrap(), 2);
+        let b = r.get_by_path("b").unwrap().unwrap();
+        assert_eq!(r.resource(b).unwrap(), Resource::String(String::new()));
+    }
+
+    #[test]
+    fn format_1_bundles_reject_string_v2() {
+        let mut r = open(legacy_bundle([1, 2, 0, 0]));
+        assert!(r.resource(BundleKey(0x6000_0000)).is_err());
+        assert!(r.string_v2(BundleKey(0x6000_0001)).is_err());
+        assert!(r.string_len(BundleKey(0x6000_0001)).is_err());
+
+        let mut r = open(legacy_bundle([2, 0, 0, 0]));
+        assert_eq!(r.string_v2(BundleKey(0x6000_0000)).unwrap(), "");
+        let a = r.get_by_path("a").unwrap().unwrap();
+        assert_eq!(r.resource(a).unwrap(), Resource::String("hi".to_string()));
+    }
+
+    #[test]
+    fn string_len_counts_units_without_decoding() {
+        let mut r = open(string_v2_bundle());
+        let lengths = [(0, 0), (1, 2), (4, 2), (8, 1), (12, 1), (17, 1), (19, 2)];
+        for &(offset, length) in &lengths {
+            assert_eq!(r.string_len(BundleKey(0x6000_0000 | offset)).unwrap(), length);
+        }
+        assert_eq!(r.string_len(BundleKey(0)).unwrap(), 0);
+        assert!(r.string_len(BundleKey(0x7000_0001)).is_err());
+
+        let mut r = open(small_bundle());
+        assert_eq!(r.string_len(BundleKey(7)).unwrap(), 2);
+        assert!(r.string_len(BundleKey(0x8000_000a)).is_err());
+    }
+
+    #[test]
+    fn string_v2_rejects_bad_strings() {
+        let mut r = open(string_v2_bundle());
+        assert!(r.string_v2(BundleKey(0x6000_0011)).is_err());
+        assert!(r.string_v2(BundleKey(0x6000_0016)).is_err());
+        assert!(r.string_v2(BundleKey(0x0000_0001)).is_err());
+    }
+
+    /// The checksum of `pool_bundle`'s keys.
+    const POOL_CHECKSUM: u32 = 0xcff3_3976;
+
+    /// A pool bundle with the keys "p", "q", "r" and the string "Pool".
+    fn pool_bundle(checksum: u32) -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_0000, // root: empty table
+            8, 11, 14, 14, 0, 2, 14, checksum, // indexes
+            0x7000_7100, 0x7200_aaaa, // keys: "p" at 0, "q" at 2, "r" at 4
+            0x0000_0050, 0x006f_006f, 0x006c_0000, // 16-bit units: 1: "Pool"
+        ])
+    }
+
+    /// A bundle whose root table is `{ a: "x", p: "Pool" }`, sharing the key
+    /// "p" and the string "Pool" with `pool_bundle`.
+    fn pooled_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_000b, // root: table at 11
+            6 << 8 | 8, 10, 15, 15, 2, 4, 11, POOL_CHECKSUM, // indexes, using 6 pool units
+            0x6100_aaaa, // keys: "a" at byte 36
+            0x0078_0000, // 16-bit units: 6: "x"
+            0x0002_0024, 0x0028_0000, 0x6000_0006, 0x6000_0001, // 11: { a: "x", p: "Pool" }
+        ])
+    }
+
+    #[test]
+    fn with_pool_resolves_pooled_keys_and_strings() {
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        let mut r = open(pooled_bundle());
+        assert_eq!(r.string_v2(BundleKey(0x6000_0006)).unwrap(), "x");
+        assert!(r.string_v2(BundleKey(0x6000_0001)).is_err());
+        assert!(r.key_at(0x8000_0000).is_err());
+
+        let mut r = r.with_pool(pool).expect("Failed to attach pool");
+        let root = r.root_resource();
+        assert_eq!(
+            r.resource(root).unwrap(),
+            Resource::Table(vec![
+                (0x24, BundleKey(0x6000_0006)),
+                (0x8000_0000, BundleKey(0x6000_0001)),
+            ])
+        );
+        assert_eq!(r.key_at(0x8000_0000).unwrap(), "p");
+        assert_eq!(r.get(root, "p").unwrap(), Some(BundleKey(0x6000_0001)));
+        assert_eq!(r.string_v2(BundleKey(0x6000_0001)).unwrap(), "Pool");
+    }
+
+    /// A bundle whose root `URES_TABLE16` is `{ a: "x", p: "Pool" }`, with
+    /// "p" and "Pool" from `pool_bundle`. Its first 6 string indexes are
+    /// the pool's, and 16-bit children below 4 are pool strings, so its
+    /// local "x", at local unit 1, is string 7 and 16-bit child 5.
+    fn pooled_table16_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x5000_0003, // root: 16-bit table at unit 3
+            6 << 8 | 8, 10, 14, 14, 2, 4 << 16 | 4, 14, POOL_CHECKSUM, // indexes
+            0x6100_aaaa, // keys: "a" at byte 36, then the pool's from byte 40
+            // 16-bit units: 1: "x", 3: { a: 5, p: 1 }
+            0x0000_0078, 0x0000_0002, 0x0024_0028, 0x0005_0001,
+        ])
+    }
+
+    #[test]
+    fn pooled_strings_split_at_the_pool_limits() {
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        let mut r = open(pooled_table16_bundle()).with_pool(pool).expect("Failed to attach pool");
+        assert_eq!(r.pool_string_index_limit, 6);
+        assert_eq!(r.pool_string_index_16_limit, 4);
+        let root = r.root_resource();
+        assert_eq!(
+            r.resource(root).unwrap(),
+            Resource::Table(vec![
+                (36, BundleKey(0x6000_0007)),
+                (0x8000_0000, BundleKey(0x6000_0001)),
+            ])
+        );
+        assert_eq!(r.string_v2(BundleKey(0x6000_0007)).unwrap(), "x");
+        assert_eq!(r.string_v2(BundleKey(0x6000_0001)).unwrap(), "Pool");
+        assert_eq!(r.get_string("a").unwrap(), Some("x".to_string()));
+        assert_eq!(r.get_string("p").unwrap(), Some("Pool".to_string()));
+        // the local units end before string 6 + 8
+        assert!(r.string_v2(BundleKey(0x6000_000e)).is_err());
+    }
+
+    #[test]
+    fn with_pool_finds_pooled_keys_in_ebcdic_order() {
+        // the pool's keys as "P", "Q" and "R", with the checksum of those
+        let mut pool_bytes = pool_bundle(0).into_inner();
+        pool_bytes[9] = 1;
+        pool_bytes[68..73].copy_from_slice(&[0xd7, 0, 0xd8, 0, 0xd9]);
+        let checksum = compute_pool_checksum(&open(Cursor::new(pool_bytes.clone())).keys.local);
+        pool_bytes[64..68].copy_from_slice(&checksum.to_be_bytes());
+
+        // { a: "x", P: "Pool" }, which ASCII order would sort the other way
+        let mut bytes = pooled_bundle().into_inner();
+        bytes[9] = 1;
+        bytes[64..68].copy_from_slice(&checksum.to_be_bytes());
+        bytes[68] = 0x81;
+        let pool = open(Cursor::new(pool_bytes));
+        let mut r = open(Cursor::new(bytes)).with_pool(pool).expect("Failed to attach pool");
+        assert_eq!(r.get_string("a").unwrap(), Some("x".to_string()));
+        assert_eq!(r.get_string("P").unwrap(), Some("Pool".to_string()));
+        assert_eq!(r.get_string("p").unwrap(), None);
+    }
+
+    #[test]
+    fn with_pool_rejects_mismatched_pools() {
+        // a pool whose first key is "s" rather than "p"
+        let mut bytes = pool_bundle(POOL_CHECKSUM).into_inner();
+        bytes[32 + 4 * 9] = b's';
+        let pool = open(Cursor::new(bytes));
+        let r = open(pooled_bundle());
+        assert!(r.with_pool(pool).is_err());
+
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        let r = open(small_bundle());
+        assert!(r.with_pool(pool).is_err());
+
+        // an ASCII pool for an EBCDIC bundle
+        let mut bytes = pooled_bundle().into_inner();
+        bytes[9] = 1;
+        bytes[68] = 0x81;
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        assert!(open(Cursor::new(bytes)).with_pool(pool).is_err());
+    }
+
+    #[test]
+    fn try_init_reports_structured_errors() {
+        let mut bytes = small_bundle().into_inner();
+        bytes[3] = 0x28;
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(IcuError::BadMagic) => {}
+            r => panic!("expected BadMagic, got {:?}", r.map(|_| ())),
+        }
+
+        let mut bytes = small_bundle().into_inner();
+        bytes[16] = 4;
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(IcuError::UnsupportedFormatVersion { version, .. }) => {
+                assert_eq!(version, [4, 0, 0, 0])
+            }
+            r => panic!("expected UnsupportedFormatVersion, got {:?}", r.map(|_| ())),
+        }
+
+        let mut bytes = small_bundle().into_inner();
+        bytes[16..20].copy_from_slice(&[0, 9, 0, 0]);
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(e) => {
+                let e = io::Error::from(e);
+                assert_eq!(e.to_string(), "ResB format version 0.9.0.0 is not supported");
+            }
+            Ok(_) => panic!("expected an error for format version 0.9"),
+        }
+
+        match ResourceBundleReader::try_init(small_bundle(), DataFormat::Collation) {
+            Err(IcuError::HeaderAuth) => {}
+            r => panic!("expected HeaderAuth, got {:?}", r.map(|_| ())),
+        }
+
+        let mut bytes = small_bundle().into_inner();
+        bytes[9] = 2;
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(IcuError::UnsupportedCharset(2)) => {}
+            r => panic!("expected UnsupportedCharset, got {:?}", r.map(|_| ())),
+        }
+        let e = IcuError::UnsupportedCharset(1);
+        assert_eq!(e.to_string(), "unsupported charset family EBCDIC");
+
+        let mut bytes = small_bundle().into_inner();
+        bytes[10] = 1;
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(IcuError::UnsupportedCharSize(1)) => {}
+            r => panic!("expected UnsupportedCharSize, got {:?}", r.map(|_| ())),
+        }
+
+        let c = bundle(&[0x2000_0000, 4, 5, 5, 5]);
+        match ResourceBundleReader::try_init(c, DataFormat::ResourceBundle) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            r => panic!("expected NotEnoughIndexes, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn table_iter_yields_entries_in_key_order() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let entries = r.table_iter(root).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(entries, vec![("a", BundleKey(0x8000_000a)), ("b", BundleKey(0x7fff_ffff))]);
+        assert!(r.table_iter(BundleKey(0x8000_000a)).is_err());
+    }
+
+    #[test]
+    fn table_entry_at_reads_raw_entries() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        assert_eq!(r.table_entry_at(root, 0).unwrap(), (0x18, BundleKey(0x8000_000a)));
+        assert_eq!(r.table_entry_at(root, 1).unwrap(), (0x1a, BundleKey(0x7fff_ffff)));
+        match r.table_entry_at(root, 2) {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidInput),
+            other => panic!("expected an error, got {:?}", other),
+        }
+        assert!(r.table_entry_at(BundleKey(0x2000_0000), 0).is_err());
+        assert!(r.table_entry_at(BundleKey(0x8000_000a), 0).is_err());
+
+        let mut r = open(table16_bundle());
+        let entry = r.table_entry_at(BundleKey(0x5000_0007), 1).unwrap();
+        assert_eq!(entry, (0x22, BundleKey(0x6000_0004)));
+    }
+
+    /// A bundle whose root table `{ a: 1, b: { a: 3 } }` and its table `b`
+    /// are `URES_TABLE32`s.
+    fn table32_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x4000_0007, // root: 32-bit table at 7
+            5, 7, 15, 15, 2,
+            0x6100_6200, // keys: "a" at byte 24, "b" at byte 26
+            2, 0x18, 0x1a, 0x7000_0001, 0x4000_000c, // 7: { a: 1, b: 12 }
+            1, 0x18, 0x7000_0003, // 12: { a: 3 }
+        ])
+    }
+
+    #[test]
+    fn bundle_key_splits_the_word() {
+        let key = BundleKey(0x8000_000a);
+        assert_eq!((key.type_tag(), key.offset()), (URES_ARRAY, 0xa));
+        assert_eq!(BundleKey(0x7fff_ffff).offset(), 0x0fff_ffff);
+        assert_eq!(open(small_bundle()).root_resource(), BundleKey(0x2000_000d));
+    }
+
+    #[test]
+    fn table_len_reads_each_width() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        assert_eq!(r.table_len(root).unwrap(), 2);
+        assert_eq!(r.table_len(BundleKey(0x2000_0000)).unwrap(), 0);
+        assert!(r.table_len(BundleKey(0x8000_000a)).is_err());
+
+        let mut r = open(table16_bundle());
+        assert_eq!(r.table_len(BundleKey(0x5000_0007)).unwrap(), 2);
+        assert_eq!(r.table_len(BundleKey(0x5000_0000)).unwrap(), 0);
+
+        let mut r = open(table32_bundle());
+        assert_eq!(r.table_len(BundleKey(0x4000_0007)).unwrap(), 2);
+        assert_eq!(r.table_len(BundleKey(0x4000_000c)).unwrap(), 1);
+        assert_eq!(r.table_len(BundleKey(0x4000_0000)).unwrap(), 0);
+        assert!(r.table_len(BundleKey(0x7000_0001)).is_err());
+    }
+
+    #[test]
+    fn table_iter_reads_table32() {
+        let mut r = open(table32_bundle());
+        let root = r.root_resource();
+        let mut iter = r.table_iter(root).unwrap();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.next().unwrap().unwrap(), ("a", BundleKey(0x7000_0001)));
+        assert_eq!(iter.next().unwrap().unwrap(), ("b", BundleKey(0x4000_000c)));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn table32_lookups_match_16_bit_tables() {
+        let mut r = open(table32_bundle());
+        let root = r.root_resource();
+        assert_eq!(r.get(root, "a").unwrap(), Some(BundleKey(0x7000_0001)));
+        assert_eq!(r.get(root, "c").unwrap(), None);
+        assert_eq!(r.get_by_path("b/a").unwrap(), Some(BundleKey(0x7000_0003)));
+        assert_eq!(r.get_by_path("b/b").unwrap(), None);
+        assert_eq!(
+            r.resource(root).unwrap(),
+            Resource::Table(vec![(0x18, BundleKey(0x7000_0001)), (0x1a, BundleKey(0x4000_000c))])
+        );
+    }
+
+    #[test]
+    fn int_vector_decodes_values() {
+        let mut r = open(bundle(&[
+            0x2000_0000, // root: empty table
+            5, 6, 10, 10, 0,
+            3, 1, 0xffff_fffe, 0x7fff_ffff, // 6: [1, -2, i32::MAX]
+        ]));
+        assert_eq!(r.int_vector(BundleKey(0xe000_0006)).unwrap(), vec![1, -2, i32::MAX]);
+        assert_eq!(
+            r.resource(BundleKey(0xe000_0006)).unwrap(),
+            Resource::IntVector(vec![1, -2, i32::MAX])
+        );
+        assert!(r.int_vector(BundleKey(0x8000_0006)).is_err());
+    }
+
+    #[test]
+    fn array_returns_child_words() {
+        let mut r = open(bundle(&[
+            0x2000_0000, // root: empty table
+            5, 6, 9, 9, 0,
+            2, 0x7000_0001, 0x7fff_fffe, // 6: [1, -2]
+        ]));
+        let items = r.array(BundleKey(0x8000_0006)).unwrap();
+        assert_eq!(items, vec![BundleKey(0x7000_0001), BundleKey(0x7fff_fffe)]);
+        let ints: Vec<_> = items.iter().map(|&item| r.int(item).unwrap()).collect();
+        assert_eq!(ints, vec![1, -2]);
+        assert_eq!(r.array(BundleKey(0x8000_0000)).unwrap(), Vec::<BundleKey>::new());
+        assert!(r.array(BundleKey(0x7000_0001)).is_err());
+
+        let mut r = open(small_bundle());
+        let items = r.array(BundleKey(0x8000_000a)).unwrap();
+        assert_eq!(items, vec![BundleKey(0x7000_0005), BundleKey(7)]);
+    }
+
+    #[test]
+    fn info_sums_up_the_header_and_indexes() {
+        let r = open(small_bundle());
+        assert_eq!(
+            r.info(),
+            BundleInfo {
+                format: DataFormat::ResourceBundle,
+                format_version: [3, 0, 0, 0],
+                data_version: (1, 4, 0, 0),
+                version: r.version(),
+                order: Order::BigEndian,
+                header_size: 32,
+                data_size: 68,
+                keys_size: 4,
+                units_16_size: 0,
+                resources_size: 40,
+                no_fallback: false,
+                is_pool_bundle: false,
+                uses_pool_bundle: false,
+            }
+        );
+
+        let info = open(table16_bundle()).info();
+        assert_eq!((info.keys_size, info.units_16_size, info.resources_size), (8, 32, 16));
+        let info = open(little_endian(table16_bundle(), 8..10, &[10..18, 18..20])).info();
+        assert_eq!(info.order, Order::LittleEndian);
+
+        let info = open(pool_bundle(POOL_CHECKSUM)).info();
+        assert!(info.is_pool_bundle && !info.uses_pool_bundle);
+        assert_eq!((info.keys_size, info.units_16_size, info.resources_size), (8, 12, 0));
+    }
+
+    #[test]
+    fn attribute_flags_are_exposed() {
+        let r = open(small_bundle());
+        assert!(!r.no_fallback() && !r.is_pool_bundle() && !r.uses_pool_bundle());
+
+        let r = open(pool_bundle(POOL_CHECKSUM));
+        assert!(!r.no_fallback() && r.is_pool_bundle() && !r.uses_pool_bundle());
+
+        let r = open(pooled_bundle());
+        assert!(!r.no_fallback() && !r.is_pool_bundle() && r.uses_pool_bundle());
+
+        let r = open(bundle(&[0x2000_0000, 6, 7, 7, 7, 0, 1]));
+        assert!(r.no_fallback() && !r.is_pool_bundle() && !r.uses_pool_bundle());
+    }
+
+    #[test]
+    fn short_indexes_leave_the_attributes_at_their_defaults() {
+        // five indexes, through RES_INDEX_MAX_TABLE_LENGTH, then a word of
+        // keys that would set every attribute if read as RES_INDEX_ATTRIBUTES
+        let r = open(bundle(&[0x2000_0000, 5, 7, 7, 7, 0, 0x0000_0007]));
+        assert!(!r.no_fallback() && !r.is_pool_bundle() && !r.uses_pool_bundle());
+        assert_eq!(r.pool_checksum(), None);
+    }
+
+    #[test]
+    fn format_1_attributes_are_read_as_far_as_the_format_has_them() {
+        // an attributes word with every attribute bit and pool limit bit set
+        let words = [0x2000_0000, 0x0000_0006, 7, 7, 7, 0, 0xffff_f007, 0];
+
+        let r = open(bundle_with_format([1, 1, 0, 0], &words));
+        assert!(!r.no_fallback() && !r.is_pool_bundle() && !r.uses_pool_bundle());
+        assert_eq!((r.pool_string_index_limit, r.pool_string_index_16_limit), (0, 0));
+        assert_eq!(r.fallback_behavior(), FallbackBehavior::Full);
+
+        let r = open(bundle_with_format([1, 2, 0, 0], &words));
+        assert!(r.no_fallback() && !r.is_pool_bundle() && !r.uses_pool_bundle());
+        assert_eq!((r.pool_string_index_limit, r.pool_string_index_16_limit), (0, 0));
+
+        let r = open(bundle_with_format([1, 3, 0, 0], &[0x2000_0000, 5, 6, 6, 6, 0]));
+        assert!(!r.no_fallback());
+    }
+
+    #[test]
+    fn pool_string_index_limits_unpack_as_in_icu() {
+        // the limit's low 24 bits above the index count, its top 4 bits in
+        // attribute bits 15..12, and the 16-bit limit in attribute bits 31..16
+        let words = [0x2000_0000, 0x00ab_cd08, 9, 9, 9, 0, 0x1234_5000, 9, 0];
+        let r = open(bundle(&words));
+        assert_eq!(r.pool_string_index_limit, 0x0500_abcd);
+        assert_eq!(r.pool_string_index_16_limit, 0x1234);
+
+        // before format 3, the index count's upper bits aren't the limit
+        let r = open(bundle_with_format([2, 0, 0, 0], &words));
+        assert_eq!(r.pool_string_index_limit, 0x0500_0000);
+        assert_eq!(r.pool_string_index_16_limit, 0x1234);
+
+        // without the attributes, only the index count's bits are there
+        let r = open(bundle(&[0x2000_0000, 0x0000_0105, 6, 6, 6, 0]));
+        assert_eq!(r.pool_string_index_limit, 1);
+        assert_eq!(r.pool_string_index_16_limit, 0);
+    }
+
+    #[test]
+    fn fallback_behavior_follows_the_attributes() {
+        let r = open(small_bundle());
+        assert_eq!(r.fallback_behavior(), FallbackBehavior::Full);
+        let r = open(bundle(&[0x2000_0000, 6, 7, 7, 7, 0, 0]));
+        assert_eq!(r.fallback_behavior(), FallbackBehavior::Full);
+        let r = open(bundle(&[0x2000_0000, 6, 7, 7, 7, 0, 1]));
+        assert_eq!(r.fallback_behavior(), FallbackBehavior::None);
+    }
+
+    #[test]
+    fn from_slice_borrows_the_bytes() {
+        let bytes = small_bundle().into_inner();
+        let mut r = ResourceBundleReader::from_slice(&bytes, DataFormat::ResourceBundle)
+            .expect("Failed to read header");
+        assert_eq!(r.get_by_path("a/1").unwrap(), Some(BundleKey(7)));
+        assert_eq!(r.resource(BundleKey(7)).unwrap(), Resource::String("hi".to_string()));
+        let truncated = &bytes[..40];
+        assert!(ResourceBundleReader::from_slice(truncated, DataFormat::ResourceBundle).is_err());
+    }
+
+    #[test]
+    fn try_from_reads_slices() {
+        let bytes = small_bundle().into_inner();
+        let mut r = ResourceBundleReader::try_from((&bytes[..], DataFormat::ResourceBundle))
+            .expect("Failed to read header");
+        assert_eq!(r.get_by_path("a/1").unwrap(), Some(BundleKey(7)));
+        match ResourceBundleReader::try_from((&bytes[..], DataFormat::Collation)) {
+            Err(IcuError::HeaderAuth) => {}
+            other => panic!("expected HeaderAuth, got {:?}", other.map(|_| ())),
+        }
+        let truncated = &bytes[..40];
+        assert!(ResourceBundleReader::try_from((truncated, DataFormat::ResourceBundle)).is_err());
+    }
+
+    #[test]
+    fn buffered_source_reads_unseekable_streams() {
+        let bytes = small_bundle().into_inner();
+        let source = BufferedSource::read_from(&bytes[..]).unwrap();
+        let mut r = ResourceBundleReader::try_init(source, DataFormat::ResourceBundle)
+            .expect("Failed to read header");
+        let root = r.root_resource();
+        assert_eq!(r.get(root, "b").unwrap(), Some(BundleKey(0x7fff_ffff)));
+
+        let source = BufferedSource::from(&bytes[..]);
+        assert!(ResourceBundleReader::try_init(source, DataFormat::ResourceBundle).is_ok());
+    }
+
+    #[test]
+    fn slice_readers_stay_in_the_slice() {
+        let mut slice = SliceReader::new(Cursor::new(b"xxABCDEyy".to_vec()), 2, 5).unwrap();
+        assert_eq!(slice.len(), 5);
+        let mut bytes = Vec::new();
+        slice.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, b"ABCDE");
+
+        assert_eq!(slice.seek(SeekFrom::Start(1)).unwrap(), 1);
+        let mut two = [0; 2];
+        slice.read_exact(&mut two).unwrap();
+        assert_eq!(&two, b"BC");
+        assert_eq!(slice.seek(SeekFrom::Current(-1)).unwrap(), 2);
+        assert_eq!(slice.seek(SeekFrom::End(-1)).unwrap(), 4);
+        assert!(slice.read_exact(&mut two).is_err());
+        assert_eq!(slice.read(&mut two).unwrap(), 0);
+
+        assert!(slice.seek(SeekFrom::Start(6)).is_err());
+        assert!(slice.seek(SeekFrom::End(1)).is_err());
+        assert!(slice.seek(SeekFrom::Current(-6)).is_err());
+        assert_eq!(slice.stream_position().unwrap(), 5);
+        assert_eq!(slice.into_inner().position(), 7);
+    }
+
+    #[test]
+    fn slice_readers_hold_whole_bundles() {
+        let mut bytes = b"junk".to_vec();
+        bytes.extend(small_bundle().into_inner());
+        bytes.extend_from_slice(b"more junk");
+        let len = bytes.len() as u64 - 4 - 9;
+        let slice = SliceReader::new(Cursor::new(bytes), 4, len).unwrap();
+        let mut r = ResourceBundleReader::try_init(slice, DataFormat::ResourceBundle)
+            .expect("Failed to read header");
+        assert_eq!(r.get_string("a/1").unwrap(), Some("hi".to_string()));
+    }
+
+    #[test]
+    fn from_bytes_reads_owned_bytes() {
+        let bytes = small_bundle().into_inner();
+        let mut r = ResourceBundleReader::from_bytes(bytes, DataFormat::ResourceBundle)
+            .expect("Failed to read header");
+        let root = r.root_resource();
+        assert_eq!(r.get(root, "a").unwrap(), Some(BundleKey(0x8000_000a)));
+    }
+
+    #[test]
+    fn break_iteration_accepts_only_version_4() {
+        assert!(DataFormat::BreakIteration.is_acceptable_version([4, 0, 0, 0]));
+        assert!(!DataFormat::BreakIteration.is_acceptable_version([4, 0, 0, 1]));
+        assert!(!DataFormat::BreakIteration.is_acceptable_version([3, 1, 0, 0]));
+        assert!(!DataFormat::BreakIteration.is_acceptable_version([0, 0, 0, 4]));
+    }
+
+    #[test]
+    fn spoof_requires_major_version_2() {
+        assert!(DataFormat::Spoof.is_acceptable_version([2, 0, 0, 0]));
+        assert!(!DataFormat::Spoof.is_acceptable_version([1, 2, 3, 4]));
+        assert!(!DataFormat::Spoof.is_acceptable_version([3, 0, 0, 0]));
+    }
+
+    #[test]
+    fn get_by_path_descends_tables_and_arrays() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        assert_eq!(r.get_by_path("").unwrap(), Some(root));
+        assert_eq!(r.get_by_path("a").unwrap(), Some(BundleKey(0x8000_000a)));
+        assert_eq!(r.get_by_path("a/0").unwrap(), Some(BundleKey(0x7000_0005)));
+        assert_eq!(r.get_by_path("a/1").unwrap(), Some(BundleKey(7)));
+        assert_eq!(r.get_by_path("/a/1/").unwrap(), Some(BundleKey(7)));
+        assert_eq!(r.get_by_path("b").unwrap(), Some(BundleKey(0x7fff_ffff)));
+    }
+
+    #[test]
+    fn get_by_path_reports_missing_components() {
+        let mut r = open(small_bundle());
+        assert_eq!(r.get_by_path("c").unwrap(), None);
+        assert_eq!(r.get_by_path("a/2").unwrap(), None);
+        assert_eq!(r.get_by_path("a/x").unwrap(), None);
+        assert_eq!(r.get_by_path("b/0").unwrap(), None);
+        assert_eq!(r.get_by_path("a/1/x").unwrap(), None);
+    }
+
+    #[test]
+    fn binary_reads_raw_bytes() {
+        let mut r = open(bundle(&[
+            0x2000_0000, // root: empty table
+            5, 6, 9, 9, 0,
+            5, 0x0102_03ff, 0x8000_0000, // 6: [01 02 03 ff 80]
+        ]));
+        let bytes = vec![0x01, 0x02, 0x03, 0xff, 0x80];
+        assert_eq!(r.binary(BundleKey(0x1000_0006)).unwrap(), bytes);
+        assert_eq!(r.resource(BundleKey(0x1000_0006)).unwrap(), Resource::Binary(bytes));
+        assert!(r.binary(BundleKey(0xe000_0006)).is_err());
     }
-    // format version starts at 16
-    let format_version = [
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-        reader.read_u8()?,
-    ];
-    if !data_format.is_acceptable_version(format_version) {
-        // TODO print data format and format_version bytes with error message
-        return Err(Error::new(ErrorKind::InvalidData, HEADER_CHECK_FAILED));
+
+    #[test]
+    fn binary_rejects_truncated_data() {
+        let mut r = open(bundle(&[0x2000_0000, 5, 6, 8, 8, 0, 9, 0x0102_0304]));
+        assert_eq!(
+            r.binary(BundleKey(0x1000_0006)).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn write_header_round_trips() {
+        for &order in &[Order::BigEndian, Order::LittleEndian] {
+            let mut bytes = Vec::new();
+            let size =
+                write_header(&mut bytes, DataFormat::Spoof, [2, 0, 0, 0], (10, 0, 0, 0), order)
+                    .unwrap();
+            assert_eq!(size, 32);
+            assert_eq!(bytes.len(), 32);
+            assert_eq!(&bytes[12..16], b"Cfu ");
+            let mut reader = wrap_ordered(Cursor::new(bytes)).unwrap();
+            let (size, data_version) = read_header(&mut reader, DataFormat::Spoof).unwrap();
+            assert_eq!(size, 32);
+            assert_eq!(data_version, (10, 0, 0, 0));
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use DataFormat;
-    use ResourceBundleReader;
-    use std::io::Cursor;
-    use version::Version;
     #[test]
-    fn read_header_doesnt_fail() {
-        // header from a real resource bundle
-        let mut c = Cursor::new(vec![
-            0x0, 0x20, 0xda, 0x27,
-            0x0, 0x14, 0x0, 0x0,
-            0x1, 0x0, 0x02, 0x0,
-            0x52, 0x65, 0x73, 0x42,
-            0x03, 0x0, 0x0, 0x0,
-            0x01, 0x04, 0x0, 0x0,
-            0x0, 0x0, 0x0, 0x0,
-            0x0, 0x0, 0x0, 0x0,
-            0x20, 0x0, 0x18, 0x78,
-            0x0, 0xcb, 0x92, 0x08,
-            0x0, 0x0, 0x0, 0x09,
-            0x0, 0x0, 0x18, 0x92,
+    fn try_init_requires_a_table_or_alias_at_the_root() {
+        let format = DataFormat::ResourceBundle;
+        let words = [0x8000_0006, 5, 6, 9, 9, 0, 2, 0x7000_0001, 0x7fff_fffe];
+        match ResourceBundleReader::try_init(bundle(&words), format) {
+            Err(IcuError::Malformed(msg)) => {
+                assert_eq!(msg, "root resource isn't a table or alias")
+            }
+            other => panic!("expected Malformed, got {:?}", other.map(|_| ())),
+        }
+        let words = [0x7000_0005, 5, 6, 6, 6, 0];
+        assert!(ResourceBundleReader::try_init(bundle(&words), format).is_err());
+
+        for &root in &[0x2000_0000, 0x4000_0000, 0x5000_0000] {
+            open(bundle(&[root, 5, 6, 6, 6, 0]));
+        }
+        let r = open(bundle(&[0x3000_0006, 5, 6, 8, 8, 0, 1, 0x0061_0000]));
+        assert_eq!(r.resource_type(r.root_resource()), ResourceType::Alias);
+    }
+
+    #[test]
+    fn try_init_rejects_missing_indexes() {
+        // claims 8 indexes but the data ends after 5
+        let c = bundle(&[0x2000_0000, 8, 7, 7, 7, 0]);
+        match ResourceBundleReader::try_init(c, DataFormat::ResourceBundle) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn try_init_rejects_truncated_keys() {
+        let c = bundle(&[0x2000_0000, 5, 9, 9, 9, 0, 0x6100_6200]);
+        assert!(ResourceBundleReader::try_init(c, DataFormat::ResourceBundle).is_err());
+    }
+
+    #[test]
+    fn detect_format_reads_the_format_id() {
+        assert_eq!(detect_format(&mut small_bundle()).unwrap(), DataFormat::ResourceBundle);
+        let mut bytes = small_bundle().into_inner();
+        bytes[12..16].copy_from_slice(b"Cfu ");
+        assert_eq!(detect_format(&mut Cursor::new(bytes.clone())).unwrap(), DataFormat::Spoof);
+        bytes[12..16].copy_from_slice(b"Nope");
+        let e = detect_format(&mut Cursor::new(bytes)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(detect_format(&mut Cursor::new(vec![0; 32])).is_err());
+    }
+
+    #[test]
+    fn open_any_dispatches_on_the_format() {
+        match open_any(small_bundle()).unwrap() {
+            AnyReader::ResourceBundle(mut r) => {
+                let root = r.root_resource();
+                assert_eq!(r.get(root, "b").unwrap(), Some(BundleKey(0x7fff_ffff)));
+            }
+            _ => panic!("expected a resource bundle"),
+        }
+        let mut bytes = small_bundle().into_inner();
+        bytes[12..16].copy_from_slice(b"Cfu ");
+        bytes[16] = 2;
+        assert!(open_any(Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn dump_writes_derb_style_text() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let mut out = Vec::new();
+        r.dump(root, &mut out).unwrap();
+        let expected = concat!(
+            "{\n",
+            "    a {\n",
+            "        :int { 5 }\n",
+            "        \"hi\",\n",
+            "    }\n",
+            "    b:int { -1 }\n",
+            "}\n",
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut r = open(alias_bundle());
+        let mut out = Vec::new();
+        let b = r.get_by_path("b").unwrap().unwrap();
+        r.dump(b, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), ":alias { \"/LOCALE/a/1\" }\n");
+    }
+
+    #[test]
+    fn dump_escapes_strings() {
+        assert_eq!(quote("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000A\"");
+    }
+
+    #[test]
+    fn dump_rejects_tables_that_contain_themselves() {
+        let mut r = open(bundle(&[
+            0x2000_0007, // root: table at 7
+            5, 7, 9, 9, 1,
+            0x6100_0000, // keys: "a" at byte 24
+            0x0001_0018, 0x2000_0007, // 7: { a: the root table }
+        ]));
+        let root = r.root_resource();
+        let e = r.dump(root, &mut io::sink()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn to_value_decodes_the_whole_tree() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let expected = Value::Table(vec![
+            ("a".to_string(), Value::Array(vec![Value::Int(5), Value::String("hi".into())])),
+            ("b".to_string(), Value::Int(-1)),
         ]);
-        let r = ResourceBundleReader::try_init(&mut c, DataFormat::ResourceBundle)
-            .expect("Failed to read header");
-        assert_eq!(r.version(), Version::Unicode10_0((0x01, 0x04, 0x0, 0x0)));
+        assert_eq!(r.to_value(root).unwrap(), expected);
+
+        let mut r = open(alias_bundle());
+        assert_eq!(r.to_value(BundleKey(0x3000_0013)).unwrap(), Value::String("hi".into()));
+        // c is an alias to itself
+        assert!(r.to_value(r.root_resource()).is_err());
+    }
+
+    #[test]
+    fn to_value_rejects_tables_that_contain_themselves() {
+        let mut r = open(bundle(&[
+            0x2000_0007, // root: table at 7
+            5, 7, 9, 9, 1,
+            0x6100_0000, // keys: "a" at byte 24
+            0x0001_0018, 0x2000_0007, // 7: { a: the root table }
+        ]));
+        let root = r.root_resource();
+        assert_eq!(r.to_value(root).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// A visitor that records what it's shown, one line per call.
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn visit_string(&mut self, key: Option<&str>, value: &str) -> io::Result<()> {
+            self.0.push(format!("{:?} string {}", key, value));
+            Ok(())
+        }
+
+        fn visit_int(&mut self, key: Option<&str>, value: i32) -> io::Result<()> {
+            self.0.push(format!("{:?} int {}", key, value));
+            Ok(())
+        }
+
+        fn visit_table_start(&mut self, key: Option<&str>, len: usize) -> io::Result<()> {
+            self.0.push(format!("{:?} table {}", key, len));
+            Ok(())
+        }
+
+        fn visit_table_end(&mut self) -> io::Result<()> {
+            self.0.push("end table".to_string());
+            Ok(())
+        }
+
+        fn visit_array_start(&mut self, key: Option<&str>, len: usize) -> io::Result<()> {
+            self.0.push(format!("{:?} array {}", key, len));
+            Ok(())
+        }
+
+        fn visit_array_end(&mut self) -> io::Result<()> {
+            self.0.push("end array".to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn accept_visits_depth_first() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let mut recorder = Recorder(Vec::new());
+        r.accept(root, &mut recorder).expect("Failed to walk the bundle");
+        assert_eq!(
+            recorder.0,
+            [
+                "None table 2",
+                "Some(\"a\") array 2",
+                "None int 5",
+                "None string hi",
+                "end array",
+                "Some(\"b\") int -1",
+                "end table",
+            ]
+        );
+
+        let mut r = open(alias_bundle());
+        let b = r.get_by_path("b").unwrap().unwrap();
+        let mut recorder = Recorder(Vec::new());
+        r.accept(b, &mut recorder).unwrap();
+        assert_eq!(recorder.0, ["None string hi"]);
+        let root = r.root_resource();
+        assert!(r.accept(root, &mut Recorder(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn accept_stops_at_the_first_error() {
+        struct Stop;
+        impl Visitor for Stop {
+            fn visit_int(&mut self, _key: Option<&str>, _value: i32) -> io::Result<()> {
+                Err(io::Error::other("stop"))
+            }
+        }
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let e = r.accept(root, &mut Stop).unwrap_err();
+        assert_eq!(e.to_string(), "stop");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn to_value_serializes_to_json() {
+        let mut r = open(small_bundle());
+        let root = r.root_resource();
+        let json = serde_json::to_string(&r.to_value(root).unwrap()).unwrap();
+        assert_eq!(json, r#"{"a":[5,"hi"],"b":-1}"#);
+    }
+
+    #[test]
+    fn any_reader_gives_the_concrete_reader() {
+        let mut any = open_any(small_bundle()).unwrap();
+        assert_eq!(any.format(), DataFormat::ResourceBundle);
+        assert!(any.as_dat().is_none() && any.as_dat_mut().is_none());
+        assert!(any.as_normalized2().is_none() && any.as_character_property().is_none());
+        assert!(any.as_collation().is_none() && any.as_bidi().is_none());
+        assert!(any.as_case().is_none() && any.as_string_prep().is_none());
+        let root = any.as_resource_bundle().unwrap().root_resource();
+        let r = any.as_resource_bundle_mut().unwrap();
+        assert_eq!(r.get(root, "b").unwrap(), Some(BundleKey(0x7fff_ffff)));
+    }
+
+    /// A bundle whose root table `{a:[5,"hi"], b, c}` has aliases for `b`,
+    /// to `/LOCALE/a/1`, and `c`, to itself.
+    fn alias_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_000e, // root: table at 14
+            5, 8, 32, 32, 3,
+            0x6100_6200, 0x6300_0000, // keys: "a" at 24, "b" at 26, "c" at 28
+            2, 0x0068_0069, 0, // 8: "hi"
+            2, 0x7000_0005, 8, // 11: [5, "hi"]
+            0x0003_0018, 0x001a_001c, 0x8000_000b, 0x3000_0013, 0x3000_001a, // 14
+            // 19: "/LOCALE/a/1"
+            11, 0x002f_004c, 0x004f_0043, 0x0041_004c, 0x0045_002f, 0x0061_002f, 0x0031_0000,
+            // 26: "/LOCALE/c"
+            9, 0x002f_004c, 0x004f_0043, 0x0041_004c, 0x0045_002f, 0x0063_0000,
+        ])
+    }
+
+    #[test]
+    fn resolve_alias_reads_the_target() {
+        let mut r = open(alias_bundle());
+        let target = r.resolve_alias(BundleKey(0x3000_0013)).unwrap();
+        assert_eq!(target.package, None);
+        assert_eq!(target.bundle, None);
+        assert_eq!(target.path, "a/1");
+        assert_eq!(
+            r.resource(BundleKey(0x3000_0013)).unwrap(),
+            Resource::Alias("/LOCALE/a/1".to_string())
+        );
+        assert!(r.resolve_alias(BundleKey(8)).is_err());
+    }
+
+    #[test]
+    fn alias_targets_parse_all_forms() {
+        let target = |package: Option<&str>, bundle: Option<&str>, path: &str| AliasTarget {
+            package: package.map(str::to_string),
+            bundle: bundle.map(str::to_string),
+            path: path.to_string(),
+        };
+        assert_eq!(
+            AliasTarget::parse("/ICUDATA/en/calendar/gregorian"),
+            target(Some("ICUDATA"), Some("en"), "calendar/gregorian")
+        );
+        assert_eq!(AliasTarget::parse("/ICUDATA/en"), target(Some("ICUDATA"), Some("en"), ""));
+        assert_eq!(
+            AliasTarget::parse("/LOCALE/calendar/gregorian"),
+            target(None, None, "calendar/gregorian")
+        );
+        assert_eq!(AliasTarget::parse("de/Countries"), target(None, Some("de"), "Countries"));
+        assert_eq!(AliasTarget::parse("de"), target(None, Some("de"), ""));
+    }
+
+    #[test]
+    fn follow_aliases_resolves_same_bundle_aliases() {
+        let mut r = open(alias_bundle());
+        let b = r.get_by_path("b").unwrap().unwrap();
+        assert_eq!(r.follow_aliases(b).unwrap(), Some(BundleKey(8)));
+        assert_eq!(r.follow_aliases(BundleKey(8)).unwrap(), Some(BundleKey(8)));
+        let c = r.get_by_path("c").unwrap().unwrap();
+        assert!(r.follow_aliases(c).is_err());
+    }
+
+    #[test]
+    fn get_string_follows_aliases_and_the_pool() {
+        let mut r = open(alias_bundle());
+        assert_eq!(r.get_string("a/1").unwrap(), Some("hi".to_string()));
+        assert_eq!(r.get_string("b").unwrap(), Some("hi".to_string()));
+        assert_eq!(r.get_string("d").unwrap(), None);
+        assert_eq!(r.get_string("a/2").unwrap(), None);
+        assert!(r.get_string("a/0").is_err());
+        assert!(r.get_string("c").is_err());
+
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        let mut r = open(pooled_bundle()).with_pool(pool).expect("Failed to attach pool");
+        assert_eq!(r.get_string("a").unwrap(), Some("x".to_string()));
+        assert_eq!(r.get_string("p").unwrap(), Some("Pool".to_string()));
+    }
+
+    /// A bundle whose root table is `{ f: "2.5", i: 5, s: "-7", v: [3, 4],
+    /// x: "hi" }`, with `v` an integer vector.
+    fn numbers_bundle() -> Cursor<Vec<u8>> {
+        bundle(&[
+            0x2000_0015, // root: table at 21
+            5, 9, 29, 29, 5,
+            // keys: "f" at 24, "i" at 26, "s" at 28, "v" at 30, "x" at 32
+            0x6600_6900, 0x7300_7600, 0x7800_0000,
+            2, 0x002d_0037, 0, // 9: "-7"
+            3, 0x0032_002e, 0x0035_0000, // 12: "2.5"
+            2, 0x0068_0069, 0, // 15: "hi"
+            2, 3, 4, // 18: [3, 4]
+            0x0005_0018, 0x001a_001c, 0x001e_0020, // 21: { f, i, s, v, x }
+            0x0000_000c, 0x7000_0005, 0x0000_0009, 0xe000_0012, 0x0000_000f,
+        ])
+    }
+
+    #[test]
+    fn get_i32_coerces_ints_vectors_and_strings() {
+        let mut r = open(numbers_bundle());
+        assert_eq!(r.get_i32("i").unwrap(), Some(5));
+        assert_eq!(r.get_i32("v").unwrap(), Some(3));
+        assert_eq!(r.get_i32("s").unwrap(), Some(-7));
+        assert_eq!(r.get_i32("z").unwrap(), None);
+        assert_eq!(r.get_i32("f").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(r.get_i32("x").is_err());
+        assert!(r.get_i32("").is_err());
+
+        let mut r = open(alias_bundle());
+        assert_eq!(r.get_i32("a/0").unwrap(), Some(5));
+    }
+
+    #[test]
+    fn get_string_as_f64_coerces_ints_vectors_and_strings() {
+        let mut r = open(numbers_bundle());
+        assert_eq!(r.get_string_as_f64("f").unwrap(), Some(2.5));
+        assert_eq!(r.get_string_as_f64("i").unwrap(), Some(5.0));
+        assert_eq!(r.get_string_as_f64("v").unwrap(), Some(3.0));
+        assert_eq!(r.get_string_as_f64("s").unwrap(), Some(-7.0));
+        assert_eq!(r.get_string_as_f64("z").unwrap(), None);
+        let e = r.get_string_as_f64("x").unwrap_err();
+        assert_eq!(e.to_string(), "string \"hi\" isn't a number");
+    }
+
+    #[test]
+    fn order_native_matches_the_target() {
+        let native = Order::native();
+        let bytes = 1u16.to_ne_bytes();
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes.to_vec()), native).unwrap();
+        assert_eq!(EndianReader::read_u16(&mut reader).unwrap(), 1);
+        let opposite = native.opposite();
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes.to_vec()), opposite).unwrap();
+        assert_eq!(EndianReader::read_u16(&mut reader).unwrap(), 0x100);
+        let big_endian = match native.opposite().opposite() {
+            Order::BigEndian => true,
+            Order::LittleEndian => false,
+        };
+        assert_eq!(big_endian, cfg!(target_endian = "big"));
+    }
+
+    #[test]
+    fn resource_offsets_count_words_from_the_root_resource() {
+        // the keys end at word 7, and the resources run from there to 17
+        let r = open(small_bundle());
+        assert_eq!(r.resource_region(), 60..100);
+        assert_eq!(r.resource_position(BundleKey(0x0000_0007)), Some(60));
+        assert_eq!(r.resource_position(BundleKey(0x8000_000a)), Some(72));
+        assert_eq!(r.resource_position(r.root_resource()), Some(84));
+        assert_eq!(r.resource_position(BundleKey(0x7fff_ffff)), None);
+        assert_eq!(r.resource_position(BundleKey(0x8000_0000)), None);
+
+        // past 16-bit units from word 10 to 18, the resources run to 22
+        let r = open(table16_bundle());
+        assert_eq!(r.resource_region(), 104..120);
+        assert_eq!(r.resource_position(r.root_resource()), Some(104));
+        assert_eq!(r.resource_position(BundleKey(0x5000_0007)), None);
+        assert_eq!(r.resource_position(BundleKey(0x6000_0001)), None);
+
+        let c = bundle(&[0x2000_0000, 5, 7, 6, 7, 0, 0]);
+        match ResourceBundleReader::try_init(c, DataFormat::ResourceBundle) {
+            Err(IcuError::Malformed(_)) => {}
+            other => panic!("expected Malformed, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn resources_by_offset_lists_stored_resources_in_order() {
+        let mut r = open(small_bundle());
+        assert_eq!(
+            r.resources_by_offset().unwrap(),
+            [
+                (BundleKey(0x0000_0007), 60),
+                (BundleKey(0x8000_000a), 72),
+                (BundleKey(0x2000_000d), 84),
+            ]
+        );
+
+        // the 16-bit strings are in both { a, b } and [4, 1], but listed once
+        let mut r = open(table16_bundle());
+        assert_eq!(
+            r.resources_by_offset().unwrap(),
+            [
+                (BundleKey(0x6000_0001), 74),
+                (BundleKey(0x6000_0004), 80),
+                (BundleKey(0x5000_0007), 86),
+                (BundleKey(0x9000_000c), 96),
+                (BundleKey(0x2000_0012), 104),
+            ]
+        );
+
+        // "Pool" is in the pool bundle, and "x" at 16-bit unit 0 here
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        let mut r = open(pooled_bundle()).with_pool(pool).unwrap();
+        let found = r.resources_by_offset().unwrap();
+        assert_eq!(found, [(BundleKey(0x6000_0006), 72), (BundleKey(0x2000_000b), 76)]);
+
+        // a root table { a: <itself> }
+        let words = [0x2000_0007, 5, 7, 9, 9, 1, 0x6100_0000, 0x0001_0018, 0x2000_0007];
+        let mut r = open(bundle(&words));
+        assert_eq!(r.resources_by_offset().unwrap(), [(BundleKey(0x2000_0007), 60)]);
+    }
+
+    #[test]
+    fn data_size_is_the_bundle_top() {
+        // small_bundle declares a bundle top of 17 words, all of its data
+        let r = open(small_bundle());
+        assert_eq!(r.data_size(), 17 * 4);
+        assert_eq!(u64::from(r.data_size()), r.reader.len() - 32);
+        assert_eq!(open(string_v2_bundle()).data_size(), 24 * 4);
+    }
+
+    #[test]
+    fn max_table_length_bounds_tables() {
+        let mut r = open(small_bundle());
+        assert_eq!(r.max_table_length(), 2);
+        let root = r.root_resource();
+        assert!(r.resource(root).is_ok());
+
+        // the same bundle, claiming its longest table has one entry
+        let mut bytes = small_bundle().into_inner();
+        bytes[32 + 4 * 5 + 3] = 1;
+        let mut r = open(Cursor::new(bytes));
+        assert_eq!(r.max_table_length(), 1);
+        assert!(r.resource(root).is_err());
+        assert!(r.get(root, "a").is_err());
+        assert!(r.table_iter(root).is_err());
+    }
+
+    #[test]
+    fn read_16bit_at_reads_the_16_bit_units() {
+        let mut r = open(string_v2_bundle());
+        assert_eq!(r.units_16_length, 22);
+        assert_eq!(r.read_16bit_at(0).unwrap(), 0);
+        assert_eq!(r.read_16bit_at(1).unwrap(), 0x68);
+        assert_eq!(r.read_16bit_at(21).unwrap(), 0);
+        assert!(r.read_16bit_at(22).is_err());
+        assert_eq!(r.read_16bit_units(1, 2).unwrap(), vec![0x68, 0x69]);
+        assert!(r.read_16bit_units(21, 2).is_err());
+    }
+
+    #[test]
+    fn try_init_rejects_misplaced_16_bit_units() {
+        // the 16-bit top below the keys top, then above the resources top
+        let c = bundle(&[0x2000_0000, 7, 9, 9, 9, 0, 0, 8, 0]);
+        assert!(ResourceBundleReader::try_init(c, DataFormat::ResourceBundle).is_err());
+        let c = bundle(&[0x2000_0000, 7, 8, 9, 9, 0, 0, 10, 0]);
+        assert!(ResourceBundleReader::try_init(c, DataFormat::ResourceBundle).is_err());
+    }
+
+    /// A bundle whose root table is `{ r: ["ho", "hi"], t: { a: "hi",
+    /// b: "ho" } }`, with `r` a `URES_ARRAY16` and `t` a `URES_TABLE16`.
+    fn table16_bundle() -> Cursor<Vec<u8>> {
+        bundle(&table16_words())
+    }
+
+    /// The words of `table16_bundle`, to put behind other format versions.
+    fn table16_words() -> [u32; 22] {
+        [
+            0x2000_0012, // root: table at 18
+            7, 10, 22, 22, 2, 0, 18,
+            0x6100_6200, 0x7200_7400, // keys: "a", "b", "r" and "t" from byte 32
+            // 16-bit units from word 10: 1: "hi", 4: "ho", 7: { a: 1, b: 4 },
+            // 12: [4, 1]
+            0x0000_0068, 0x0069_0000, 0x0068_006f, 0x0000_0002, 0x0020_0022,
+            0x0001_0004, 0x0002_0004, 0x0001_0000,
+            0x0002_0024, 0x0026_0000, 0x9000_000c, 0x5000_0007, // 18: { r, t }
+        ]
+    }
+
+    #[test]
+    fn resource_decodes_16_bit_tables_and_arrays() {
+        let mut r = open(table16_bundle());
+        assert_eq!(
+            r.resource(BundleKey(0x5000_0007)).unwrap(),
+            Resource::Table(vec![(0x20, BundleKey(0x6000_0001)), (0x22, BundleKey(0x6000_0004))])
+        );
+        assert_eq!(
+            r.resource(BundleKey(0x9000_000c)).unwrap(),
+            Resource::Array(vec![BundleKey(0x6000_0004), BundleKey(0x6000_0001)])
+        );
+        assert_eq!(r.string_v2(BundleKey(0x6000_0004)).unwrap(), "ho");
+        assert!(r.resource(BundleKey(0x5000_000e)).is_err());
+    }
+
+    #[test]
+    fn lookups_descend_16_bit_tables_and_arrays() {
+        let mut r = open(table16_bundle());
+        assert_eq!(r.get(BundleKey(0x5000_0007), "b").unwrap(), Some(BundleKey(0x6000_0004)));
+        assert_eq!(r.get(BundleKey(0x5000_0007), "c").unwrap(), None);
+        assert_eq!(r.get_by_path("t/a").unwrap(), Some(BundleKey(0x6000_0001)));
+        assert_eq!(r.get_by_path("r/0").unwrap(), Some(BundleKey(0x6000_0004)));
+        assert_eq!(r.get_by_path("r/2").unwrap(), None);
+        let entries = r
+            .table_iter(BundleKey(0x5000_0007))
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries, vec![("a", BundleKey(0x6000_0001)), ("b", BundleKey(0x6000_0004))]);
+    }
+
+    #[test]
+    fn resource_from_16_separates_pool_and_local_strings() {
+        assert_eq!(resource_from_16(5, 0, 0), BundleKey(0x6000_0005));
+        assert_eq!(resource_from_16(5, 16, 100), BundleKey(0x6000_0005));
+        assert_eq!(resource_from_16(20, 16, 100), BundleKey(0x6000_0068));
+    }
+
+    #[test]
+    fn data_formats_display_their_ids() {
+        assert_eq!(DataFormat::ResourceBundle.to_string(), "ResB");
+        assert_eq!(DataFormat::BreakIteration.to_string(), "Brk ");
+        assert_eq!(DataFormat::Spoof.to_string(), "Cfu ");
+        assert_eq!(DataFormat::Case.to_string(), "cAsE");
+        let e = IcuError::UnsupportedFormatVersion {
+            format: DataFormat::ResourceBundle,
+            version: [2, 1, 0, 0],
+        };
+        assert_eq!(e.to_string(), "ResB format version 2.1.0.0 is not supported");
+    }
+
+    #[test]
+    fn from_signature_inverts_the_ids() {
+        const RES_B: Option<DataFormat> = DataFormat::from_signature(*b"ResB");
+        assert_eq!(RES_B, Some(DataFormat::ResourceBundle));
+        for &format in DATA_FORMATS.iter() {
+            let sig = (format as u32).to_be_bytes();
+            assert_eq!(DataFormat::from_signature(sig), Some(format));
+        }
+        assert_eq!(DataFormat::from_signature(*b"resb"), None);
+        assert_eq!(DataFormat::from_signature([0; 4]), None);
+    }
+
+    #[test]
+    fn data_formats_round_trip_through_their_ids() {
+        for &format in DATA_FORMATS.iter() {
+            let id = <[u8; 4]>::from(format);
+            assert_eq!(id, (format as u32).to_be_bytes());
+            let back = DataFormat::try_from(id).unwrap();
+            assert_eq!(back, format);
+        }
+        let formats: HashSet<DataFormat> = DATA_FORMATS.iter().cloned().collect();
+        assert_eq!(formats.len(), DATA_FORMATS.len());
+        assert!(formats.contains(&DataFormat::Dat));
+        assert_eq!(<[u8; 4]>::from(DataFormat::Dat), *b"CmnD");
+        let e = DataFormat::try_from(*b"Nope").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn compute_pool_checksum_matches_genrb() {
+        assert_eq!(compute_pool_checksum(b""), 0);
+        assert_eq!(compute_pool_checksum(b"123456789"), 0x2dfd_2d88);
+        assert_eq!(compute_pool_checksum(b"p\0q\0r\0\xaa\xaa"), POOL_CHECKSUM);
+
+        let pool = open(pool_bundle(POOL_CHECKSUM));
+        assert_eq!(pool.pool_checksum(), Some(POOL_CHECKSUM));
+        assert_eq!(open(pooled_bundle()).pool_checksum(), Some(POOL_CHECKSUM));
+        assert_eq!(open(small_bundle()).pool_checksum(), None);
+    }
+
+    #[test]
+    fn endian_reader_reads_in_either_order() {
+        let bytes = vec![0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc];
+        let mut be = OrderedReader::wrap(Cursor::new(bytes.clone()), Order::BigEndian).unwrap();
+        let mut le = OrderedReader::wrap(Cursor::new(bytes), Order::LittleEndian).unwrap();
+        assert_eq!((be.len(), le.len()), (6, 6));
+        assert_eq!(EndianReader::read_u16(&mut be).unwrap(), 0x1234);
+        assert_eq!(EndianReader::read_u32(&mut be).unwrap(), 0x5678_9abc);
+        assert_eq!(EndianReader::read_u16(&mut le).unwrap(), 0x3412);
+        assert_eq!(EndianReader::read_u32(&mut le).unwrap(), 0xbc9a_7856);
+        assert_eq!(be.read_u32_from(io::SeekFrom::Start(2)).unwrap(), 0x5678_9abc);
+        assert_eq!(le.read_u16_from(io::SeekFrom::Start(4)).unwrap(), 0xbc9a);
+        assert_eq!(le.read_u8_from(io::SeekFrom::Start(1)).unwrap(), 0x34);
+        assert!(be.read_u32_from(io::SeekFrom::Start(4)).is_err());
+    }
+
+    #[test]
+    fn wrap_finds_the_length_and_keeps_the_position() {
+        let mut bytes = Cursor::new(vec![0; 6]);
+        bytes.set_position(4);
+        let r = OrderedReader::wrap(bytes, Order::BigEndian).unwrap();
+        assert_eq!(r.len(), 6);
+        assert_eq!(r.reader.position(), 4);
+
+        let fixture = small_bundle().into_inner();
+        let r = open(Cursor::new(fixture.clone()));
+        assert_eq!(r.reader.len(), fixture.len() as u64);
+    }
+
+    #[test]
+    fn try_init_names_the_field_cut_short() {
+        let mut bytes = small_bundle().into_inner();
+        bytes.truncate(32 + 4 + 2);
+        let e = ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle)
+            .unwrap_err();
+        match e {
+            IcuError::UnexpectedEof { what: "RES_INDEX_LENGTH", pos: 36 } => {}
+            ref other => panic!("expected UnexpectedEof, got {:?}", other),
+        }
+        assert_eq!(e.to_string(), "unexpected EOF reading RES_INDEX_LENGTH at offset 36");
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut r = OrderedReader::wrap(Cursor::new(vec![0; 6]), Order::BigEndian).unwrap();
+        assert_eq!(r.read_u32_ctx("a").unwrap(), 0);
+        assert_eq!(r.read_u16_ctx("b").unwrap(), 0);
+        let e = IcuError::from(r.read_u16_ctx("c").unwrap_err());
+        assert_eq!(e.to_string(), "unexpected EOF reading c at offset 6");
+    }
+
+    #[test]
+    fn index_slots_locate_the_indexes() {
+        assert_eq!(IndexSlot::Length.offset(32), 36);
+        assert_eq!(IndexSlot::PoolChecksum.offset(32), 64);
+        assert_eq!(IndexSlot::KeysTop.offset(0x80), 0x88);
+        assert!(IndexSlot::MaxTableLength.is_in(5));
+        assert!(!IndexSlot::Attributes.is_in(5));
+        assert!(IndexSlot::PoolChecksum.is_in(8));
+
+        let indexes = [5, 7, 17, 17, 2];
+        assert_eq!(IndexSlot::BundleTop.of(&indexes), 17);
+        assert_eq!(IndexSlot::MaxTableLength.of(&indexes), 2);
+    }
+
+    #[test]
+    fn indexes_are_read_in_one_go() {
+        assert_eq!(open(small_bundle()).indexes(), [5, 7, 17, 17, 2]);
+        let words = [0x2000_0000, 0x00ab_cd08, 9, 9, 9, 0, 0x1234_5000, 9, 0];
+        assert_eq!(open(bundle(&words)).indexes(), &words[1..]);
+        let r = open(little_endian(table16_bundle(), 8..10, &[10..18, 18..20]));
+        assert_eq!(r.indexes(), open(table16_bundle()).indexes());
+
+        let mut reader = wrap_ordered(small_bundle()).unwrap();
+        assert_eq!(reader.read_u32_array(36, 3).unwrap(), [5, 7, 17]);
+        assert!(reader.read_u32_array(0x64, 0).unwrap().is_empty());
+        match reader.read_u32_array(0x60, 2).map_err(IcuError::from) {
+            Err(IcuError::Truncated { pos: 0x60, need: 8, len: 0x64 }) => {}
+            other => panic!("expected Truncated, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn seek_checked_stops_short_of_the_end() {
+        let mut r = OrderedReader::wrap(Cursor::new(vec![0; 6]), Order::BigEndian).unwrap();
+        r.seek_checked(2, 4).unwrap();
+        assert_eq!(r.reader.position(), 2);
+        r.seek_checked(6, 0).unwrap();
+        match r.seek_checked(4, 4).map_err(IcuError::from) {
+            Err(IcuError::Truncated { pos, need, len }) => assert_eq!((pos, need, len), (4, 4, 6)),
+            other => panic!("expected Truncated, got {:?}", other),
+        }
+        assert!(r.seek_checked(u64::MAX, 1).is_err());
+        assert_eq!(r.reader.position(), 6);
+    }
+
+    #[test]
+    fn try_init_rejects_headers_longer_than_the_file() {
+        let mut bytes = small_bundle().into_inner();
+        bytes[1] = 0xff; // a header longer than the file
+        match ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle) {
+            Err(IcuError::HeaderExceedsFile { header_size: 0xff, len: 100 }) => {}
+            other => panic!("expected HeaderExceedsFile, got {:?}", other.map(|_| ())),
+        }
+
+        let mut bytes = small_bundle().into_inner();
+        bytes.truncate(48);
+        bytes[0] = 0x20; // 0x2000 bytes
+        bytes[1] = 0;
+        let e = ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "header of 8192 bytes is longer than the data, which is only 48 bytes"
+        );
+
+        // a header that fills the file is fine as far as the header goes
+        let mut bytes = small_bundle().into_inner();
+        bytes[1] = 100;
+        let r = ResourceBundleReader::try_init(Cursor::new(bytes), DataFormat::ResourceBundle);
+        assert!(!matches!(r, Err(IcuError::HeaderExceedsFile { .. })));
+    }
+
+    #[test]
+    fn try_init_rejects_files_smaller_than_a_header() {
+        let bytes = small_bundle().into_inner();
+        match ResourceBundleReader::try_init(Cursor::new(&bytes[..10]), DataFormat::ResourceBundle)
+        {
+            Err(IcuError::TooSmall { len: 10 }) => {}
+            other => panic!("expected TooSmall, got {:?}", other.map(|_| ())),
+        }
+        let e = detect_format(&mut Cursor::new(&bytes[..31])).unwrap_err();
+        assert_eq!(IcuError::from(e).to_string(), "data too small for a header: 31 bytes");
+    }
+
+    #[test]
+    fn endian_reader_reads_signed_values() {
+        let bytes = vec![
+            0xff, 0xfe, 0x80, 0x00, 0x00, 0x01, // i16: -2, i16::MIN, 1
+            0xff, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, // i32: -1, i32::MAX
+            0x70, 0x00, 0x00, 0x05, 0x7f, 0xff, 0xff, 0xff, // int28: 5, -1
+            0x77, 0xff, 0xff, 0xff, 0x78, 0x00, 0x00, 0x00, // int28: max, min
+        ];
+        let mut r = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert_eq!(r.read_i16().unwrap(), -2);
+        assert_eq!(r.read_i16().unwrap(), i16::MIN);
+        assert_eq!(r.read_i16().unwrap(), 1);
+        assert_eq!(r.read_i32().unwrap(), -1);
+        assert_eq!(r.read_i32().unwrap(), i32::MAX);
+        assert_eq!(r.read_int28().unwrap(), 5);
+        assert_eq!(r.read_int28().unwrap(), -1);
+        assert_eq!(r.read_int28().unwrap(), 0x07ff_ffff);
+        assert_eq!(r.read_int28().unwrap(), -0x0800_0000);
+    }
+
+    #[test]
+    fn int_sign_extends_the_resource_word() {
+        let mut r = open(small_bundle());
+        assert_eq!(r.int(BundleKey(0x7000_0005)).unwrap(), 5);
+        assert_eq!(r.int(BundleKey(0x7000_0000)).unwrap(), 0);
+        assert_eq!(r.int(BundleKey(0x7fff_ffff)).unwrap(), -1);
+        assert_eq!(r.int(BundleKey(0x7800_0000)).unwrap(), -0x0800_0000);
+        assert!(r.int(BundleKey(0x0000_0005)).is_err());
+    }
+
+    #[test]
+    fn resource_type_reads_the_type_tag() {
+        let r = open(small_bundle());
+        let types = [
+            ResourceType::String,
+            ResourceType::Binary,
+            ResourceType::Table,
+            ResourceType::Alias,
+            ResourceType::Table32,
+            ResourceType::Table16,
+            ResourceType::StringV2,
+            ResourceType::Int,
+            ResourceType::Array,
+            ResourceType::Array16,
+            ResourceType::Reserved,
+            ResourceType::Reserved,
+            ResourceType::Reserved,
+            ResourceType::Reserved,
+            ResourceType::IntVector,
+            ResourceType::Reserved,
+        ];
+        for (tag, &expected) in types.iter().enumerate() {
+            assert_eq!(r.resource_type(BundleKey((tag as u32) << 28 | 0x123)), expected);
+        }
+        assert_eq!(r.resource_type(BundleKey(0)), ResourceType::String);
+        assert_eq!(r.resource_type(r.root_resource()), ResourceType::Table);
+    }
+
+    #[test]
+    fn empty_resources_dont_touch_the_reader() {
+        use std::io::{Seek, SeekFrom};
+        let mut r = open(table16_bundle());
+        r.reader.seek(SeekFrom::Start(7)).unwrap();
+        let empty_table = Resource::Table(Vec::new());
+        let empty_array = Resource::Array(Vec::new());
+        assert_eq!(r.resource(BundleKey(0)).unwrap(), Resource::String(String::new()));
+        assert_eq!(r.resource(BundleKey(0x6000_0000)).unwrap(), Resource::String(String::new()));
+        assert_eq!(r.resource(BundleKey(0x1000_0000)).unwrap(), Resource::Binary(Vec::new()));
+        assert_eq!(r.resource(BundleKey(0xe000_0000)).unwrap(), Resource::IntVector(Vec::new()));
+        assert_eq!(r.resource(BundleKey(0x2000_0000)).unwrap(), empty_table);
+        assert_eq!(r.resource(BundleKey(0x5000_0000)).unwrap(), empty_table);
+        assert_eq!(r.resource(BundleKey(0x8000_0000)).unwrap(), empty_array);
+        assert_eq!(r.resource(BundleKey(0x9000_0000)).unwrap(), empty_array);
+        assert_eq!(r.get(BundleKey(0x4000_0000), "a").unwrap(), None);
+        assert_eq!(r.table_iter(BundleKey(0x4000_0000)).unwrap().count(), 0);
+        assert_eq!(r.reader.reader.position(), 7);
+    }
+
+    #[test]
+    fn fallback_chain_truncates_subtags() {
+        assert_eq!(fallback_chain("sr_Latn_RS"), vec!["sr_Latn_RS", "sr_Latn", "sr", "root"]);
+        assert_eq!(fallback_chain("de_DE"), vec!["de_DE", "de", "root"]);
+        assert_eq!(fallback_chain("en__POSIX"), vec!["en__POSIX", "en", "root"]);
+        assert_eq!(fallback_chain("root"), vec!["root"]);
+        assert_eq!(fallback_chain(""), vec!["root"]);
+    }
+
+    #[test]
+    fn fallback_chain_with_goes_through_explicit_parents() {
+        let parents = |id: &str| match id {
+            "es_MX" => Some("es_419".to_string()),
+            "pt_AO" => Some("pt_PT".to_string()),
+            "xx" => Some("yy".to_string()),
+            "yy" => Some("xx".to_string()),
+            _ => None,
+        };
+        assert_eq!(fallback_chain_with("es_MX", parents), ["es_MX", "es_419", "es", "root"]);
+        assert_eq!(fallback_chain_with("pt_AO", parents), ["pt_AO", "pt_PT", "pt", "root"]);
+        assert_eq!(fallback_chain_with("de_DE", parents), ["de_DE", "de", "root"]);
+        assert_eq!(fallback_chain_with("xx_XX", parents), ["xx_XX", "xx", "yy", "root"]);
+
+        let mut es_mx = open(one_string_bundle("%%Parent", "es_419"));
+        assert_eq!(es_mx.explicit_parent().unwrap(), Some("es_419".to_string()));
+        let chain = fallback_chain_with("es_MX", |id| match id {
+            "es_MX" => es_mx.explicit_parent().unwrap(),
+            _ => None,
+        });
+        assert_eq!(chain, ["es_MX", "es_419", "es", "root"]);
+    }
+
+    #[test]
+    fn locale_alias_and_explicit_parent_read_the_root_table() {
+        let mut r = open(one_string_bundle("%%ALIAS", "sr_Cyrl_RS"));
+        assert_eq!(r.locale_alias().unwrap(), Some("sr_Cyrl_RS".to_string()));
+        assert_eq!(r.explicit_parent().unwrap(), None);
+
+        let mut r = open(one_string_bundle("%%Parent", "es_419"));
+        assert_eq!(r.locale_alias().unwrap(), None);
+        assert_eq!(r.explicit_parent().unwrap(), Some("es_419".to_string()));
+
+        let mut r = open(small_bundle());
+        assert_eq!(r.locale_alias().unwrap(), None);
+        assert_eq!(r.explicit_parent().unwrap(), None);
+    }
+
+    /// A bundle whose root table holds the one string `key: value`.
+    fn one_string_bundle(key: &str, value: &str) -> Cursor<Vec<u8>> {
+        let mut keys = key.as_bytes().to_vec();
+        keys.push(0);
+        while keys.len() & 3 != 0 {
+            keys.push(0xaa);
+        }
+        let mut units: Vec<u16> = value.encode_utf16().collect();
+        let length = units.len() as u32;
+        units.push(0);
+        if units.len() & 1 != 0 {
+            units.push(0);
+        }
+        let string = 6 + keys.len() as u32 / 4;
+        let table = string + 1 + units.len() as u32 / 2;
+        let top = table + 2;
+        let mut words = vec![0x2000_0000 | table, 5, string, top, top, 1];
+        words.extend(keys.chunks(4).map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]])));
+        words.push(length);
+        words.extend(units.chunks(2).map(|u| u32::from(u[0]) << 16 | u32::from(u[1])));
+        words.extend_from_slice(&[0x0001_0018, string]);
+        bundle(&words)
+    }
+
+    #[test]
+    fn get_with_fallback_searches_the_chain() {
+        let empty = || open(bundle(&[0x2000_0000, 5, 6, 6, 6, 0]));
+        let mut chain = vec![empty(), open(small_bundle()), open(small_bundle())];
+        let found = ResourceBundleReader::get_with_fallback(&mut chain, "a/1").unwrap();
+        assert_eq!(found, Some((1, BundleKey(7))));
+        let found = ResourceBundleReader::get_with_fallback(&mut chain[2..], "b").unwrap();
+        assert_eq!(found, Some((0, BundleKey(0x7fff_ffff))));
+        let found = ResourceBundleReader::get_with_fallback(&mut chain, "c").unwrap();
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn get_with_fallback_stops_at_no_fallback_bundles() {
+        let mut chain = vec![open(bundle(&[0x2000_0000, 6, 7, 7, 7, 0, 1])), open(small_bundle())];
+        let found = ResourceBundleReader::get_with_fallback(&mut chain, "a").unwrap();
+        assert_eq!(found, None);
+        let found = ResourceBundleReader::get_with_fallback(&mut chain[1..], "a").unwrap();
+        assert_eq!(found, Some((0, BundleKey(0x8000_000a))));
     }
 }
@@ -0,0 +1,210 @@
+//! Normalization data ("Nrm2"), as in `nfc.nrm` and `nfkc.nrm`.
+
+use std::convert::TryFrom;
+use std::io::prelude::*;
+use error::IcuError;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots, counted in 32-bit words from the start of the data
+const IX_NORM_TRIE_OFFSET: usize = 0;
+const IX_EXTRA_DATA_OFFSET: usize = 1;
+const IX_SMALL_FYI_OFFSET: usize = 2;
+const IX_TOTAL_SIZE: usize = 7;
+const IX_MIN_DECOMP_NO_CP: usize = 8;
+const IX_MIN_COMP_NO_MAYBE_CP: usize = 9;
+const IX_MIN_YES_NO: usize = 10;
+const IX_MIN_NO_NO: usize = 11;
+const IX_LIMIT_NO_NO: usize = 12;
+const IX_MIN_MAYBE_YES: usize = 13;
+const IX_MIN_YES_NO_MAPPINGS_ONLY: usize = 14;
+const IX_MIN_NO_NO_COMP_BOUNDARY_BEFORE: usize = 15;
+const IX_MIN_NO_NO_COMP_NO_MAYBE_CC: usize = 16;
+const IX_MIN_NO_NO_EMPTY: usize = 17;
+const IX_MIN_LCCC_CP: usize = 18;
+
+/// The header of a normalization data file.
+///
+/// The data starts with an `indexes[]` array whose first entry is the byte
+/// offset of the normalization trie, and so also the array's size. The
+/// offsets below count bytes from the start of the data, after the ICU
+/// header. The `min_*` and `limit_*` thresholds split the 16-bit trie
+/// values ("norm16") into their yes/no/maybe ranges.
+#[derive(Clone, Debug)]
+pub struct Normalizer2Data<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    pub norm_trie_offset: u32,
+    pub extra_data_offset: u32,
+    pub small_fyi_offset: u32,
+    pub total_size: u32,
+    /// Code points below this one decompose to themselves.
+    pub min_decomp_no_cp: u32,
+    /// Code points below this one are composition "yes".
+    pub min_comp_no_maybe_cp: u32,
+    pub min_yes_no: u16,
+    pub min_no_no: u16,
+    pub limit_no_no: u16,
+    pub min_maybe_yes: u16,
+    pub min_yes_no_mappings_only: u16,
+    pub min_no_no_comp_boundary_before: u16,
+    pub min_no_no_comp_no_maybe_cc: u16,
+    pub min_no_no_empty: u16,
+    /// Code points below this one have a lead combining class of 0.
+    pub min_lccc_cp: u32,
+}
+
+impl<R> Normalizer2Data<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<Normalizer2Data<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::Normalized2)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4)?;
+        let trie_offset = EndianReader::read_u32(&mut reader)?;
+        let count = trie_offset as usize / 4;
+        if count <= IX_MIN_LCCC_CP {
+            return Err(IcuError::NotEnoughIndexes);
+        }
+        if base + u64::from(trie_offset) > len {
+            return Err(IcuError::Malformed("indexes run past the end of the data"));
+        }
+        let mut indexes = Vec::with_capacity(count);
+        indexes.push(trie_offset);
+        for _ in 1..count {
+            indexes.push(EndianReader::read_u32(&mut reader)?);
+        }
+
+        let offsets = [
+            indexes[IX_NORM_TRIE_OFFSET],
+            indexes[IX_EXTRA_DATA_OFFSET],
+            indexes[IX_SMALL_FYI_OFFSET],
+            indexes[IX_TOTAL_SIZE],
+        ];
+        if offsets.windows(2).any(|w| w[0] > w[1]) {
+            return Err(IcuError::Malformed("data offsets out of order"));
+        }
+        if base + u64::from(indexes[IX_TOTAL_SIZE]) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        Ok(Normalizer2Data {
+            reader,
+            header_size,
+            data_version,
+            norm_trie_offset: indexes[IX_NORM_TRIE_OFFSET],
+            extra_data_offset: indexes[IX_EXTRA_DATA_OFFSET],
+            small_fyi_offset: indexes[IX_SMALL_FYI_OFFSET],
+            total_size: indexes[IX_TOTAL_SIZE],
+            min_decomp_no_cp: indexes[IX_MIN_DECOMP_NO_CP],
+            min_comp_no_maybe_cp: indexes[IX_MIN_COMP_NO_MAYBE_CP],
+            min_yes_no: indexes[IX_MIN_YES_NO] as u16,
+            min_no_no: indexes[IX_MIN_NO_NO] as u16,
+            limit_no_no: indexes[IX_LIMIT_NO_NO] as u16,
+            min_maybe_yes: indexes[IX_MIN_MAYBE_YES] as u16,
+            min_yes_no_mappings_only: indexes[IX_MIN_YES_NO_MAPPINGS_ONLY] as u16,
+            min_no_no_comp_boundary_before: indexes[IX_MIN_NO_NO_COMP_BOUNDARY_BEFORE] as u16,
+            min_no_no_comp_no_maybe_cc: indexes[IX_MIN_NO_NO_COMP_NO_MAYBE_CC] as u16,
+            min_no_no_empty: indexes[IX_MIN_NO_NO_EMPTY] as u16,
+            min_lccc_cp: indexes[IX_MIN_LCCC_CP],
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::IcuError;
+    use nfc::Normalizer2Data;
+    use std::io::Cursor;
+    use version::Version;
+    use {write_header, DataFormat, Order};
+
+    /// A big-endian Nrm2 header for Unicode 10, followed by `indexes`.
+    fn nrm2(indexes: &[u32]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::Normalized2;
+        write_header(&mut bytes, format, [3, 0, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        for index in indexes {
+            bytes.extend_from_slice(&[
+                (index >> 24) as u8,
+                (index >> 16) as u8,
+                (index >> 8) as u8,
+                *index as u8,
+            ]);
+        }
+        Cursor::new(bytes)
+    }
+
+    fn indexes() -> Vec<u32> {
+        vec![
+            80, 80, 80, 80, 80, 80, 80, 80, // offsets, with no trie or extra data
+            0xc0, 0x300, 0x2, 0x4, 0x6, 0xfc00, 0x3, 0x5, 0x5, 0x6, 0x300, 0,
+        ]
+    }
+
+    #[test]
+    fn try_init_reads_indexes() {
+        let n = Normalizer2Data::try_init(nrm2(&indexes())).expect("Failed to read Nrm2");
+        assert_eq!(n.version(), Some(Version::Unicode10_0((10, 0, 0, 0))));
+        assert_eq!(n.norm_trie_offset, 80);
+        assert_eq!(n.total_size, 80);
+        assert_eq!(n.min_decomp_no_cp, 0xc0);
+        assert_eq!(n.min_comp_no_maybe_cp, 0x300);
+        assert_eq!(
+            (n.min_yes_no, n.min_no_no, n.limit_no_no, n.min_maybe_yes),
+            (2, 4, 6, 0xfc00)
+        );
+        assert_eq!(n.min_yes_no_mappings_only, 3);
+        assert_eq!(n.min_no_no_comp_boundary_before, 5);
+        assert_eq!(n.min_no_no_comp_no_maybe_cc, 5);
+        assert_eq!(n.min_no_no_empty, 6);
+        assert_eq!(n.min_lccc_cp, 0x300);
+    }
+
+    #[test]
+    fn try_init_rejects_short_indexes() {
+        let mut short = indexes();
+        short.truncate(18);
+        short[0] = 72;
+        match Normalizer2Data::try_init(nrm2(&short)) {
+            Err(IcuError::NotEnoughIndexes) => {}
+            other => panic!("expected NotEnoughIndexes, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn try_init_rejects_bad_offsets() {
+        let mut bad = indexes();
+        bad[1] = 96; // extra data past the total size
+        assert!(Normalizer2Data::try_init(nrm2(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[7] = 84; // total size past the end of the file
+        assert!(Normalizer2Data::try_init(nrm2(&bad)).is_err());
+    }
+
+    #[test]
+    fn try_init_rejects_other_versions() {
+        let mut bytes = nrm2(&indexes()).into_inner();
+        bytes[16] = 2;
+        assert!(Normalizer2Data::try_init(Cursor::new(bytes)).is_err());
+    }
+}
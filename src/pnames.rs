@@ -0,0 +1,357 @@
+//! Property and property value aliases ("pnam"), as in `pnames.icu`, which
+//! name the Unicode properties and their values.
+
+use std::io::{self, SeekFrom, prelude::*};
+use error::IcuError;
+use version::PiecewiseVersion;
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+// indexes[] slots, byte offsets from the start of the data
+const IX_VALUE_MAPS_OFFSET: usize = 0;
+const IX_BYTE_TRIES_OFFSET: usize = 1;
+const IX_NAME_GROUPS_OFFSET: usize = 2;
+const IX_RESERVED3_OFFSET: usize = 3;
+const IX_COUNT: usize = 8;
+
+/// A value map's count at or above this is a list of values rather than
+/// of ranges, offset by this much.
+const VALUE_LIST_BASE: u32 = 0x10;
+
+/// Which of a property's or value's names to look up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameChoice {
+    /// The short name, as in "gc" or "Lu".
+    Short,
+    /// The long name, as in "General_Category" or "Uppercase_Letter".
+    Long,
+}
+
+/// The property and value names of a property alias data file.
+///
+/// The data starts with eight indexes holding the byte offsets of its
+/// sections: the value maps, the byte tries that map names to numbers, and
+/// the name groups. The value maps start with a map from property numbers,
+/// in ranges, to each property's name group and value map. A value map
+/// maps the property's values to their name groups, either in ranges or as
+/// a sorted list. A name group is a count of names followed by that many
+/// NUL-terminated names: the short name, which may be empty, the long name
+/// and any other aliases.
+///
+/// Names are matched by walking the name groups rather than through the
+/// byte tries.
+#[derive(Clone, Debug)]
+pub struct PropertyAliasData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    value_maps: Vec<u32>,
+    byte_tries: Vec<u8>,
+    name_groups: Vec<u8>,
+}
+
+impl<R> PropertyAliasData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<PropertyAliasData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::PropertyAlias)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+
+        reader.seek_checked(base, 4 * IX_COUNT as u64)?;
+        let mut indexes = [0; IX_COUNT];
+        for index in indexes.iter_mut() {
+            *index = EndianReader::read_u32(&mut reader)?;
+        }
+        let value_maps_offset = indexes[IX_VALUE_MAPS_OFFSET];
+        let byte_tries_offset = indexes[IX_BYTE_TRIES_OFFSET];
+        let name_groups_offset = indexes[IX_NAME_GROUPS_OFFSET];
+        let end = indexes[IX_RESERVED3_OFFSET];
+        if value_maps_offset < 4 * IX_COUNT as u32
+            || byte_tries_offset < value_maps_offset
+            || name_groups_offset < byte_tries_offset
+            || end < name_groups_offset
+            || (byte_tries_offset - value_maps_offset) % 4 != 0
+        {
+            return Err(IcuError::Malformed("bad section offsets"));
+        }
+        if base + u64::from(end) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        reader.seek(SeekFrom::Start(base + u64::from(value_maps_offset)))?;
+        let count = (byte_tries_offset - value_maps_offset) / 4;
+        let mut value_maps = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            value_maps.push(EndianReader::read_u32(&mut reader)?);
+        }
+        let byte_tries = read_bytes(&mut reader, name_groups_offset - byte_tries_offset)?;
+        let name_groups = read_bytes(&mut reader, end - name_groups_offset)?;
+        if value_maps.is_empty() {
+            return Err(IcuError::Malformed("no property map"));
+        }
+
+        Ok(PropertyAliasData {
+            reader,
+            header_size,
+            data_version,
+            value_maps,
+            byte_tries,
+            name_groups,
+        })
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The number of the property named `name`, as ICU's `UProperty`
+    /// numbers it.
+    ///
+    /// Names match as ICU matches them: ignoring ASCII case, `-`, `_` and
+    /// whitespace, so `"gc"`, `"General_Category"` and `"general category"`
+    /// all name the same property.
+    pub fn property_for_name(&self, name: &str) -> Option<u32> {
+        self.properties()
+            .find(|&(_, i)| self.group_has_name(self.value_maps[i], name))
+            .map(|(property, _)| property)
+    }
+
+    /// The `choice` name of `property`, or none if it has no such name.
+    pub fn property_name(&self, property: u32, choice: NameChoice) -> Option<&str> {
+        let i = self.find_property(property)?;
+        self.name(self.value_maps[i], choice)
+    }
+
+    /// The number of the value named `name` of `property`, like
+    /// `property_for_name`.
+    pub fn value_for_name(&self, property: u32, name: &str) -> Option<u32> {
+        let i = self.find_property(property)?;
+        self.values(*self.value_maps.get(i + 1)?)
+            .find(|&(_, group)| self.group_has_name(group, name))
+            .map(|(value, _)| value)
+    }
+
+    /// The `choice` name of `value` of `property`, or none if it has no such
+    /// name.
+    pub fn value_name(&self, property: u32, value: u32, choice: NameChoice) -> Option<&str> {
+        let i = self.find_property(property)?;
+        let (_, group) = self
+            .values(*self.value_maps.get(i + 1)?)
+            .find(|&(v, _)| v == value)?;
+        self.name(group, choice)
+    }
+
+    /// Each property's number and the index of its entry in the property
+    /// map: its name group's offset, then its value map's index.
+    fn properties(&self) -> impl Iterator<Item = (u32, usize)> {
+        let maps = &self.value_maps;
+        let mut i = 1;
+        let mut properties = Vec::new();
+        for _ in 0..maps[0] {
+            let (start, limit) = match maps.get(i..i + 2) {
+                Some(&[start, limit]) => (start, limit),
+                _ => break,
+            };
+            i += 2;
+            for property in start..limit {
+                if i + 1 >= maps.len() {
+                    break;
+                }
+                properties.push((property, i));
+                i += 2;
+            }
+        }
+        properties.into_iter()
+    }
+
+    fn find_property(&self, property: u32) -> Option<usize> {
+        self.properties().find(|&(p, _)| p == property).map(|(_, i)| i)
+    }
+
+    /// Each value and its name group's offset, from the value map at `map`,
+    /// which starts with the offset of its byte trie. Index 0 is no map.
+    fn values(&self, map: u32) -> impl Iterator<Item = (u32, u32)> {
+        let maps = &self.value_maps;
+        let mut values = Vec::new();
+        let mut i = map as usize + 1;
+        let count = match map {
+            0 => 0,
+            _ => maps.get(i).cloned().unwrap_or(0),
+        };
+        i += 1;
+        if count < VALUE_LIST_BASE {
+            for _ in 0..count {
+                let (start, limit) = match maps.get(i..i + 2) {
+                    Some(&[start, limit]) => (start, limit),
+                    _ => break,
+                };
+                i += 2;
+                for value in start..limit {
+                    match maps.get(i) {
+                        Some(&group) => values.push((value, group)),
+                        None => break,
+                    }
+                    i += 1;
+                }
+            }
+        } else {
+            let count = (count - VALUE_LIST_BASE) as usize;
+            let groups = maps.get(i + count..i + 2 * count).unwrap_or(&[]);
+            values.extend(maps[i..].iter().cloned().zip(groups.iter().cloned()));
+        }
+        values.into_iter()
+    }
+
+    /// The names in the group at `offset`.
+    fn names(&self, offset: u32) -> impl Iterator<Item = &str> {
+        let group = self.name_groups.get(offset as usize..).unwrap_or(&[]);
+        let count = group.first().map_or(0, |&count| usize::from(count));
+        group
+            .get(1..)
+            .unwrap_or(&[])
+            .split(|&b| b == 0)
+            .take(count)
+            .map(|name| ::std::str::from_utf8(name).unwrap_or(""))
+    }
+
+    /// The `choice` name in the group at `offset`. Offset 0 is no group,
+    /// and empty names are missing ones.
+    fn name(&self, offset: u32, choice: NameChoice) -> Option<&str> {
+        if offset == 0 {
+            return None;
+        }
+        let index = match choice {
+            NameChoice::Short => 0,
+            NameChoice::Long => 1,
+        };
+        self.names(offset).nth(index).filter(|name| !name.is_empty())
+    }
+
+    fn group_has_name(&self, offset: u32, name: &str) -> bool {
+        offset != 0 && self.names(offset).any(|alias| !alias.is_empty() && loose_eq(alias, name))
+    }
+}
+
+fn read_bytes<R>(reader: &mut OrderedReader<R>, count: u32) -> io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let mut bytes = vec![0; count as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Compares property names as ICU does, ignoring ASCII case, `-`, `_` and
+/// whitespace.
+fn loose_eq(a: &str, b: &str) -> bool {
+    let loose = |s: &str| {
+        s.bytes()
+            .filter(|&b| b != b'-' && b != b'_' && !b.is_ascii_whitespace() && b != 0x0b)
+            .map(|b| b.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    loose(a) == loose(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use pnames::{NameChoice, PropertyAliasData};
+    use std::io::Cursor;
+    use {write_header, DataFormat, Order};
+
+    const ALPHABETIC: u32 = 0;
+    const GENERAL_CATEGORY: u32 = 0x1005;
+
+    /// A big-endian file naming the binary property Alphabetic, with its
+    /// values in a range, and General_Category, with its first three values
+    /// in a list.
+    fn pnames() -> Cursor<Vec<u8>> {
+        let mut groups = vec![0];
+        let mut group = |names: &[&str]| {
+            let offset = groups.len() as u32;
+            groups.push(names.len() as u8);
+            for name in names {
+                groups.extend_from_slice(name.as_bytes());
+                groups.push(0);
+            }
+            offset
+        };
+        let alpha = group(&["Alpha", "Alphabetic"]);
+        let gc = group(&["gc", "General_Category"]);
+        let no = group(&["N", "No", "F", "False"]);
+        let yes = group(&["Y", "Yes", "T", "True"]);
+        let cn = group(&["Cn", "Unassigned"]);
+        let lu = group(&["Lu", "Uppercase_Letter"]);
+        let ll = group(&["", "Lowercase_Letter"]);
+
+        let value_maps = [
+            2, // property ranges
+            ALPHABETIC, ALPHABETIC + 1, alpha, 9,
+            GENERAL_CATEGORY, GENERAL_CATEGORY + 1, gc, 15,
+            0, 1, 0, 2, no, yes, // Alphabetic: one range
+            0, 0x13, 0, 1, 2, cn, lu, ll, // General_Category: three values
+        ];
+
+        let maps_size = 4 * value_maps.len() as u32;
+        let indexes = [32, 32 + maps_size, 32 + maps_size, 32 + maps_size + groups.len() as u32];
+        let mut bytes = Vec::new();
+        let format = DataFormat::PropertyAlias;
+        write_header(&mut bytes, format, [2, 0, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        for word in indexes.iter().chain(&[0; 4]).chain(&value_maps) {
+            bytes.extend_from_slice(&[
+                (word >> 24) as u8,
+                (word >> 16) as u8,
+                (word >> 8) as u8,
+                *word as u8,
+            ]);
+        }
+        bytes.extend(groups);
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn properties_map_to_and_from_names() {
+        let p = PropertyAliasData::try_init(pnames()).expect("Failed to read names");
+        assert_eq!(p.property_for_name("gc"), Some(GENERAL_CATEGORY));
+        assert_eq!(p.property_for_name("General_Category"), Some(GENERAL_CATEGORY));
+        assert_eq!(p.property_for_name("general category"), Some(GENERAL_CATEGORY));
+        assert_eq!(p.property_for_name("ALPHA"), Some(ALPHABETIC));
+        assert_eq!(p.property_for_name("Script"), None);
+        assert_eq!(p.property_for_name(""), None);
+        assert_eq!(p.property_name(GENERAL_CATEGORY, NameChoice::Short), Some("gc"));
+        assert_eq!(p.property_name(GENERAL_CATEGORY, NameChoice::Long), Some("General_Category"));
+        assert_eq!(p.property_name(1, NameChoice::Long), None);
+    }
+
+    #[test]
+    fn values_map_to_and_from_names() {
+        let p = PropertyAliasData::try_init(pnames()).expect("Failed to read names");
+        assert_eq!(p.value_for_name(GENERAL_CATEGORY, "Lu"), Some(1));
+        assert_eq!(p.value_for_name(GENERAL_CATEGORY, "lowercase-letter"), Some(2));
+        assert_eq!(p.value_for_name(GENERAL_CATEGORY, "Y"), None);
+        assert_eq!(p.value_for_name(ALPHABETIC, "True"), Some(1));
+        assert_eq!(p.value_for_name(ALPHABETIC, "n"), Some(0));
+        assert_eq!(p.value_name(GENERAL_CATEGORY, 1, NameChoice::Long), Some("Uppercase_Letter"));
+        assert_eq!(p.value_name(GENERAL_CATEGORY, 2, NameChoice::Short), None);
+        assert_eq!(p.value_name(GENERAL_CATEGORY, 3, NameChoice::Short), None);
+        assert_eq!(p.value_name(ALPHABETIC, 1, NameChoice::Short), Some("Y"));
+    }
+
+    #[test]
+    fn try_init_rejects_bad_offsets() {
+        let mut bytes = pnames().into_inner();
+        bytes[32 + 7] = 33; // value maps not in whole words
+        assert!(PropertyAliasData::try_init(Cursor::new(bytes)).is_err());
+
+        let mut bytes = pnames().into_inner();
+        let len = bytes.len();
+        bytes.truncate(len - 1);
+        assert!(PropertyAliasData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
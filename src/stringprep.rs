@@ -0,0 +1,245 @@
+//! StringPrep profile data ("SPRP"), as in `rfc3491.spp` for nameprep and
+//! the other RFC 3454 profiles.
+
+use std::convert::TryFrom;
+use std::io::{SeekFrom, prelude::*};
+use error::IcuError;
+use trie::Trie1;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+const INDEX_COUNT: usize = 16;
+
+// indexes[] slots
+const INDEX_TRIE_SIZE: usize = 0;
+const INDEX_MAPPING_DATA_SIZE: usize = 1;
+const NORM_CORRECTNS_LAST_UNI_VERSION: usize = 2;
+const ONE_UCHAR_MAPPING_INDEX_START: usize = 3;
+const OPTIONS: usize = 7;
+
+// OPTIONS bits
+const NORMALIZATION_ON: u32 = 1;
+const CHECK_BIDI_ON: u32 = 2;
+
+/// The indexes and mappings of a StringPrep profile.
+///
+/// The data starts with sixteen 32-bit indexes, followed by the trie that
+/// maps code points to their type (unassigned, mapped, prohibited or
+/// deleted) or a mapping index, and then the mappings, 16-bit units the
+/// mapping indexes point into.
+///
+/// The trie is a UTrie, the format UTrie2 replaced.
+#[derive(Clone, Debug)]
+pub struct StringPrepData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: [u32; INDEX_COUNT],
+    trie: Trie1,
+    mappings: Vec<u16>,
+}
+
+impl<R> StringPrepData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<StringPrepData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) = read_header(&mut reader, DataFormat::StringPrep)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+        reader.seek_checked(base, 0)?;
+
+        let mut indexes = [0; INDEX_COUNT];
+        for index in indexes.iter_mut() {
+            *index = EndianReader::read_u32(&mut reader)?;
+        }
+        let trie_pos = base + 4 * INDEX_COUNT as u64;
+        let mappings_pos = trie_pos + u64::from(indexes[INDEX_TRIE_SIZE]);
+        let mappings_size = indexes[INDEX_MAPPING_DATA_SIZE];
+        if mappings_pos + u64::from(mappings_size) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+        if mappings_size % 2 != 0 {
+            return Err(IcuError::Malformed("mappings aren't 16-bit units"));
+        }
+        let trie = Trie1::try_init(&mut reader)?;
+        if trie.serialized_length() > indexes[INDEX_TRIE_SIZE] {
+            return Err(IcuError::Malformed("trie overruns its space"));
+        }
+
+        reader.seek(SeekFrom::Start(mappings_pos))?;
+        let mut mappings = Vec::with_capacity(mappings_size as usize / 2);
+        for _ in 0..mappings_size / 2 {
+            mappings.push(EndianReader::read_u16(&mut reader)?);
+        }
+
+        Ok(StringPrepData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            trie,
+            mappings,
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The trie of the profile's code point types and mapping indexes.
+    pub fn trie(&self) -> &Trie1 {
+        &self.trie
+    }
+
+    /// The byte offset of the trie from the start of the data, just past
+    /// the indexes.
+    pub fn trie_offset(&self) -> u32 {
+        4 * INDEX_COUNT as u32
+    }
+
+    /// The number of bytes the trie takes up.
+    pub fn trie_size(&self) -> u32 {
+        self.indexes[INDEX_TRIE_SIZE]
+    }
+
+    /// The mappings, as UTF-16 units.
+    pub fn mappings(&self) -> &[u16] {
+        &self.mappings
+    }
+
+    /// Where the mappings to one, two, three and four UTF-16 units start in
+    /// `mappings`. Longer mappings follow those of four units, each after a
+    /// unit holding its length.
+    pub fn mapping_index_starts(&self) -> [u32; 4] {
+        let start = ONE_UCHAR_MAPPING_INDEX_START;
+        [
+            self.indexes[start],
+            self.indexes[start + 1],
+            self.indexes[start + 2],
+            self.indexes[start + 3],
+        ]
+    }
+
+    /// The last Unicode version whose normalization corrections the
+    /// profile takes into account, packed a byte per component.
+    pub fn norm_correction_version(&self) -> u32 {
+        self.indexes[NORM_CORRECTNS_LAST_UNI_VERSION]
+    }
+
+    /// Whether the profile normalizes to NFKC after mapping.
+    pub fn normalization_on(&self) -> bool {
+        self.indexes[OPTIONS] & NORMALIZATION_ON != 0
+    }
+
+    /// Whether the profile checks the RFC 3454 bidi rules.
+    pub fn check_bidi_on(&self) -> bool {
+        self.indexes[OPTIONS] & CHECK_BIDI_ON != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::IcuError;
+    use std::io::Cursor;
+    use stringprep::StringPrepData;
+    use {write_header, DataFormat, Order};
+
+    const TRIE_SIZE: u32 = 16 + 2 * 2080 + 2 * 64;
+
+    /// A big-endian 16-bit UTrie giving 'A' the value 0xfff2, with no
+    /// supplementary data.
+    fn trie() -> Vec<u8> {
+        let index_length = 2080;
+        let mut units = vec![0x5472, 0x6965, 0, 0x25, 0, index_length, 0, 64];
+        for i in 0..index_length {
+            units.push(if i == 2 { (32 + index_length) >> 2 } else { index_length >> 2 });
+        }
+        let mut data = vec![0u16; 64];
+        data[32 + 1] = 0xfff2;
+        units.extend(data);
+        let mut bytes = Vec::new();
+        for unit in units {
+            bytes.extend_from_slice(&[(unit >> 8) as u8, unit as u8]);
+        }
+        bytes
+    }
+
+    /// A big-endian profile with `format_version`, the trie, and mappings
+    /// `ss`, `a` and `bc`.
+    fn spp(format_version: [u8; 4], indexes: &[u32; 16]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::StringPrep;
+        write_header(&mut bytes, format, format_version, (3, 2, 0, 0), Order::BigEndian).unwrap();
+        for index in indexes {
+            bytes.extend_from_slice(&[
+                (index >> 24) as u8,
+                (index >> 16) as u8,
+                (index >> 8) as u8,
+                *index as u8,
+            ]);
+        }
+        bytes.extend(trie());
+        for unit in &[0x61u16, 0x62, 0x63, 0x73, 0x73] {
+            bytes.extend_from_slice(&[(unit >> 8) as u8, *unit as u8]);
+        }
+        Cursor::new(bytes)
+    }
+
+    fn indexes() -> [u32; 16] {
+        [TRIE_SIZE, 10, 0x0302_0000, 0, 1, 3, 5, 3, 0, 0, 0, 0, 0, 0, 0, 0]
+    }
+
+    #[test]
+    fn try_init_reads_indexes_and_mappings() {
+        let s = StringPrepData::try_init(spp([3, 2, 5, 2], &indexes())).expect("Failed to read");
+        assert_eq!(s.trie_offset(), 64);
+        assert_eq!(s.trie_size(), TRIE_SIZE);
+        assert_eq!(s.trie().get('A'), 0xfff2);
+        assert_eq!(s.trie().get('B'), 0);
+        assert_eq!(s.mappings(), &[0x61, 0x62, 0x63, 0x73, 0x73]);
+        assert_eq!(s.mapping_index_starts(), [0, 1, 3, 5]);
+        assert_eq!(s.norm_correction_version(), 0x0302_0000);
+        assert!(s.normalization_on() && s.check_bidi_on());
+    }
+
+    #[test]
+    fn try_init_requires_format_3_x_5_2() {
+        assert!(StringPrepData::try_init(spp([3, 0, 5, 2], &indexes())).is_ok());
+        match StringPrepData::try_init(spp([3, 0, 5, 1], &indexes())) {
+            Err(IcuError::UnsupportedFormatVersion { version, .. }) => {
+                assert_eq!(version, [3, 0, 5, 1])
+            }
+            other => panic!("expected UnsupportedFormatVersion, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn try_init_rejects_bad_indexes() {
+        let mut bad = indexes();
+        bad[1] = 12; // mappings past the end of the file
+        assert!(StringPrepData::try_init(spp([3, 2, 5, 2], &bad)).is_err());
+
+        let mut bad = indexes();
+        bad[0] -= 2; // too little space for the trie
+        assert!(StringPrepData::try_init(spp([3, 2, 5, 2], &bad)).is_err());
+
+        let mut bad = indexes();
+        bad[1] = 9; // odd-sized mappings
+        assert!(StringPrepData::try_init(spp([3, 2, 5, 2], &bad)).is_err());
+
+        let mut bytes = spp([3, 2, 5, 2], &indexes()).into_inner();
+        bytes[32 + 64 + 3] = b'2'; // a UTrie2
+        assert!(StringPrepData::try_init(Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,661 @@
+//! Serialized UTrie2 code point tries, which map code points to 16- or
+//! 32-bit values in most ICU property data, and the original UTrie that
+//! older data, like StringPrep profiles, still uses.
+
+use std::io::{SeekFrom, prelude::*};
+use error::IcuError;
+use {EndianReader, OrderedReader};
+
+const SIGNATURE: u32 = 0x5472_6932; // "Tri2"
+const SIGNATURE_V1: u32 = 0x5472_6965; // "Trie"
+
+/// Bits of a code point that index into a data block.
+const SHIFT_2: u32 = 5;
+/// Bits of a supplementary code point that index into the index-1 table.
+const SHIFT_1: u32 = 11;
+/// Index-2 entries hold data offsets shifted right by this much.
+const INDEX_SHIFT: u32 = 2;
+const DATA_MASK: u32 = (1 << SHIFT_2) - 1;
+const INDEX_2_MASK: u32 = (1 << (SHIFT_1 - SHIFT_2)) - 1;
+
+/// The index-2 table for lead surrogate code units, after the BMP's.
+const LSCP_INDEX_2_OFFSET: u32 = 0x1_0000 >> SHIFT_2;
+const LSCP_INDEX_2_LENGTH: u32 = 0x400 >> SHIFT_2;
+/// The index-2 table for two-byte UTF-8, which follows.
+const UTF8_2B_INDEX_2_LENGTH: u32 = 0x800 >> 6;
+/// Where the index-1 table for supplementary code points starts.
+const INDEX_1_OFFSET: u32 = LSCP_INDEX_2_OFFSET + LSCP_INDEX_2_LENGTH + UTF8_2B_INDEX_2_LENGTH;
+/// The BMP's entries, which would come first, are left out of index-1.
+const OMITTED_BMP_INDEX_1_LENGTH: u32 = 0x1_0000 >> SHIFT_1;
+
+/// Where the value for ill-formed UTF-8 sits in the data.
+const BAD_UTF8_DATA_OFFSET: u32 = 0x80;
+/// The length of the ASCII and ill-formed UTF-8 blocks at the start of the
+/// data.
+const DATA_START_OFFSET: u32 = 0xc0;
+/// Data lengths are multiples of this.
+const DATA_GRANULARITY: u32 = 1 << INDEX_SHIFT;
+
+/// A read-only UTrie2.
+///
+/// A serialized trie is a 16-byte header followed by a 16-bit index array
+/// and then the data, either 16- or 32-bit values. BMP code points index
+/// the index-2 table directly by their top bits; supplementary ones go
+/// through the index-1 table first. Code points from `high_start` on all
+/// share one value.
+///
+/// In a 16-bit trie the data directly follows the index in the same array,
+/// and the index entries already count the index's length.
+#[derive(Clone, Debug)]
+pub struct Trie2 {
+    /// The index, followed by the data for a 16-bit trie.
+    index: Vec<u16>,
+    /// The data of a 32-bit trie; empty for a 16-bit trie.
+    data32: Vec<u32>,
+    index_length: u32,
+    data_length: u32,
+    index_2_null_offset: u16,
+    data_null_offset: u16,
+    high_start: u32,
+    high_value_index: u32,
+    initial_value: u32,
+    error_value: u32,
+}
+
+impl Trie2 {
+    /// Reads a serialized trie from the reader's position, leaving it just
+    /// past the trie.
+    pub fn try_init<R>(reader: &mut OrderedReader<R>) -> Result<Trie2, IcuError>
+    where
+        R: Read + Seek,
+    {
+        if EndianReader::read_u32(reader)? != SIGNATURE {
+            return Err(IcuError::Malformed("not a UTrie2"));
+        }
+        let options = EndianReader::read_u16(reader)?;
+        let index_length = u32::from(EndianReader::read_u16(reader)?);
+        let data_length = u32::from(EndianReader::read_u16(reader)?) << INDEX_SHIFT;
+        let index_2_null_offset = EndianReader::read_u16(reader)?;
+        let data_null_offset = EndianReader::read_u16(reader)?;
+        let high_start = u32::from(EndianReader::read_u16(reader)?) << SHIFT_1;
+
+        let is_32_bit = match options & 0xf {
+            0 => false,
+            1 => true,
+            _ => return Err(IcuError::Malformed("unknown UTrie2 value width")),
+        };
+        let index_1_length = (high_start >> SHIFT_1).saturating_sub(OMITTED_BMP_INDEX_1_LENGTH);
+        if index_length < INDEX_1_OFFSET + index_1_length {
+            return Err(IcuError::Malformed("UTrie2 index is too short"));
+        }
+        if data_length < DATA_START_OFFSET {
+            return Err(IcuError::Malformed("UTrie2 data is too short"));
+        }
+
+        let mut index = Vec::with_capacity((index_length + data_length) as usize);
+        for _ in 0..index_length {
+            index.push(EndianReader::read_u16(reader)?);
+        }
+        let mut data32 = Vec::new();
+        let data_offset = if is_32_bit {
+            data32.reserve(data_length as usize);
+            for _ in 0..data_length {
+                data32.push(EndianReader::read_u32(reader)?);
+            }
+            0
+        } else {
+            for _ in 0..data_length {
+                index.push(EndianReader::read_u16(reader)?);
+            }
+            index_length
+        };
+
+        let mut trie = Trie2 {
+            index,
+            data32,
+            index_length,
+            data_length,
+            index_2_null_offset,
+            data_null_offset,
+            high_start,
+            high_value_index: data_offset + data_length - DATA_GRANULARITY,
+            initial_value: 0,
+            error_value: 0,
+        };
+        trie.initial_value = trie.value_at(u32::from(data_null_offset));
+        trie.error_value = trie.value_at(data_offset + BAD_UTF8_DATA_OFFSET);
+        Ok(trie)
+    }
+
+    /// Looks up the value for `c`.
+    pub fn get(&self, c: char) -> u32 {
+        let c = c as u32;
+        let data_index = if c <= 0xffff {
+            // chars are never lead surrogates, whose code points use the
+            // index-2 table at LSCP_INDEX_2_OFFSET instead
+            self.data_index(c >> SHIFT_2, c)
+        } else if c >= self.high_start {
+            return self.value_at(self.high_value_index);
+        } else {
+            let i1 = INDEX_1_OFFSET - OMITTED_BMP_INDEX_1_LENGTH + (c >> SHIFT_1);
+            let i2 = self.index_at(i1) + ((c >> SHIFT_2) & INDEX_2_MASK);
+            self.data_index(i2, c)
+        };
+        self.value_at(data_index)
+    }
+
+    /// Whether the trie holds 32-bit values rather than 16-bit ones.
+    pub fn is_32_bit(&self) -> bool {
+        !self.data32.is_empty()
+    }
+
+    /// The value of code points that were never set.
+    pub fn initial_value(&self) -> u32 {
+        self.initial_value
+    }
+
+    /// The number of bytes the serialized trie takes up.
+    pub fn serialized_length(&self) -> u32 {
+        let value_size = if self.is_32_bit() { 4 } else { 2 };
+        16 + 2 * self.index_length + value_size * self.data_length
+    }
+
+    fn index_at(&self, i: u32) -> u32 {
+        self.index.get(i as usize).map_or(0, |&v| u32::from(v))
+    }
+
+    /// The data index for `c`, given the index-2 entry of its block.
+    fn data_index(&self, i2: u32, c: u32) -> u32 {
+        (self.index_at(i2) << INDEX_SHIFT) + (c & DATA_MASK)
+    }
+
+    /// The value at `i` in the data, or the error value for indexes that a
+    /// malformed trie points outside of it.
+    fn value_at(&self, i: u32) -> u32 {
+        let value = if self.is_32_bit() {
+            self.data32.get(i as usize).cloned()
+        } else {
+            self.index.get(i as usize).map(|&v| u32::from(v))
+        };
+        value.unwrap_or(self.error_value)
+    }
+}
+
+// UTrie options
+const V1_SHIFT_MASK: u32 = 0xf;
+const V1_INDEX_SHIFT_SHIFT: u32 = 4;
+const V1_DATA_IS_32_BIT: u32 = 0x100;
+
+/// The number of index entries for the BMP, one per data block.
+const V1_BMP_INDEX_LENGTH: u32 = 0x1_0000 >> SHIFT_2;
+/// The number of index entries for the lead surrogate code points, which
+/// come after the BMP's so that those can hold the lead surrogate code
+/// units' values.
+const V1_SURROGATE_BLOCK_COUNT: u32 = 1 << 5;
+
+/// A read-only UTrie, the format UTrie2 replaced.
+///
+/// A serialized trie is a 16-byte header followed by a 16-bit index array
+/// and then the data, either 16- or 32-bit values, with the same block and
+/// index shifts as a UTrie2 and, for 16-bit tries, the data following the
+/// index in the same array. BMP code points index the index array directly
+/// by their top bits. A supplementary code point first looks up its lead
+/// surrogate code unit, whose value the data format folds into an offset
+/// into the index, and then looks up its trail surrogate's low ten bits
+/// from there.
+#[derive(Clone, Debug)]
+pub struct Trie1 {
+    /// The index, followed by the data for a 16-bit trie.
+    index: Vec<u16>,
+    /// The data of a 32-bit trie; empty for a 16-bit trie.
+    data32: Vec<u32>,
+    index_length: u32,
+    data_length: u32,
+    initial_value: u32,
+    folding_offset: fn(u32) -> u32,
+}
+
+impl Trie1 {
+    /// Reads a serialized trie from the reader's position, leaving it just
+    /// past the trie.
+    ///
+    /// Lead surrogate values are taken as index offsets as they are, as
+    /// ICU's default folding does; see `with_folding_offset`.
+    pub fn try_init<R>(reader: &mut OrderedReader<R>) -> Result<Trie1, IcuError>
+    where
+        R: Read + Seek,
+    {
+        if EndianReader::read_u32(reader)? != SIGNATURE_V1 {
+            return Err(IcuError::Malformed("not a UTrie"));
+        }
+        let options = EndianReader::read_u32(reader)?;
+        let index_length = EndianReader::read_u32(reader)?;
+        let data_length = EndianReader::read_u32(reader)?;
+
+        if options & V1_SHIFT_MASK != SHIFT_2
+            || (options >> V1_INDEX_SHIFT_SHIFT) & V1_SHIFT_MASK != INDEX_SHIFT
+        {
+            return Err(IcuError::Malformed("unknown UTrie block shifts"));
+        }
+        if !(V1_BMP_INDEX_LENGTH + V1_SURROGATE_BLOCK_COUNT..=0xffff).contains(&index_length) {
+            return Err(IcuError::Malformed("UTrie index is the wrong length"));
+        }
+        if !(1 << SHIFT_2..=0x3_ffff).contains(&data_length) {
+            return Err(IcuError::Malformed("UTrie data is the wrong length"));
+        }
+        let is_32_bit = options & V1_DATA_IS_32_BIT != 0;
+        let value_size = if is_32_bit { 4 } else { 2 };
+        let pos = reader.stream_position()?;
+        let size = 2 * u64::from(index_length) + value_size * u64::from(data_length);
+        reader.seek_checked(pos, size)?;
+
+        let mut index = Vec::with_capacity((index_length + data_length) as usize);
+        for _ in 0..index_length {
+            index.push(EndianReader::read_u16(reader)?);
+        }
+        let mut data32 = Vec::new();
+        if is_32_bit {
+            data32.reserve(data_length as usize);
+            for _ in 0..data_length {
+                data32.push(EndianReader::read_u32(reader)?);
+            }
+        } else {
+            for _ in 0..data_length {
+                index.push(EndianReader::read_u16(reader)?);
+            }
+        }
+
+        // the first data block is all the initial value
+        let initial_value = match data32.first() {
+            Some(&value) => value,
+            None => u32::from(index[index_length as usize]),
+        };
+        Ok(Trie1 {
+            index,
+            data32,
+            index_length,
+            data_length,
+            initial_value,
+            folding_offset: |value| value,
+        })
+    }
+
+    /// Uses `folding_offset` to turn the values of lead surrogates into
+    /// offsets into the index, for data formats that pack other bits in
+    /// with them. An offset of 0 means no supplementary code point with
+    /// that lead surrogate has a value.
+    pub fn with_folding_offset(mut self, folding_offset: fn(u32) -> u32) -> Trie1 {
+        self.folding_offset = folding_offset;
+        self
+    }
+
+    /// Looks up the value for `c`.
+    pub fn get(&self, c: char) -> u32 {
+        let c = c as u32;
+        if c <= 0xffff {
+            // chars are never lead surrogates, whose code points use the
+            // index entries after the BMP's instead
+            return self.get_raw(0, c);
+        }
+        let lead = 0xd7c0 + (c >> 10);
+        let offset = (self.folding_offset)(self.get_raw(0, lead));
+        if offset > 0 {
+            self.get_raw(offset, c & 0x3ff)
+        } else {
+            self.initial_value
+        }
+    }
+
+    /// Whether the trie holds 32-bit values rather than 16-bit ones.
+    pub fn is_32_bit(&self) -> bool {
+        !self.data32.is_empty()
+    }
+
+    /// The value of code points that were never set.
+    pub fn initial_value(&self) -> u32 {
+        self.initial_value
+    }
+
+    /// The number of bytes the serialized trie takes up.
+    pub fn serialized_length(&self) -> u32 {
+        let value_size = if self.is_32_bit() { 4 } else { 2 };
+        16 + 2 * self.index_length + value_size * self.data_length
+    }
+
+    /// The value for the 16-bit unit `c16` in the index block at `offset`,
+    /// or the initial value where a malformed trie points outside the data.
+    fn get_raw(&self, offset: u32, c16: u32) -> u32 {
+        let block = self.index.get((offset + (c16 >> SHIFT_2)) as usize);
+        let i = block.map_or(0, |&block| u32::from(block) << INDEX_SHIFT) + (c16 & DATA_MASK);
+        let value = if self.is_32_bit() {
+            self.data32.get(i as usize).cloned()
+        } else {
+            self.index.get(i as usize).map(|&v| u32::from(v))
+        };
+        value.unwrap_or(self.initial_value)
+    }
+}
+
+/// A trie in either format, for data that may hold either.
+#[derive(Clone, Debug)]
+pub enum AnyTrie {
+    V1(Trie1),
+    V2(Trie2),
+}
+
+impl AnyTrie {
+    /// Reads a serialized trie of either format from the reader's position,
+    /// going by its signature, and leaves the reader just past it.
+    pub fn try_init<R>(reader: &mut OrderedReader<R>) -> Result<AnyTrie, IcuError>
+    where
+        R: Read + Seek,
+    {
+        let signature = EndianReader::read_u32(reader)?;
+        reader.seek(SeekFrom::Current(-4))?;
+        match signature {
+            SIGNATURE => Trie2::try_init(reader).map(AnyTrie::V2),
+            SIGNATURE_V1 => Trie1::try_init(reader).map(AnyTrie::V1),
+            _ => Err(IcuError::Malformed("not a UTrie or UTrie2")),
+        }
+    }
+
+    /// Looks up the value for `c`.
+    pub fn get(&self, c: char) -> u32 {
+        match *self {
+            AnyTrie::V1(ref trie) => trie.get(c),
+            AnyTrie::V2(ref trie) => trie.get(c),
+        }
+    }
+
+    /// The number of bytes the serialized trie takes up.
+    pub fn serialized_length(&self) -> u32 {
+        match *self {
+            AnyTrie::V1(ref trie) => trie.serialized_length(),
+            AnyTrie::V2(ref trie) => trie.serialized_length(),
+        }
+    }
+}
+
+/// Builders for the big-endian data that other modules' tests read.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::ops::Range;
+
+    /// The serialized length of a trie from `bmp_trie`.
+    pub(crate) const fn bmp_trie_length(is_32_bit: bool) -> u32 {
+        16 + 2 * 2112 + if is_32_bit { 4 } else { 2 } * 0xe0
+    }
+
+    /// Big-endian bytes of `units`.
+    pub(crate) fn be_u16s(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|unit| vec![(unit >> 8) as u8, *unit as u8]).collect()
+    }
+
+    /// Big-endian bytes of `words`.
+    pub(crate) fn be_u32s(words: &[u32]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| {
+                vec![(word >> 24) as u8, (word >> 16) as u8, (word >> 8) as u8, *word as u8]
+            })
+            .collect()
+    }
+
+    /// A big-endian trie giving the code points in each range its value and
+    /// everything else 0, with no supplementary data.
+    ///
+    /// The data holds the four ASCII blocks, the ill-formed UTF-8 block and
+    /// a null block at 0xc0, so the ranges must stay below U+0080.
+    pub(crate) fn bmp_trie(is_32_bit: bool, values: &[(Range<usize>, u32)]) -> Vec<u8> {
+        let index_length = 2112;
+        let offset = if is_32_bit { 0 } else { index_length };
+        let null_block = (0xc0 + offset) >> 2;
+        let mut units = vec![0x5472, 0x6932, is_32_bit as u16, index_length, 0xe0 >> 2, 0];
+        units.push(0xc0 + offset);
+        units.push((0x10000 >> 11) as u16); // high start
+        for i in 0..index_length {
+            units.push(if i < 4 { (i * 32 + offset) >> 2 } else { null_block });
+        }
+        let mut data = vec![0u32; 0xe0];
+        for &(ref range, value) in values {
+            for slot in &mut data[range.clone()] {
+                *slot = value;
+            }
+        }
+        let mut bytes = be_u16s(&units);
+        if is_32_bit {
+            bytes.extend(be_u32s(&data));
+        } else {
+            bytes.extend(be_u16s(&data.iter().map(|&value| value as u16).collect::<Vec<_>>()));
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use trie::{AnyTrie, Trie1, Trie2};
+    use {Order, OrderedReader};
+
+    const INDEX_LENGTH: u32 = 2272;
+    const DATA_LENGTH: u32 = 0x104;
+
+    /// A big-endian trie mapping A-Z to 1, U+10001 to 7, and everything
+    /// from U+20000 on to 9.
+    ///
+    /// The data holds the ASCII blocks, the ill-formed UTF-8 block, a null
+    /// block at 0xc0, a supplementary block at 0xe0 and the high value at
+    /// 0x100. The index holds the BMP's index-2 entries, a 32-entry
+    /// index-1 table at 2112, then a supplementary index-2 block at 2144
+    /// and a null index-2 block at 2208.
+    fn serialized(is_32_bit: bool) -> Vec<u8> {
+        let offset = if is_32_bit { 0 } else { INDEX_LENGTH };
+        let null_block = ((0xc0 + offset) >> 2) as u16;
+        let mut index = vec![0u16; INDEX_LENGTH as usize];
+        for (i, entry) in index.iter_mut().enumerate().take(2048) {
+            *entry = if i < 4 {
+                ((i as u32 * 32 + offset) >> 2) as u16
+            } else {
+                null_block
+            };
+        }
+        for entry in &mut index[2112..2144] {
+            *entry = 2208;
+        }
+        index[2112] = 2144;
+        for entry in &mut index[2144..2272] {
+            *entry = null_block;
+        }
+        index[2144] = ((0xe0 + offset) >> 2) as u16;
+
+        let mut data = vec![0u32; DATA_LENGTH as usize];
+        for value in &mut data[0x41..0x5b] {
+            *value = 1;
+        }
+        data[0x80] = 0xbad;
+        data[0xe1] = 7;
+        for value in &mut data[0x100..0x104] {
+            *value = 9;
+        }
+
+        let mut bytes = b"Tri2".to_vec();
+        let header = [
+            if is_32_bit { 1 } else { 0 },
+            INDEX_LENGTH as u16,
+            (DATA_LENGTH >> 2) as u16,
+            2208,
+            (0xc0 + offset) as u16,
+            (0x20000 >> 11) as u16,
+        ];
+        for unit in header.iter().chain(&index) {
+            bytes.extend_from_slice(&[(unit >> 8) as u8, *unit as u8]);
+        }
+        for value in data {
+            if is_32_bit {
+                bytes.extend_from_slice(&[0, 0, (value >> 8) as u8, value as u8]);
+            } else {
+                bytes.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+            }
+        }
+        bytes
+    }
+
+    fn read(bytes: Vec<u8>) -> Trie2 {
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        Trie2::try_init(&mut reader).expect("Failed to read trie")
+    }
+
+    fn check_values(trie: &Trie2) {
+        assert_eq!(trie.get('A'), 1);
+        assert_eq!(trie.get('Z'), 1);
+        assert_eq!(trie.get('a'), 0);
+        assert_eq!(trie.get('\u{3042}'), 0);
+        assert_eq!(trie.get('\u{ffff}'), 0);
+        assert_eq!(trie.get('\u{10000}'), 0);
+        assert_eq!(trie.get('\u{10001}'), 7);
+        assert_eq!(trie.get('\u{10801}'), 0);
+        assert_eq!(trie.get('\u{1ffff}'), 0);
+        assert_eq!(trie.get('\u{20000}'), 9);
+        assert_eq!(trie.get('\u{10ffff}'), 9);
+        assert_eq!(trie.initial_value(), 0);
+    }
+
+    #[test]
+    fn get_reads_16_bit_tries() {
+        let bytes = serialized(false);
+        let trie = read(bytes.clone());
+        assert!(!trie.is_32_bit());
+        assert_eq!(trie.serialized_length() as usize, bytes.len());
+        check_values(&trie);
+    }
+
+    #[test]
+    fn get_reads_32_bit_tries() {
+        let bytes = serialized(true);
+        let trie = read(bytes.clone());
+        assert!(trie.is_32_bit());
+        assert_eq!(trie.serialized_length() as usize, bytes.len());
+        check_values(&trie);
+    }
+
+    #[test]
+    fn try_init_rejects_bad_headers() {
+        let mut bytes = serialized(false);
+        bytes[3] = b'1';
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(Trie2::try_init(&mut reader).is_err());
+
+        let mut bytes = serialized(false);
+        bytes[5] = 2; // unknown value width
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(Trie2::try_init(&mut reader).is_err());
+
+        let bytes = serialized(false)[..1000].to_vec();
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(Trie2::try_init(&mut reader).is_err());
+    }
+
+    const V1_INDEX_LENGTH: u32 = 2112;
+
+    /// A big-endian UTrie mapping A-Z to 1 and U+10001 to 7.
+    ///
+    /// The data holds a null block, a block for A-Z, the block of lead
+    /// surrogate code units, whose value for U+D800 is the folding offset
+    /// 2080, and a supplementary block. The index holds the BMP's entries,
+    /// the lead surrogate code points' at 2048, then the folded index block
+    /// for U+D800 at 2080.
+    fn serialized_v1(is_32_bit: bool) -> Vec<u8> {
+        let offset = if is_32_bit { 0 } else { V1_INDEX_LENGTH };
+        let block = |data: u32| ((data + offset) >> 2) as u16;
+        let mut index = vec![block(0); V1_INDEX_LENGTH as usize];
+        index[0x41 >> 5] = block(32);
+        index[0xd800 >> 5] = block(64);
+        index[2080] = block(96);
+
+        let mut data = vec![0u32; 128];
+        for value in &mut data[32 + 1..32 + 27] {
+            *value = 1;
+        }
+        data[64] = 2080;
+        data[96 + 1] = 7;
+
+        let mut bytes = b"Trie".to_vec();
+        let options = 0x25 | if is_32_bit { 0x100 } else { 0 };
+        for word in &[options, V1_INDEX_LENGTH, data.len() as u32] {
+            bytes.extend_from_slice(&[
+                (word >> 24) as u8,
+                (word >> 16) as u8,
+                (word >> 8) as u8,
+                *word as u8,
+            ]);
+        }
+        for unit in &index {
+            bytes.extend_from_slice(&[(unit >> 8) as u8, *unit as u8]);
+        }
+        for value in data {
+            if is_32_bit {
+                bytes.extend_from_slice(&[0, 0, (value >> 8) as u8, value as u8]);
+            } else {
+                bytes.extend_from_slice(&[(value >> 8) as u8, value as u8]);
+            }
+        }
+        bytes
+    }
+
+    fn read_v1(bytes: Vec<u8>) -> Trie1 {
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        Trie1::try_init(&mut reader).expect("Failed to read trie")
+    }
+
+    #[test]
+    fn trie1_get_reads_both_widths() {
+        for &is_32_bit in &[false, true] {
+            let bytes = serialized_v1(is_32_bit);
+            let trie = read_v1(bytes.clone());
+            assert_eq!(trie.is_32_bit(), is_32_bit);
+            assert_eq!(trie.serialized_length() as usize, bytes.len());
+            assert_eq!(trie.get('A'), 1);
+            assert_eq!(trie.get('Z'), 1);
+            assert_eq!(trie.get('a'), 0);
+            assert_eq!(trie.get('\u{ffff}'), 0);
+            assert_eq!(trie.get('\u{10000}'), 0);
+            assert_eq!(trie.get('\u{10001}'), 7);
+            assert_eq!(trie.get('\u{20001}'), 0);
+            assert_eq!(trie.initial_value(), 0);
+        }
+    }
+
+    #[test]
+    fn trie1_folds_lead_values_with_the_given_function() {
+        let trie = read_v1(serialized_v1(false)).with_folding_offset(|_| 0);
+        assert_eq!(trie.get('\u{10001}'), 0);
+        assert_eq!(trie.get('A'), 1);
+    }
+
+    #[test]
+    fn trie1_try_init_rejects_bad_headers() {
+        let mut bytes = serialized_v1(false);
+        bytes[7] = 0x24; // a data block shift of 4
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(Trie1::try_init(&mut reader).is_err());
+
+        let bytes = serialized_v1(false)[..1000].to_vec();
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(Trie1::try_init(&mut reader).is_err());
+    }
+
+    #[test]
+    fn any_trie_picks_the_format_by_signature() {
+        for bytes in [serialized(false), serialized_v1(false)].iter().cloned() {
+            let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+            let trie = AnyTrie::try_init(&mut reader).expect("Failed to read trie");
+            assert_eq!(trie.get('A'), 1);
+            assert_eq!(trie.get('\u{10001}'), 7);
+        }
+        let mut bytes = serialized(false);
+        bytes[3] = b'1';
+        let mut reader = OrderedReader::wrap(Cursor::new(bytes), Order::BigEndian).unwrap();
+        assert!(AnyTrie::try_init(&mut reader).is_err());
+    }
+}
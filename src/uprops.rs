@@ -0,0 +1,201 @@
+//! Character property data ("UPro"), as in `uprops.icu`.
+
+use std::convert::TryFrom;
+use std::io::prelude::*;
+use error::IcuError;
+use trie::Trie2;
+use version::{PiecewiseVersion, Version};
+use {read_header, wrap_ordered, DataFormat, EndianReader, OrderedReader};
+
+const INDEX_COUNT: usize = 16;
+
+// indexes[] slots; the offsets count 32-bit words from the start of the data
+const PROPS32_INDEX: usize = 0;
+const EXCEPTIONS_INDEX: usize = 1;
+const EXCEPTIONS_TOP_INDEX: usize = 2;
+const ADDITIONAL_TRIE_INDEX: usize = 3;
+const ADDITIONAL_VECTORS_INDEX: usize = 4;
+const ADDITIONAL_VECTORS_COLUMNS_INDEX: usize = 5;
+const SCRIPT_EXTENSIONS_INDEX: usize = 6;
+const RESERVED_INDEX_7: usize = 7;
+const RESERVED_INDEX_8: usize = 8;
+const DATA_TOP_INDEX: usize = 9;
+const MAX_VALUES_INDEX: usize = 10;
+const MAX_VALUES_2_INDEX: usize = 11;
+
+/// The indexes and main trie of a character property data file.
+///
+/// The data starts with sixteen 32-bit indexes, followed by the main
+/// properties trie. After it come the additional properties trie, its
+/// properties vectors and the script extensions, at the offsets the
+/// indexes give.
+#[derive(Clone, Debug)]
+pub struct UPropsData<R>
+where
+    R: Read + Seek,
+{
+    reader: OrderedReader<R>,
+    header_size: u16,
+    data_version: PiecewiseVersion,
+    indexes: [u32; INDEX_COUNT],
+    main_trie: Trie2,
+}
+
+impl<R> UPropsData<R>
+where
+    R: Read + Seek,
+{
+    pub fn try_init(bytes: R) -> Result<UPropsData<R>, IcuError> {
+        let mut reader = wrap_ordered(bytes)?;
+        let (header_size, data_version) =
+            read_header(&mut reader, DataFormat::CharacterProperty)?;
+        let base = u64::from(header_size);
+        let len = reader.len();
+        reader.seek_checked(base, 0)?;
+
+        let mut indexes = [0; INDEX_COUNT];
+        for index in indexes.iter_mut() {
+            *index = EndianReader::read_u32(&mut reader)?;
+        }
+        let offsets = [
+            INDEX_COUNT as u32,
+            indexes[PROPS32_INDEX],
+            indexes[EXCEPTIONS_INDEX],
+            indexes[EXCEPTIONS_TOP_INDEX],
+            indexes[ADDITIONAL_TRIE_INDEX],
+            indexes[ADDITIONAL_VECTORS_INDEX],
+            indexes[SCRIPT_EXTENSIONS_INDEX],
+            indexes[RESERVED_INDEX_7],
+            indexes[RESERVED_INDEX_8],
+            indexes[DATA_TOP_INDEX],
+        ];
+        if offsets.windows(2).any(|w| w[0] > w[1]) {
+            return Err(IcuError::Malformed("data offsets out of order"));
+        }
+        if base + 4 * u64::from(indexes[DATA_TOP_INDEX]) > len {
+            return Err(IcuError::Malformed("data runs past the end of the file"));
+        }
+
+        let main_trie = Trie2::try_init(&mut reader)?;
+        if main_trie.serialized_length() > 4 * (indexes[PROPS32_INDEX] - INDEX_COUNT as u32) {
+            return Err(IcuError::Malformed("main trie overruns its space"));
+        }
+
+        Ok(UPropsData {
+            reader,
+            header_size,
+            data_version,
+            indexes,
+            main_trie,
+        })
+    }
+
+    /// The Unicode version of the data.
+    pub fn version(&self) -> Option<Version> {
+        Version::try_from(self.data_version).ok()
+    }
+
+    /// The raw data version bytes from the header.
+    pub fn data_version(&self) -> PiecewiseVersion {
+        self.data_version
+    }
+
+    /// The trie of main properties; its values hold the general category
+    /// in their low five bits, and the numeric type and value above.
+    pub fn main_trie(&self) -> &Trie2 {
+        &self.main_trie
+    }
+
+    /// The number of 32-bit words in each row of the properties vectors.
+    pub fn props_vectors_columns(&self) -> u32 {
+        self.indexes[ADDITIONAL_VECTORS_COLUMNS_INDEX]
+    }
+
+    /// The maximum values of the script, East Asian width and block
+    /// properties, packed as in the first properties vector word.
+    pub fn max_values(&self) -> u32 {
+        self.indexes[MAX_VALUES_INDEX]
+    }
+
+    /// The maximum values of the properties packed in the third properties
+    /// vector word, like the decomposition type and line break.
+    pub fn max_values_2(&self) -> u32 {
+        self.indexes[MAX_VALUES_2_INDEX]
+    }
+
+    /// The number of bytes set aside for the main trie.
+    pub fn main_trie_size(&self) -> u32 {
+        4 * (self.indexes[PROPS32_INDEX] - INDEX_COUNT as u32)
+    }
+
+    /// The number of bytes set aside for the additional properties trie.
+    pub fn additional_trie_size(&self) -> u32 {
+        4 * (self.indexes[ADDITIONAL_VECTORS_INDEX] - self.indexes[ADDITIONAL_TRIE_INDEX])
+    }
+
+    /// The size of the data in bytes, not counting the header.
+    pub fn data_size(&self) -> u32 {
+        4 * self.indexes[DATA_TOP_INDEX]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use trie::fixtures::{be_u32s, bmp_trie, bmp_trie_length};
+    use uprops::UPropsData;
+    use {write_header, DataFormat, Order};
+
+    const TRIE_WORDS: u32 = bmp_trie_length(false) / 4;
+
+    /// A 16-bit trie giving A-Z the general category Lu (1) and a-z Ll (2).
+    fn trie() -> Vec<u8> {
+        bmp_trie(false, &[(0x41..0x5b, 1), (0x61..0x7b, 2)])
+    }
+
+    fn uprops(indexes: &[u32; 16]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let format = DataFormat::CharacterProperty;
+        write_header(&mut bytes, format, [7, 0, 0, 0], (10, 0, 0, 0), Order::BigEndian).unwrap();
+        bytes.extend(be_u32s(indexes));
+        bytes.extend(trie());
+        Cursor::new(bytes)
+    }
+
+    fn indexes() -> [u32; 16] {
+        let top = 16 + TRIE_WORDS;
+        [top, top, top, top, top, 3, top, top, top, top, 0x00bf_00ff, 0x0c3f_ffff, 0, 0, 0, 0]
+    }
+
+    #[test]
+    fn try_init_reads_indexes_and_main_trie() {
+        let p = UPropsData::try_init(uprops(&indexes())).expect("Failed to read UPro");
+        assert_eq!(p.main_trie().get('A'), 1);
+        assert_eq!(p.main_trie().get('z'), 2);
+        assert_eq!(p.main_trie().get('0'), 0);
+        assert_eq!(p.main_trie().get('\u{1f600}'), 0);
+        assert_eq!(p.main_trie_size(), 4 * TRIE_WORDS);
+        assert_eq!(p.additional_trie_size(), 0);
+        assert_eq!(p.props_vectors_columns(), 3);
+        assert_eq!(p.max_values(), 0x00bf_00ff);
+        assert_eq!(p.max_values_2(), 0x0c3f_ffff);
+        assert_eq!(p.data_size(), 4 * (16 + TRIE_WORDS));
+    }
+
+    #[test]
+    fn try_init_rejects_bad_offsets() {
+        let mut bad = indexes();
+        bad[3] -= 1; // additional trie before the exceptions end
+        assert!(UPropsData::try_init(uprops(&bad)).is_err());
+
+        let mut bad = indexes();
+        bad[9] += 1; // past the end of the file
+        assert!(UPropsData::try_init(uprops(&bad)).is_err());
+
+        let mut bad = indexes();
+        for index in &mut bad[..10] {
+            *index -= 1; // too little space for the main trie
+        }
+        assert!(UPropsData::try_init(uprops(&bad)).is_err());
+    }
+}
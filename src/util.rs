@@ -0,0 +1,51 @@
+//! Helpers shared by the decoders.
+
+use std::char;
+use std::io::{self, Error, ErrorKind};
+
+/// Decodes UTF-16 `units` to a string, failing with `InvalidData` on an
+/// unpaired surrogate.
+pub fn utf16_to_string(units: &[u16]) -> io::Result<String> {
+    char::decode_utf16(units.iter().cloned())
+        .map(|c| c.map_err(unpaired))
+        .collect()
+}
+
+/// The number of chars UTF-16 `units` decode to, checked the same way as
+/// `utf16_to_string` but without building the string.
+pub(crate) fn utf16_len_from_units(units: &[u16]) -> io::Result<usize> {
+    char::decode_utf16(units.iter().cloned()).try_fold(0, |len, c| {
+        c.map(|_| len + 1).map_err(unpaired)
+    })
+}
+
+fn unpaired(e: char::DecodeUtf16Error) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("unpaired surrogate {:#06x}", e.unpaired_surrogate()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use util::{utf16_len_from_units, utf16_to_string};
+
+    #[test]
+    fn utf16_to_string_decodes_bmp_and_pairs() {
+        assert_eq!(utf16_to_string(&[]).unwrap(), "");
+        assert_eq!(utf16_to_string(&[0x61, 0x3042]).unwrap(), "a\u{3042}");
+        assert_eq!(utf16_to_string(&[0xd83d, 0xde00, 0x21]).unwrap(), "\u{1f600}!");
+        assert_eq!(utf16_len_from_units(&[0x61, 0x3042]).unwrap(), 2);
+        assert_eq!(utf16_len_from_units(&[0xd83d, 0xde00, 0x21]).unwrap(), 2);
+    }
+
+    #[test]
+    fn utf16_to_string_rejects_lone_surrogates() {
+        for units in &[&[0xd83d][..], &[0xde00, 0x61], &[0x61, 0xd83d, 0x62]] {
+            let e = utf16_to_string(units).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+            assert!(utf16_len_from_units(units).is_err());
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Fully decoded resources, owned and free of the reader they came from.
+
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A resource and everything under it, as `ResourceBundleReader::to_value`
+/// decodes it.
+///
+/// With the `serde` feature, values serialize the way a JSON dump of a
+/// bundle would show them: tables as maps, arrays and integer vectors as
+/// sequences, and binaries and aliases as strings, binaries in base64.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i32),
+    IntVector(Vec<i32>),
+    Binary(Vec<u8>),
+    Array(Vec<Value>),
+    /// `(key, value)` pairs, in key order.
+    Table(Vec<(String, Value)>),
+    /// The path of an alias that can't be followed within the bundle, into
+    /// another bundle or to a path that isn't there.
+    Alias(String),
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Value::String(ref s) | Value::Alias(ref s) => serializer.serialize_str(s),
+            Value::Int(n) => serializer.serialize_i32(n),
+            Value::IntVector(ref values) => values.serialize(serializer),
+            Value::Binary(ref bytes) => serializer.serialize_str(&base64(bytes)),
+            Value::Array(ref items) => items.serialize(serializer),
+            Value::Table(ref entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Encodes `bytes` in standard, padded base64.
+#[cfg(feature = "serde")]
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &b)| {
+            group | (u32::from(b) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[((group >> (18 - 6 * i)) & 0x3f) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    extern crate serde_json;
+
+    use value::{base64, Value};
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(&[0xff, 0xfe, 0x00, 0x01]), "//4AAQ==");
+    }
+
+    #[test]
+    fn values_serialize_to_json() {
+        let value = Value::Table(vec![
+            ("a".to_string(), Value::Array(vec![Value::Int(5), Value::String("hi".into())])),
+            ("b".to_string(), Value::IntVector(vec![1, -2])),
+            ("c".to_string(), Value::Binary(vec![0xff, 0x00])),
+            ("d".to_string(), Value::Alias("other/x".into())),
+        ]);
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"a":[5,"hi"],"b":[1,-2],"c":"/wA=","d":"other/x"}"#
+        );
+    }
+}
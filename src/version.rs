@@ -36,11 +36,94 @@ pub enum Version {
     Unicode10_0(PiecewiseVersion),
 }
 
-// TODO make this more than a stub
+/// Builds a `Version` variant from the version tuple it carries.
+type VersionConstructor = fn(PiecewiseVersion) -> Version;
+
+/// Unicode versions, keyed on the first three bytes of the version tuple
+/// ICU embeds in its data headers.
+const UNICODE_VERSIONS: [((u8, u8, u8), VersionConstructor); 28] = [
+    ((1, 0, 0), Version::Unicode1_0),
+    ((1, 0, 1), Version::Unicode1_0_1),
+    ((1, 1, 0), Version::Unicode1_1_0),
+    ((1, 1, 5), Version::Unicode1_1_5),
+    ((2, 0, 0), Version::Unicode2_0),
+    ((2, 1, 2), Version::Unicode2_1_2),
+    ((2, 1, 5), Version::Unicode2_1_5),
+    ((2, 1, 8), Version::Unicode2_1_8),
+    ((2, 1, 9), Version::Unicode2_1_9),
+    ((3, 0, 0), Version::Unicode3_0),
+    ((3, 0, 1), Version::Unicode3_0_1),
+    ((3, 1, 0), Version::Unicode3_1_0),
+    ((3, 1, 1), Version::Unicode3_1_1),
+    ((3, 2, 0), Version::Unicode3_2),
+    ((4, 0, 0), Version::Unicode4_0),
+    ((4, 0, 1), Version::Unicode4_0_1),
+    ((4, 1, 0), Version::Unicode4_1),
+    ((5, 0, 0), Version::Unicode5_0),
+    ((5, 1, 0), Version::Unicode5_1),
+    ((5, 2, 0), Version::Unicode5_2),
+    ((6, 0, 0), Version::Unicode6_0),
+    ((6, 1, 0), Version::Unicode6_1),
+    ((6, 2, 0), Version::Unicode6_2),
+    ((6, 3, 0), Version::Unicode6_3),
+    ((7, 0, 0), Version::Unicode7_0),
+    ((8, 0, 0), Version::Unicode8_0),
+    ((9, 0, 0), Version::Unicode9_0),
+    ((10, 0, 0), Version::Unicode10_0),
+];
+
 impl TryFrom<PiecewiseVersion> for Version {
     type Error = io::Error;
 
     fn try_from(version: PiecewiseVersion) -> Result<Self, Self::Error> {
-        Ok(Version::Unicode10_0(version))
+        let key = (version.0, version.1, version.2);
+        UNICODE_VERSIONS
+            .iter()
+            .find(|&&(v, _)| v == key)
+            .map(|&(_, variant)| variant(version))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "unknown Unicode version {}.{}.{}.{}",
+                        version.0, version.1, version.2, version.3
+                    ),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::io::ErrorKind;
+    use version::Version;
+
+    #[test]
+    fn maps_known_unicode_versions() {
+        assert_eq!(
+            Version::try_from((5, 2, 0, 0)).unwrap(),
+            Version::Unicode5_2((5, 2, 0, 0))
+        );
+        assert_eq!(
+            Version::try_from((7, 0, 0, 0)).unwrap(),
+            Version::Unicode7_0((7, 0, 0, 0))
+        );
+        assert_eq!(
+            Version::try_from((10, 0, 0, 0)).unwrap(),
+            Version::Unicode10_0((10, 0, 0, 0))
+        );
+        assert_eq!(
+            Version::try_from((1, 1, 5, 0)).unwrap(),
+            Version::Unicode1_1_5((1, 1, 5, 0))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_unicode_versions() {
+        for &v in &[(11, 0, 0, 0), (5, 3, 0, 0), (1, 4, 0, 0)] {
+            let err = Version::try_from(v).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
     }
 }
@@ -0,0 +1,54 @@
+//! Walking resources depth-first without decoding them into owned trees.
+
+use std::io;
+
+/// Receives the resources of a bundle as `ResourceBundleReader::accept`
+/// walks them, depth-first and in key order.
+///
+/// `key` is the resource's key in its table, or `None` for array items and
+/// the resource the walk starts at. Each method does nothing by default, so
+/// a visitor only implements the ones it cares about; returning an error
+/// stops the walk and makes `accept` return it.
+pub trait Visitor {
+    fn visit_string(&mut self, _key: Option<&str>, _value: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn visit_int(&mut self, _key: Option<&str>, _value: i32) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn visit_int_vector(&mut self, _key: Option<&str>, _values: &[i32]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn visit_binary(&mut self, _key: Option<&str>, _bytes: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// An alias that can't be followed within the bundle, into another
+    /// bundle or to a path that isn't there.
+    fn visit_alias(&mut self, _key: Option<&str>, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The start of a table of `len` entries, which are visited before
+    /// `visit_table_end`.
+    fn visit_table_start(&mut self, _key: Option<&str>, _len: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn visit_table_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The start of an array of `len` items, which are visited before
+    /// `visit_array_end`.
+    fn visit_array_start(&mut self, _key: Option<&str>, _len: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn visit_array_end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
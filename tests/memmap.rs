@@ -0,0 +1,47 @@
+#![cfg(feature = "memmap")]
+
+extern crate icu4rs;
+
+use icu4rs::{write_header, DataFormat, Order, Resource, ResourceBundleReader};
+use std::env;
+use std::fs;
+
+/// A big-endian bundle whose root table is `{ a: [5, "hi"], b: -1 }`.
+fn small_bundle() -> Vec<u8> {
+    let words: [u32; 17] = [
+        0x2000_000d, // root: table at 13
+        5, 7, 17, 17, 2,
+        0x6100_6200, // keys: "a" at byte 24, "b" at byte 26
+        2, 0x0068_0069, 0, // 7: "hi"
+        2, 0x7000_0005, 7, // 10: [5, "hi"]
+        0x0002_0018, 0x001a_0000, 0x8000_000a, 0x7fff_ffff, // 13: { a: 10, b: -1 }
+    ];
+    let mut bytes = Vec::new();
+    let format = DataFormat::ResourceBundle;
+    write_header(&mut bytes, format, [3, 0, 0, 0], (1, 4, 0, 0), Order::BigEndian).unwrap();
+    for w in &words {
+        bytes.extend_from_slice(&[(w >> 24) as u8, (w >> 16) as u8, (w >> 8) as u8, *w as u8]);
+    }
+    bytes
+}
+
+#[test]
+fn from_path_reads_a_mapped_bundle() {
+    let path = env::temp_dir().join(format!("icu4rs-memmap-{}.res", std::process::id()));
+    fs::write(&path, small_bundle()).unwrap();
+    {
+        let mut r = ResourceBundleReader::from_path(&path, DataFormat::ResourceBundle)
+            .expect("Failed to map bundle");
+        let a = r.get_by_path("a/1").unwrap().unwrap();
+        assert_eq!(r.resource(a).unwrap(), Resource::String("hi".to_string()));
+        let b = r.get_by_path("b").unwrap().unwrap();
+        assert_eq!(r.int(b).unwrap(), -1);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn from_path_reports_missing_files() {
+    let path = env::temp_dir().join("icu4rs-memmap-does-not-exist.res");
+    assert!(ResourceBundleReader::from_path(&path, DataFormat::ResourceBundle).is_err());
+}